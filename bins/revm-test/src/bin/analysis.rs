@@ -3,6 +3,7 @@ use std::{str::FromStr, time::Instant};
 use bytes::Bytes;
 use primitive_types::H160;
 use revm::{db::BenchmarkDB, Bytecode, TransactTo};
+use sha3::Digest;
 
 extern crate alloc;
 
@@ -22,7 +23,8 @@ fn main() {
 
     let bytecode_raw = Bytecode::new_raw(contract_data.clone());
     let bytecode_checked = Bytecode::new_raw(contract_data.clone()).to_checked();
-    let bytecode_analysed = Bytecode::new_raw(contract_data).to_analysed::<revm::LondonSpec>();
+    let bytecode_analysed =
+        Bytecode::new_raw(contract_data.clone()).to_analysed::<revm::LondonSpec>();
 
     evm.database(BenchmarkDB::new_bytecode(bytecode_raw));
 
@@ -52,4 +54,40 @@ fn main() {
         let (_, _) = evm.transact();
     }
     println!("Analysed elapsed time: {:?}", timer.elapsed());
+
+    // cost of the analysis itself.
+    let timer = Instant::now();
+    for _ in 0..30000 {
+        let _ = Bytecode::new_raw(contract_data.clone()).to_analysed::<revm::LondonSpec>();
+    }
+    println!("to_analysed elapsed time: {:?}", timer.elapsed());
+
+    // analysis reusing jumptable of the same code.
+    let mut cache = hashbrown::HashMap::new();
+    let timer = Instant::now();
+    for _ in 0..30000 {
+        let _ = Bytecode::new_raw(contract_data.clone())
+            .to_analysed_cached::<revm::LondonSpec>(&mut cache);
+    }
+    println!("to_analysed_cached elapsed time: {:?}", timer.elapsed());
+
+    let timer = Instant::now();
+    for _ in 0..30000 {
+        let _ = Bytecode::new_raw(contract_data.clone()).to_jumpdest_checked();
+    }
+    println!("to_jumpdest_checked elapsed time: {:?}", timer.elapsed());
+
+    // hash is cached on creation, compare with hashing the code every time.
+    let bytecode = Bytecode::new_raw(contract_data.clone());
+    let timer = Instant::now();
+    for _ in 0..30000 {
+        let _ = sha3::Keccak256::digest(bytecode.original_bytes());
+    }
+    println!("keccak256 elapsed time: {:?}", timer.elapsed());
+
+    let timer = Instant::now();
+    for _ in 0..30000 {
+        let _ = std::hint::black_box(&bytecode).hash();
+    }
+    println!("hash elapsed time: {:?}", timer.elapsed());
 }
@@ -0,0 +1,73 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use revm::{opcode, AnalysisItem, Bytecode, BytecodeBuilder};
+
+/// Allocator that tracks the peak of allocated bytes.
+struct PeakAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PeakAlloc = PeakAlloc;
+
+/// Peak of bytes allocated while running `f`, on top of what was allocated before.
+fn peak_memory<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    let out = f();
+    (out, PEAK.load(Ordering::SeqCst) - before)
+}
+
+fn main() {
+    // 24KB contract, the EIP-170 size limit.
+    let mut builder = BytecodeBuilder::new();
+    // 7 bytes per iteration.
+    for _ in 0..(0x6000 - 1) / 7 {
+        builder = builder
+            .op(opcode::JUMPDEST)
+            .push(1)
+            .push(2)
+            .op(opcode::ADD)
+            .op(opcode::POP);
+    }
+    let code = Bytecode::new_raw(builder.op(opcode::STOP).into_bytes().into());
+    println!("code size: {} bytes", code.len());
+
+    let (jumpdests, peak) = peak_memory(|| {
+        let analysed = code.clone().to_analysed::<revm::LondonSpec>();
+        (0..analysed.len())
+            .filter(|position| analysed.is_valid_jump(*position) == Some(true))
+            .count()
+    });
+    println!("to_analysed: {} jumpdests, peak {} bytes", jumpdests, peak);
+
+    let (jumpdests, peak) = peak_memory(|| {
+        code.analysis_iter::<revm::LondonSpec>()
+            .filter(|item| matches!(item, AnalysisItem::JumpDest(_)))
+            .count()
+    });
+    println!(
+        "analysis_iter: {} jumpdests, peak {} bytes",
+        jumpdests, peak
+    );
+
+    // keep the code alive until the end, so it is not counted in either run.
+    drop::<Bytecode>(code);
+}
@@ -0,0 +1,45 @@
+use std::{str::FromStr, time::Instant};
+
+use bytes::Bytes;
+use primitive_types::{H160, U256};
+use revm::{AccountInfo, Bytecode, InMemoryDB, NoOpInspector, TransactTo};
+
+extern crate alloc;
+
+fn main() {
+    // SSTORE(0, SLOAD(0) + 1), SSTORE(1, SLOAD(1) + 1), SSTORE(2, SLOAD(2) + 1)
+    let contract_data: Bytes =
+        hex::decode("6001600054016000556001600154016001556001600254016002550000")
+            .unwrap()
+            .into();
+    let contract = H160::from_str("0x0000000000000000000000000000000000000042").unwrap();
+
+    let mut db = InMemoryDB::default();
+    db.insert_account_info(
+        contract,
+        AccountInfo::new(U256::zero(), 0, Bytecode::new_raw(contract_data)),
+    );
+
+    let mut evm = revm::new();
+    evm.database(db);
+    evm.env.tx.caller = H160::from_str("0x1000000000000000000000000000000000000000").unwrap();
+    evm.env.tx.transact_to = TransactTo::Call(contract);
+    evm.env.cfg.perf_all_precompiles_have_balance = true;
+
+    // committing path, every call runs on a copy of the base state.
+    let base = evm.db.clone().unwrap();
+    let timer = Instant::now();
+    for _ in 0..30000 {
+        evm.database(base.clone());
+        evm.inspect_commit(NoOpInspector());
+    }
+    println!("Commit elapsed time: {:?}", timer.elapsed());
+
+    evm.database(base);
+    let mut cache = evm.trace_cache();
+    let timer = Instant::now();
+    for _ in 0..30000 {
+        evm.inspect_trace(&mut cache, NoOpInspector());
+    }
+    println!("Trace elapsed time: {:?}", timer.elapsed());
+}
@@ -151,7 +151,7 @@ impl<DB: Database> Inspector<DB> for Controller {
                             OPCODE_JUMPMAP[opcode as usize].unwrap_or("Invalid"),
                             gas_spend,
                             gas_remaining,
-                            interp.stack(),
+                            interp.stack,
                         );
                     }
                     CtrlPrint::Opcode => {
@@ -169,10 +169,10 @@ impl<DB: Database> Inspector<DB> for Controller {
                         )
                     }
                     CtrlPrint::Stack => {
-                        println!("PC:{} stack:{}", interp.program_counter(), interp.stack())
+                        println!("PC:{} stack:{}", interp.program_counter(), interp.stack)
                     }
                     CtrlPrint::Memory => {
-                        println!("memory:{}", hex::encode(&interp.memory.data()))
+                        println!("memory:{}", hex::encode(interp.memory()))
                     }
                 },
                 Ctrl::Continue => {
@@ -195,7 +195,7 @@ impl<DB: Database> Inspector<DB> for Controller {
                     println!("pop:{:?}", interp.stack.pop());
                 }
                 Ctrl::StackPush(value) => match interp.stack.push(value) {
-                    Ok(()) => println!("stack:{}", interp.stack()),
+                    Ok(()) => println!("stack:{}", interp.stack),
                     Err(e) => println!("push error:{:?}", e),
                 },
                 Ctrl::None => break,
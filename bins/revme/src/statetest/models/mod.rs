@@ -66,6 +66,7 @@ pub struct Env {
     #[serde(deserialize_with = "deserialize_str_as_u256")]
     pub current_timestamp: U256,
     pub current_base_fee: Option<U256>,
+    pub current_random: Option<H256>,
     pub previous_hash: H256,
 }
 
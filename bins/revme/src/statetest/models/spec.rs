@@ -21,11 +21,13 @@ pub enum SpecName {
     London,
     BerlinToLondonAt5,
     Merge,
+    Shanghai,
 }
 
 impl SpecName {
     pub fn to_spec_id(&self) -> SpecId {
         match self {
+            Self::Shanghai => SpecId::SHANGHAI,
             Self::Merge => SpecId::MERGE,
             Self::London => SpecId::LONDON,
             Self::Berlin => SpecId::BERLIN,
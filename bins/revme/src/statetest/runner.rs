@@ -143,6 +143,7 @@ pub fn execute_test_suit(path: &Path, elapsed: &Arc<Mutex<Duration>>) -> Result<
         env.block.gas_limit = unit.env.current_gas_limit;
         env.block.basefee = unit.env.current_base_fee.unwrap_or_default();
         env.block.difficulty = unit.env.current_difficulty;
+        env.block.prevrandao = unit.env.current_random;
 
         //tx env
         env.tx.caller = *map_caller_keys
@@ -230,9 +231,12 @@ pub fn execute_test_suit(path: &Path, elapsed: &Arc<Mutex<Duration>>) -> Result<
                 let state_root = state_merkle_trie_root(
                     db.accounts
                         .iter()
-                        .filter(|(_address, acc)| {
+                        .filter(|(address, acc)| {
+                            // empty accounts that were only read do not exist, unless they are
+                            // part of the pre state.
                             !(acc.info.is_empty())
-                                || matches!(acc.account_state, AccountState::None)
+                                || (matches!(acc.account_state, AccountState::None)
+                                    && database.accounts.contains_key(address))
                         })
                         .map(|(k, v)| (*k, v.clone())),
                 );
@@ -0,0 +1,216 @@
+use crate::{
+    db::DbQuery, evm_impl::EVMData, CallInputs, CreateInputs, Database, Gas, Inspector,
+    Interpreter, Return,
+};
+use alloc::vec::Vec;
+use bytes::Bytes;
+use hashbrown::HashSet;
+use primitive_types::{H160, U256};
+
+/// Size of a code chunk in bytes, as in EIP-6800 (Verkle trees).
+pub const CODE_CHUNK_SIZE: usize = 31;
+
+/// State item accessed during execution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WitnessEntry {
+    /// Account header: balance, nonce and code hash.
+    Account(H160),
+    /// Storage slot of an account.
+    Storage(H160, U256),
+    /// Chunk of [`CODE_CHUNK_SIZE`] bytes of account code, by index.
+    CodeChunk(H160, u32),
+}
+
+/// Ordered list of state items that execution accessed, from which a witness for stateless
+/// execution can be assembled. Every item is listed once, see [`AccessWitness::entries`].
+///
+/// Accounts and storage are taken from queries recorded by [`crate::db::RecorderDB`], see
+/// [`AccessWitness::record_queries`]. Used as an [`Inspector`] it records code chunks of
+/// executed instructions.
+#[derive(Clone, Debug, Default)]
+pub struct AccessWitness {
+    entries: Vec<WitnessEntry>,
+    seen: HashSet<WitnessEntry>,
+    /// Journal depth of calls together with address of code they execute, `None` for init
+    /// code that is not stored in any account.
+    frames: Vec<(u64, Option<H160>)>,
+}
+
+impl AccessWitness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accessed items in the order they were first recorded. Code chunks are recorded in
+    /// execution order while the transaction runs, so items added by
+    /// [`AccessWitness::record_queries`] after it come after all chunks, in the order they were
+    /// read from the database.
+    pub fn entries(&self) -> &[WitnessEntry] {
+        &self.entries
+    }
+
+    pub fn contains(&self, entry: &WitnessEntry) -> bool {
+        self.seen.contains(entry)
+    }
+
+    /// Add `entry` if it is not already in the witness.
+    pub fn record(&mut self, entry: WitnessEntry) {
+        if self.seen.insert(entry) {
+            self.entries.push(entry);
+        }
+    }
+
+    pub fn record_account(&mut self, address: H160) {
+        self.record(WitnessEntry::Account(address))
+    }
+
+    pub fn record_storage(&mut self, address: H160, index: U256) {
+        self.record(WitnessEntry::Storage(address, index))
+    }
+
+    /// Record chunk of code of `address` that contains byte at `pc`.
+    pub fn record_code_chunk(&mut self, address: H160, pc: usize) {
+        self.record(WitnessEntry::CodeChunk(
+            address,
+            (pc / CODE_CHUNK_SIZE) as u32,
+        ))
+    }
+
+    /// Record accounts and storage slots read from the database. Code is part of the account,
+    /// and block hashes are not state, so they are skipped.
+    pub fn record_queries<'a>(&mut self, queries: impl IntoIterator<Item = &'a DbQuery>) {
+        for query in queries {
+            match query {
+                DbQuery::Basic { address, .. } => self.record_account(*address),
+                DbQuery::Storage { address, index, .. } => self.record_storage(*address, *index),
+                DbQuery::CodeByHash { .. } | DbQuery::BlockHash { .. } => (),
+            }
+        }
+    }
+
+    /// Frame that is executing at `depth`. Frames that ended are dropped, this does not depend
+    /// on `call_end`/`create_end` being called.
+    fn frame_at(&mut self, depth: u64) -> Option<H160> {
+        while self
+            .frames
+            .last()
+            .is_some_and(|(frame_depth, _)| *frame_depth >= depth)
+        {
+            self.frames.pop();
+        }
+        match self.frames.last() {
+            Some((frame_depth, address)) if *frame_depth + 1 == depth => *address,
+            _ => None,
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for AccessWitness {
+    fn step(
+        &mut self,
+        interp: &mut Interpreter,
+        data: &mut EVMData<'_, DB>,
+        _is_static: bool,
+    ) -> Return {
+        if let Some(address) = self.frame_at(data.journaled_state.depth()) {
+            self.record_code_chunk(address, interp.program_counter());
+        }
+        Return::Continue
+    }
+
+    fn call(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+        _is_static: bool,
+    ) -> (Return, Gas, Bytes) {
+        let depth = data.journaled_state.depth();
+        self.frame_at(depth + 1);
+        self.frames.push((depth, Some(inputs.context.code_address)));
+        (Return::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn create(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        _inputs: &mut CreateInputs,
+    ) -> (Return, Option<H160>, Gas, Bytes) {
+        let depth = data.journaled_state.depth();
+        self.frame_at(depth + 1);
+        self.frames.push((depth, None));
+        (Return::Continue, None, Gas::new(0), Bytes::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::RecorderDB, opcode, AccountInfo, BytecodeBuilder, InMemoryDB, TransactTo};
+
+    #[test]
+    fn witness_contains_accessed_state() {
+        let caller = H160::from_low_u64_be(0x1000);
+        let contract = H160::from_low_u64_be(0x42);
+        let callee = H160::from_low_u64_be(0x43);
+        let other = H160::from_low_u64_be(0x44);
+        let coinbase = H160::from_low_u64_be(0xc0);
+
+        // SLOAD slot 1, BALANCE of other, CALL callee and pad the code to span three chunks.
+        let mut code = BytecodeBuilder::new()
+            .push(1)
+            .op(opcode::SLOAD)
+            .push(U256::from_big_endian(other.as_bytes()))
+            .op(opcode::BALANCE)
+            .push(0)
+            .op(opcode::DUP1)
+            .op(opcode::DUP1)
+            .op(opcode::DUP1)
+            .op(opcode::DUP1)
+            .push(U256::from_big_endian(callee.as_bytes()))
+            .op(opcode::GAS)
+            .op(opcode::CALL);
+        for _ in 0..60 {
+            code = code.op(opcode::JUMPDEST);
+        }
+        let code = code.op(opcode::STOP).build();
+        // SLOAD slot 2
+        let callee_code = BytecodeBuilder::new()
+            .push(2)
+            .op(opcode::SLOAD)
+            .op(opcode::STOP)
+            .build();
+
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(contract, AccountInfo::from_code(code));
+        db.insert_account_info(callee, AccountInfo::from_code(callee_code));
+        let mut evm = crate::new();
+        evm.database(RecorderDB::new(db));
+        evm.env.cfg.perf_all_precompiles_have_balance = true;
+        evm.env.block.coinbase = coinbase;
+        evm.env.tx.caller = caller;
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 1_000_000;
+
+        let mut witness = AccessWitness::new();
+        let (result, _) = evm.inspect(&mut witness);
+        assert_eq!(result.exit_reason, Return::Stop);
+        witness.record_queries(&evm.db().unwrap().journal);
+
+        // chunks in execution order, CALL is in the first chunk, followed by database reads.
+        let expected = [
+            WitnessEntry::CodeChunk(contract, 0),
+            WitnessEntry::CodeChunk(callee, 0),
+            WitnessEntry::CodeChunk(contract, 1),
+            WitnessEntry::CodeChunk(contract, 2),
+            WitnessEntry::Account(coinbase),
+            WitnessEntry::Account(caller),
+            WitnessEntry::Account(contract),
+            WitnessEntry::Storage(contract, U256::one()),
+            WitnessEntry::Account(other),
+            WitnessEntry::Account(callee),
+            WitnessEntry::Storage(callee, U256::from(2)),
+        ];
+        assert_eq!(witness.entries(), &expected[..]);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    db::{Database, DatabaseCommit},
+    Account, Env, ExecutionResult, SpecId,
+};
+use hashbrown::HashMap as Map;
+use primitive_types::U256;
+
+/// Gas used by a transaction and the price it paid for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxFee {
+    pub gas_used: u64,
+    pub effective_gas_price: U256,
+}
+
+impl TxFee {
+    /// Fee of transaction executed in `env` with `result`.
+    pub fn new(env: &Env, result: &ExecutionResult) -> Self {
+        Self {
+            gas_used: result.gas_used,
+            effective_gas_price: env.effective_gas_price(),
+        }
+    }
+}
+
+/// Amounts computed by [`finalize_block`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockRewards {
+    /// Base fee of all transactions, burned since London (EIP-1559).
+    pub burned: U256,
+    /// Priority fees and block reward credited to the coinbase.
+    pub rewarded: U256,
+}
+
+/// Credit the coinbase of `env.block` with fees of the block transactions and `block_reward`.
+///
+/// Since London the base fee part of every fee is burned and only the priority fee goes to the
+/// coinbase. Meant to be used with [`crate::CfgEnv::defer_coinbase_reward`], otherwise fees
+/// were already credited by every transaction.
+pub fn finalize_block<DB: Database + DatabaseCommit>(
+    db: &mut DB,
+    env: &Env,
+    fees: &[TxFee],
+    block_reward: U256,
+) -> BlockRewards {
+    let basefee = if SpecId::enabled(env.cfg.spec_id, SpecId::LONDON) {
+        env.block.basefee
+    } else {
+        U256::zero()
+    };
+
+    let mut rewards = BlockRewards {
+        burned: U256::zero(),
+        rewarded: block_reward,
+    };
+    for fee in fees {
+        let gas_used = U256::from(fee.gas_used);
+        let priority_fee = fee.effective_gas_price.saturating_sub(basefee);
+        rewards.burned = rewards
+            .burned
+            .saturating_add(fee.effective_gas_price.min(basefee) * gas_used);
+        rewards.rewarded = rewards.rewarded.saturating_add(priority_fee * gas_used);
+    }
+
+    if !rewards.rewarded.is_zero() {
+        let coinbase = env.block.coinbase;
+        let mut account: Account = db.basic(coinbase).into();
+        account.info.balance = account.info.balance.saturating_add(rewards.rewarded);
+        account.is_touched = true;
+        db.commit(Map::from([(coinbase, account)]));
+    }
+    rewards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccountInfo, InMemoryDB, Return, TransactTo};
+    use primitive_types::H160;
+
+    #[test]
+    fn coinbase_gets_priority_fees() {
+        let coinbase = H160::from_low_u64_be(0xc0);
+        let caller = H160::from_low_u64_be(0x1000);
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(caller, AccountInfo::from_balance(U256::from(10_000_000)));
+
+        let mut evm = crate::new();
+        evm.database(db);
+        evm.env.cfg.defer_coinbase_reward = true;
+        evm.env.block.coinbase = coinbase;
+        evm.env.block.basefee = U256::from(10);
+        evm.env.tx.caller = caller;
+        evm.env.tx.gas_limit = 100_000;
+        evm.env.tx.gas_price = U256::from(15);
+        evm.env.tx.gas_priority_fee = Some(U256::from(2));
+
+        let mut fees = Vec::new();
+        for to in [0x100, 0x101] {
+            evm.env.tx.transact_to = TransactTo::Call(H160::from_low_u64_be(to));
+            let result = evm.transact_commit();
+            assert_eq!(result.exit_reason, Return::Stop);
+            fees.push(TxFee::new(&evm.env, &result));
+        }
+        assert_eq!(fees[0].effective_gas_price, U256::from(12));
+        let mut env = evm.env.clone();
+        let db = evm.db().unwrap();
+        assert_eq!(db.accounts[&coinbase].info.balance, U256::zero());
+
+        let rewards = finalize_block(db, &env, &fees, U256::zero());
+        assert_eq!(rewards.burned, U256::from(10 * 2 * 21_000));
+        assert_eq!(rewards.rewarded, U256::from(2 * 2 * 21_000));
+        assert_eq!(db.accounts[&coinbase].info.balance, rewards.rewarded);
+
+        // before London whole fee is rewarded.
+        env.cfg.spec_id = SpecId::BERLIN;
+        let rewards = finalize_block(db, &env, &fees[..1], U256::from(1));
+        assert_eq!(rewards.burned, U256::zero());
+        assert_eq!(rewards.rewarded, U256::from(12 * 21_000 + 1));
+    }
+}
@@ -1,11 +1,41 @@
+mod buffered_commit_db;
+mod cache_serialization;
 mod in_memory_db;
+mod latency_db;
+mod layered_db;
+mod pending_overlay_db;
+mod recorder_db;
+mod seeded_db;
+mod state_diff;
+#[cfg(feature = "trie")]
+mod trie_db;
+mod ttl_cache_db;
 
 #[cfg(feature = "web3db")]
 pub mod web3db;
 #[cfg(feature = "web3db")]
 pub use web3db::Web3DB;
 
-pub use in_memory_db::{AccountState, BenchmarkDB, CacheDB, DbAccount, EmptyDB, InMemoryDB};
+#[cfg(feature = "diskdb")]
+mod disk_db;
+#[cfg(feature = "diskdb")]
+pub use disk_db::DiskDB;
+
+pub use buffered_commit_db::BufferedCommitDB;
+pub use cache_serialization::LoadCacheError;
+pub use in_memory_db::{
+    AccountOverride, AccountState, BenchmarkDB, BlockHashStrategy, CacheDB, CacheStats,
+    CommitError, DbAccount, EmptyDB, InMemoryDB, Inconsistency, StateOverride,
+};
+pub use latency_db::LatencyDB;
+pub use layered_db::LayeredDB;
+pub use pending_overlay_db::PendingOverlayDB;
+pub use recorder_db::{DbQuery, RecorderDB, ReplayDB};
+pub use seeded_db::SeededDB;
+pub use state_diff::{AccountDiff, StateDiff};
+#[cfg(feature = "trie")]
+pub use trie_db::{TrieDB, EMPTY_ROOT};
+pub use ttl_cache_db::TtlCacheDB;
 
 use crate::{interpreter::bytecode::Bytecode, Account};
 use hashbrown::HashMap as Map;
@@ -16,10 +46,16 @@ use auto_impl::auto_impl;
 
 #[auto_impl(& mut, Box)]
 pub trait Database {
-    /// Get basic account information.
+    /// Get basic account information. If `code` of the returned info is set, it is used
+    /// directly and `code_by_hash` is not called for this account.
     fn basic(&mut self, address: H160) -> AccountInfo;
     /// Get account code by its hash
     fn code_by_hash(&mut self, code_hash: H256) -> Bytecode;
+    /// Get length of account code by its hash, used by EXTCODESIZE. Backends that store
+    /// code length separately can implement it without loading the code.
+    fn code_size(&mut self, code_hash: H256) -> usize {
+        self.code_by_hash(code_hash).len()
+    }
     /// Get storage value of address at index.
     fn storage(&mut self, address: H160, index: U256) -> U256;
 
@@ -36,15 +72,64 @@ pub trait DatabaseCommit {
 pub trait DatabaseRef {
     /// Whether account at address exists.
     //fn exists(&self, address: H160) -> Option<AccountInfo>;
-    /// Get basic account information.
+    /// Get basic account information. See [`Database::basic`].
     fn basic(&self, address: H160) -> AccountInfo;
     /// Get account code by its hash
     fn code_by_hash(&self, code_hash: H256) -> Bytecode;
+    /// Get length of account code by its hash. See [`Database::code_size`].
+    fn code_size(&self, code_hash: H256) -> usize {
+        self.code_by_hash(code_hash).len()
+    }
     /// Get storage value of address at index.
     fn storage(&self, address: H160, index: U256) -> U256;
 
     // History related
     fn block_hash(&self, number: U256) -> H256;
+
+    /// Get basic account information if this database has it, `None` means that lookup
+    /// should fall through to the next database. Used by [`LayeredDB`].
+    fn try_basic(&self, address: H160) -> Option<AccountInfo> {
+        Some(self.basic(address))
+    }
+    /// Get account code by its hash if this database has it. See [`DatabaseRef::try_basic`].
+    fn try_code_by_hash(&self, code_hash: H256) -> Option<Bytecode> {
+        Some(self.code_by_hash(code_hash))
+    }
+    /// Get storage value of address at index if this database has it.
+    /// See [`DatabaseRef::try_basic`].
+    fn try_storage(&self, address: H160, index: U256) -> Option<U256> {
+        Some(self.storage(address, index))
+    }
+    /// Get block hash if this database has it. See [`DatabaseRef::try_basic`].
+    fn try_block_hash(&self, number: U256) -> Option<H256> {
+        Some(self.block_hash(number))
+    }
+
+    /// Get basic account information at the end of block `number`, for archive queries like
+    /// `eth_call` at a past block. Databases without history return
+    /// [`HistoryError::Unsupported`].
+    fn basic_at(&self, _address: H160, _number: U256) -> Result<AccountInfo, HistoryError> {
+        Err(HistoryError::Unsupported)
+    }
+    /// Get storage value of address at index at the end of block `number`.
+    /// See [`DatabaseRef::basic_at`].
+    fn storage_at(
+        &self,
+        _address: H160,
+        _index: U256,
+        _number: U256,
+    ) -> Result<U256, HistoryError> {
+        Err(HistoryError::Unsupported)
+    }
+}
+
+/// Error returned by [`DatabaseRef::basic_at`] and [`DatabaseRef::storage_at`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryError {
+    /// Database has only the latest state.
+    Unsupported,
+    /// State at the block is not available, e.g. it was pruned or the block does not exist.
+    Unavailable(U256),
 }
 
 pub struct RefDBWrapper<'a> {
@@ -70,6 +155,10 @@ impl<'a> Database for RefDBWrapper<'a> {
     fn code_by_hash(&mut self, code_hash: H256) -> Bytecode {
         self.db.code_by_hash(code_hash)
     }
+    /// Get length of account code by its hash
+    fn code_size(&mut self, code_hash: H256) -> usize {
+        self.db.code_size(code_hash)
+    }
     /// Get storage value of address at index.
     fn storage(&mut self, address: H160, index: U256) -> U256 {
         self.db.storage(address, index)
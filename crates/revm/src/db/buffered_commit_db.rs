@@ -0,0 +1,207 @@
+use super::{Database, DatabaseCommit};
+use crate::{interpreter::bytecode::Bytecode, Account, AccountInfo, StorageSlot};
+use hashbrown::{hash_map::Entry, HashMap as Map};
+use primitive_types::{H160, H256, U256};
+
+/// Database wrapper that merges committed change sets and passes them to the wrapped database
+/// at once on [`BufferedCommitDB::flush`] or drop.
+///
+/// Later changes of an account override earlier ones, a destroyed or storage cleared account
+/// drops all storage buffered before. Reads see buffered changes.
+#[derive(Debug)]
+pub struct BufferedCommitDB<DB: DatabaseCommit> {
+    pub db: DB,
+    pending: Map<H160, Account>,
+}
+
+impl<DB: DatabaseCommit> BufferedCommitDB<DB> {
+    pub fn new(db: DB) -> Self {
+        Self {
+            db,
+            pending: Map::new(),
+        }
+    }
+
+    /// Changes that are not flushed yet.
+    pub fn pending(&self) -> &Map<H160, Account> {
+        &self.pending
+    }
+
+    /// Commit buffered changes to the wrapped database.
+    pub fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            self.db.commit(core::mem::take(&mut self.pending));
+        }
+    }
+}
+
+impl<DB: DatabaseCommit> Drop for BufferedCommitDB<DB> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<DB: DatabaseCommit> DatabaseCommit for BufferedCommitDB<DB> {
+    fn commit(&mut self, changes: Map<H160, Account>) {
+        // Beneficiary of a buffered selfdestruct is credited by the wrapped database only if it
+        // is not in the change set, flush before merging would hide that transfer.
+        let hides_transfer = changes.keys().any(|address| {
+            self.pending.get(address).is_some_and(|account| {
+                account.is_destroyed
+                    && matches!(account.selfdestruct_transfer, Some((beneficiary, _))
+                        if beneficiary != *address && !self.pending.contains_key(&beneficiary))
+            })
+        });
+        if hides_transfer {
+            self.flush();
+        }
+
+        // Credit beneficiaries that are buffered but not part of this change set.
+        for (address, account) in &changes {
+            if let (true, Some((beneficiary, balance))) =
+                (account.is_destroyed, account.selfdestruct_transfer)
+            {
+                if beneficiary != *address && !changes.contains_key(&beneficiary) {
+                    if let Some(buffered) = self.pending.get_mut(&beneficiary) {
+                        buffered.info.balance = buffered.info.balance.saturating_add(balance);
+                        buffered.is_touched = true;
+                    }
+                }
+            }
+        }
+
+        for (address, account) in changes {
+            match self.pending.entry(address) {
+                Entry::Vacant(entry) => {
+                    entry.insert(account);
+                }
+                Entry::Occupied(mut entry) => {
+                    if account.is_destroyed || account.storage_cleared {
+                        entry.insert(account);
+                        continue;
+                    }
+                    let buffered = entry.get_mut();
+                    buffered.storage_cleared |= buffered.is_destroyed;
+                    buffered.is_destroyed = false;
+                    buffered.selfdestruct_transfer = None;
+                    buffered.is_touched |= account.is_touched;
+                    buffered.info = account.info;
+                    for (index, slot) in account.storage {
+                        let original = buffered
+                            .storage
+                            .get(&index)
+                            .map_or(slot.original_value(), |buffered| buffered.original_value());
+                        buffered.storage.insert(
+                            index,
+                            StorageSlot::new_changed(original, slot.present_value()),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<DB: Database + DatabaseCommit> Database for BufferedCommitDB<DB> {
+    fn basic(&mut self, address: H160) -> AccountInfo {
+        match self.pending.get(&address) {
+            Some(account) if account.is_destroyed => AccountInfo::default(),
+            Some(account) => account.info.clone(),
+            None => self.db.basic(address),
+        }
+    }
+
+    fn code_by_hash(&mut self, code_hash: H256) -> Bytecode {
+        self.pending
+            .values()
+            .find_map(|account| {
+                account
+                    .info
+                    .code
+                    .clone()
+                    .filter(|_| account.info.code_hash == code_hash)
+            })
+            .unwrap_or_else(|| self.db.code_by_hash(code_hash))
+    }
+
+    fn storage(&mut self, address: H160, index: U256) -> U256 {
+        match self.pending.get(&address) {
+            Some(account) if account.is_destroyed => U256::zero(),
+            Some(account) => match account.storage.get(&index) {
+                Some(slot) => slot.present_value(),
+                None if account.storage_cleared => U256::zero(),
+                None => self.db.storage(address, index),
+            },
+            None => self.db.storage(address, index),
+        }
+    }
+
+    fn block_hash(&mut self, number: U256) -> H256 {
+        self.db.block_hash(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDB;
+
+    fn slot_change(index: U256, original: u64, present: u64) -> Account {
+        let mut account: Account = AccountInfo::from_balance(U256::one()).into();
+        account.is_touched = true;
+        account.storage.insert(
+            index,
+            StorageSlot::new_changed(U256::from(original), U256::from(present)),
+        );
+        account
+    }
+
+    #[test]
+    fn buffered_commits_flush_final_value() {
+        let address = H160::from_low_u64_be(42);
+        let index = U256::one();
+        let mut db = InMemoryDB::default();
+        let mut buffered = BufferedCommitDB::new(&mut db);
+
+        buffered.commit(Map::from([(address, slot_change(index, 0, 1))]));
+        buffered.commit(Map::from([(address, slot_change(index, 1, 2))]));
+        assert_eq!(buffered.storage(address, index), U256::from(2));
+        assert_eq!(buffered.pending().len(), 1);
+        let slot = &buffered.pending()[&address].storage[&index];
+        assert_eq!(
+            (slot.original_value(), slot.present_value()),
+            (U256::zero(), U256::from(2))
+        );
+        // nothing reached the database yet.
+        assert!(!buffered.db.accounts.contains_key(&address));
+
+        buffered.flush();
+        assert!(buffered.pending().is_empty());
+        drop(buffered);
+        assert_eq!(Database::storage(&mut db, address, index), U256::from(2));
+    }
+
+    #[test]
+    fn storage_clear_drops_buffered_slots() {
+        let address = H160::from_low_u64_be(42);
+        let mut db = InMemoryDB::default();
+        {
+            let mut buffered = BufferedCommitDB::new(&mut db);
+            buffered.commit(Map::from([(address, slot_change(U256::one(), 0, 1))]));
+
+            let mut cleared = slot_change(U256::from(2), 0, 3);
+            cleared.storage_cleared = true;
+            buffered.commit(Map::from([(address, cleared)]));
+            assert_eq!(buffered.storage(address, U256::one()), U256::zero());
+            // flushed on drop.
+        }
+        assert_eq!(
+            Database::storage(&mut db, address, U256::one()),
+            U256::zero()
+        );
+        assert_eq!(
+            Database::storage(&mut db, address, U256::from(2)),
+            U256::from(3)
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Compact binary encoding of [`CacheDB`] contents.
+//!
+//! All integers are big endian, counts and lengths are `u32`:
+//!
+//! ```text
+//! version: u8
+//! accounts: count, { address[20] balance[32] nonce[8] code_hash[32] state[1]
+//!                    storage: count, { index[32] value[32] } }
+//! contracts: count, { len, code[len] }
+//! block_hashes: count, { number[32] hash[32] }
+//! ```
+
+use super::{AccountState, CacheDB, DatabaseRef, DbAccount};
+use crate::{interpreter::bytecode::Bytecode, AccountInfo};
+use alloc::vec::Vec;
+use bytes::Bytes;
+use core::convert::TryInto;
+use primitive_types::{H160, H256, U256};
+
+const VERSION: u8 = 1;
+
+/// Error returned by [`CacheDB::load_cache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadCacheError {
+    /// Input ended before all entries were read.
+    UnexpectedEnd,
+    /// Input was written by an unsupported version.
+    UnknownVersion(u8),
+    /// Account state tag is not known.
+    InvalidAccountState(u8),
+}
+
+impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
+    /// Serialize cached accounts, storage, contracts and block hashes. The wrapped database
+    /// and logs are not included. See [`CacheDB::load_cache`].
+    pub fn serialize_cache(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.push(VERSION);
+
+        put_len(&mut out, self.accounts.len());
+        for (address, account) in &self.accounts {
+            out.extend_from_slice(address.as_bytes());
+            put_u256(&mut out, account.info.balance);
+            out.extend_from_slice(&account.info.nonce.to_be_bytes());
+            out.extend_from_slice(account.info.code_hash.as_bytes());
+            out.push(match account.account_state {
+                AccountState::None => 0,
+                AccountState::EVMTouched => 1,
+                AccountState::EVMStorageCleared => 2,
+            });
+            put_len(&mut out, account.storage.len());
+            for (index, value) in &account.storage {
+                put_u256(&mut out, *index);
+                put_u256(&mut out, *value);
+            }
+        }
+
+        // empty code is always present in a new cache.
+        let contracts: Vec<_> = self
+            .contracts
+            .values()
+            .filter(|code| !code.is_empty())
+            .collect();
+        put_len(&mut out, contracts.len());
+        for code in contracts {
+            let code = code.original_bytes();
+            put_len(&mut out, code.len());
+            out.extend_from_slice(code);
+        }
+
+        put_len(&mut out, self.block_hashes.len());
+        for (number, hash) in &self.block_hashes {
+            put_u256(&mut out, *number);
+            out.extend_from_slice(hash.as_bytes());
+        }
+        out
+    }
+
+    /// Load entries written by [`CacheDB::serialize_cache`], overriding cached entries with the
+    /// same keys. Nothing is loaded if input is malformed.
+    pub fn load_cache(&mut self, bytes: &[u8]) -> Result<(), LoadCacheError> {
+        let mut reader = Reader(bytes);
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(LoadCacheError::UnknownVersion(version));
+        }
+
+        let mut accounts = Vec::new();
+        for _ in 0..reader.len()? {
+            let address = H160::from_slice(reader.take(20)?);
+            let balance = reader.u256()?;
+            let nonce = u64::from_be_bytes(reader.take(8)?.try_into().unwrap());
+            let code_hash = H256::from_slice(reader.take(32)?);
+            let account_state = match reader.u8()? {
+                0 => AccountState::None,
+                1 => AccountState::EVMTouched,
+                2 => AccountState::EVMStorageCleared,
+                tag => return Err(LoadCacheError::InvalidAccountState(tag)),
+            };
+            let mut account = DbAccount {
+                info: AccountInfo {
+                    balance,
+                    nonce,
+                    code_hash,
+                    code: None,
+                },
+                account_state,
+                storage: Default::default(),
+            };
+            for _ in 0..reader.len()? {
+                let index = reader.u256()?;
+                account.storage.insert(index, reader.u256()?);
+            }
+            accounts.push((address, account));
+        }
+
+        let mut contracts = Vec::new();
+        for _ in 0..reader.len()? {
+            let len = reader.len()?;
+            let code = Bytecode::new_raw(Bytes::copy_from_slice(reader.take(len)?));
+            contracts.push(code);
+        }
+
+        let mut block_hashes = Vec::new();
+        for _ in 0..reader.len()? {
+            let number = reader.u256()?;
+            block_hashes.push((number, H256::from_slice(reader.take(32)?)));
+        }
+
+        self.accounts.extend(accounts);
+        self.contracts
+            .extend(contracts.into_iter().map(|code| (code.hash(), code)));
+        self.block_hashes.extend(block_hashes);
+        Ok(())
+    }
+}
+
+fn put_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u32).to_be_bytes());
+}
+
+fn put_u256(out: &mut Vec<u8>, value: U256) {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    out.extend_from_slice(&bytes);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadCacheError> {
+        if self.0.len() < len {
+            return Err(LoadCacheError::UnexpectedEnd);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadCacheError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, LoadCacheError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn u256(&mut self) -> Result<U256, LoadCacheError> {
+        Ok(U256::from_big_endian(self.take(32)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Database, InMemoryDB};
+
+    #[test]
+    fn round_trip() {
+        let account = H160::from_low_u64_be(42);
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        let mut state = InMemoryDB::default();
+        state.insert_account_info(
+            account,
+            AccountInfo {
+                balance: U256::from(1000),
+                nonce: 3,
+                ..AccountInfo::from_code(code.clone())
+            },
+        );
+        state.insert_account_storage(account, U256::from(1), U256::from(10));
+        state.insert_account_storage(account, U256::MAX, U256::from(20));
+        state.replace_account_storage(H160::from_low_u64_be(43), Default::default());
+        Database::block_hash(&mut state, U256::from(7));
+
+        let bytes = state.serialize_cache();
+        let mut loaded = InMemoryDB::default();
+        loaded.load_cache(&bytes).unwrap();
+
+        assert_eq!(loaded.serialize_cache(), bytes);
+        assert_eq!(loaded.basic(account).balance, U256::from(1000));
+        assert_eq!(loaded.basic(account).nonce, 3);
+        assert_eq!(loaded.code_by_hash(code.hash()), code);
+        assert_eq!(loaded.storage(account, U256::MAX), U256::from(20));
+        assert!(matches!(
+            loaded.accounts[&H160::from_low_u64_be(43)].account_state,
+            AccountState::EVMStorageCleared
+        ));
+        assert_eq!(loaded.block_hashes.len(), 1);
+        assert_eq!(loaded.block_hashes, state.block_hashes);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let mut state = InMemoryDB::default();
+        state.insert_account_storage(H160::zero(), U256::from(1), U256::from(10));
+        let bytes = state.serialize_cache();
+
+        let mut loaded = InMemoryDB::default();
+        assert_eq!(
+            loaded.load_cache(&bytes[..bytes.len() - 1]),
+            Err(LoadCacheError::UnexpectedEnd)
+        );
+        assert!(loaded.accounts.is_empty());
+        assert_eq!(
+            loaded.load_cache(&[2]),
+            Err(LoadCacheError::UnknownVersion(2))
+        );
+    }
+}
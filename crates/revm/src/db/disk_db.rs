@@ -0,0 +1,263 @@
+use super::{Database, DatabaseCommit, DatabaseRef};
+use crate::{interpreter::bytecode::Bytecode, Account, AccountInfo, KECCAK_EMPTY};
+use alloc::vec::Vec;
+use bytes::Bytes;
+use hashbrown::HashMap as Map;
+use primitive_types::{H160, H256, U256};
+use std::path::Path;
+
+/// Key prefixes, every key is the prefix followed by big endian fields.
+///
+/// * `ACCOUNT | address` -> `balance | nonce | code_hash`
+/// * `STORAGE | address | index` -> `value`, zero values are not stored
+/// * `CODE | code_hash` -> original code
+/// * `BLOCK_HASH | number` -> `hash`
+const ACCOUNT: u8 = 0;
+const STORAGE: u8 = 1;
+const CODE: u8 = 2;
+const BLOCK_HASH: u8 = 3;
+
+const ACCOUNT_LEN: usize = 32 + 8 + 32;
+
+/// Persistent database stored in an embedded [`sled`] key value store.
+///
+/// Changes are written with [`DatabaseCommit::commit`], every commit is applied atomically.
+/// Missing accounts and slots read as empty, missing block hashes as zero. Storage errors
+/// panic, as [`Database`] has no way to return them.
+#[derive(Debug, Clone)]
+pub struct DiskDB {
+    db: sled::Db,
+}
+
+impl DiskDB {
+    /// Open or create database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> sled::Result<Self> {
+        Ok(Self::new(sled::open(path)?))
+    }
+
+    pub fn new(db: sled::Db) -> Self {
+        Self { db }
+    }
+
+    /// Store hash of block `number`, for BLOCKHASH.
+    pub fn insert_block_hash(&self, number: U256, hash: H256) {
+        self.db
+            .insert(u256_key(BLOCK_HASH, number), hash.as_bytes())
+            .expect("diskdb write failed");
+    }
+
+    /// Wait until all written data is persisted to disk.
+    pub fn flush(&self) -> sled::Result<usize> {
+        self.db.flush()
+    }
+
+    fn get(&self, key: &[u8]) -> Option<sled::IVec> {
+        self.db.get(key).expect("diskdb read failed")
+    }
+
+    /// Remove all storage slots of `address`.
+    fn clear_storage(&self, batch: &mut sled::Batch, address: H160) {
+        for entry in self.db.scan_prefix(address_key(STORAGE, address)) {
+            let (key, _) = entry.expect("diskdb read failed");
+            batch.remove(key);
+        }
+    }
+}
+
+fn address_key(prefix: u8, address: H160) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + 20 + 32);
+    key.push(prefix);
+    key.extend_from_slice(address.as_bytes());
+    key
+}
+
+fn u256_key(prefix: u8, value: U256) -> Vec<u8> {
+    let mut key = [0u8; 33];
+    key[0] = prefix;
+    value.to_big_endian(&mut key[1..]);
+    key.to_vec()
+}
+
+fn storage_key(address: H160, index: U256) -> Vec<u8> {
+    let mut key = address_key(STORAGE, address);
+    key.resize(1 + 20 + 32, 0);
+    index.to_big_endian(&mut key[1 + 20..]);
+    key
+}
+
+fn encode_account(info: &AccountInfo) -> [u8; ACCOUNT_LEN] {
+    let mut value = [0u8; ACCOUNT_LEN];
+    info.balance.to_big_endian(&mut value[..32]);
+    value[32..40].copy_from_slice(&info.nonce.to_be_bytes());
+    value[40..].copy_from_slice(info.code_hash.as_bytes());
+    value
+}
+
+fn decode_account(value: &[u8]) -> AccountInfo {
+    let mut nonce = [0u8; 8];
+    nonce.copy_from_slice(&value[32..40]);
+    let code_hash = H256::from_slice(&value[40..]);
+    AccountInfo {
+        balance: U256::from_big_endian(&value[..32]),
+        nonce: u64::from_be_bytes(nonce),
+        code_hash,
+        // code is loaded with `code_by_hash` when needed.
+        code: (code_hash == KECCAK_EMPTY).then(Bytecode::new),
+    }
+}
+
+impl DatabaseRef for DiskDB {
+    fn basic(&self, address: H160) -> AccountInfo {
+        match self.get(&address_key(ACCOUNT, address)) {
+            Some(value) => decode_account(&value),
+            None => AccountInfo::default(),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: H256) -> Bytecode {
+        let mut key = vec![CODE];
+        key.extend_from_slice(code_hash.as_bytes());
+        match self.get(&key) {
+            // safe as the code is stored under its hash.
+            Some(code) => unsafe {
+                Bytecode::new_raw_with_hash(Bytes::copy_from_slice(&code), code_hash)
+            },
+            None => Bytecode::new(),
+        }
+    }
+
+    fn storage(&self, address: H160, index: U256) -> U256 {
+        self.get(&storage_key(address, index))
+            .map(|value| U256::from_big_endian(&value))
+            .unwrap_or_default()
+    }
+
+    fn block_hash(&self, number: U256) -> H256 {
+        self.get(&u256_key(BLOCK_HASH, number))
+            .map(|hash| H256::from_slice(&hash))
+            .unwrap_or_default()
+    }
+}
+
+impl Database for DiskDB {
+    fn basic(&mut self, address: H160) -> AccountInfo {
+        DatabaseRef::basic(self, address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: H256) -> Bytecode {
+        DatabaseRef::code_by_hash(self, code_hash)
+    }
+
+    fn storage(&mut self, address: H160, index: U256) -> U256 {
+        DatabaseRef::storage(self, address, index)
+    }
+
+    fn block_hash(&mut self, number: U256) -> H256 {
+        DatabaseRef::block_hash(self, number)
+    }
+}
+
+impl DatabaseCommit for DiskDB {
+    fn commit(&mut self, changes: Map<H160, Account>) {
+        let mut batch = sled::Batch::default();
+        for (address, account) in changes {
+            if account.is_destroyed {
+                batch.remove(address_key(ACCOUNT, address));
+                self.clear_storage(&mut batch, address);
+                continue;
+            }
+            if account.storage_cleared {
+                self.clear_storage(&mut batch, address);
+            }
+            let mut info = account.info;
+            if let Some(code) = &info.code {
+                if !code.is_empty() {
+                    info.code_hash = code.hash();
+                    let mut key = vec![CODE];
+                    key.extend_from_slice(info.code_hash.as_bytes());
+                    batch.insert(key, code.original_bytes());
+                }
+            }
+            if info.code_hash.is_zero() {
+                info.code_hash = KECCAK_EMPTY;
+            }
+            batch.insert(address_key(ACCOUNT, address), &encode_account(&info)[..]);
+            for (index, slot) in account.storage {
+                let key = storage_key(address, index);
+                let value = slot.present_value();
+                if value.is_zero() {
+                    batch.remove(key);
+                } else {
+                    let mut bytes = [0u8; 32];
+                    value.to_big_endian(&mut bytes);
+                    batch.insert(key, &bytes[..]);
+                }
+            }
+        }
+        self.db.apply_batch(batch).expect("diskdb write failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StorageSlot;
+
+    #[test]
+    fn reopen_reads_committed_state() {
+        let path = std::env::temp_dir().join(format!("revm-diskdb-{}", std::process::id()));
+        let account = H160::from_low_u64_be(0x42);
+        let destroyed = H160::from_low_u64_be(0x43);
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        // background flusher would hold the lock of the database after it is dropped.
+        let open = || {
+            let config = sled::Config::new().path(&path).flush_every_ms(None);
+            DiskDB::new(config.open().unwrap())
+        };
+        {
+            let mut db = open();
+            let mut changes = Map::new();
+            let mut changed = Account::from(AccountInfo::new(U256::from(10), 1, code.clone()));
+            changed.storage.insert(
+                U256::one(),
+                StorageSlot::new_changed(U256::zero(), U256::from(7)),
+            );
+            changes.insert(account, changed.clone());
+            changes.insert(destroyed, changed);
+            db.commit(changes);
+            db.insert_block_hash(U256::from(5), H256::from_low_u64_be(0x55));
+
+            let mut selfdestructed = Account::from(AccountInfo::default());
+            selfdestructed.is_destroyed = true;
+            db.commit(vec![(destroyed, selfdestructed)].into_iter().collect());
+            db.flush().unwrap();
+        }
+
+        let mut db = open();
+        let info = Database::basic(&mut db, account);
+        assert_eq!((info.balance, info.nonce), (U256::from(10), 1));
+        assert_eq!(info.code_hash, code.hash());
+        assert_eq!(Database::code_by_hash(&mut db, info.code_hash), code);
+        assert_eq!(
+            Database::storage(&mut db, account, U256::one()),
+            U256::from(7)
+        );
+        assert_eq!(
+            Database::storage(&mut db, account, U256::zero()),
+            U256::zero()
+        );
+        assert_eq!(
+            Database::block_hash(&mut db, U256::from(5)),
+            H256::from_low_u64_be(0x55)
+        );
+
+        assert_eq!(Database::basic(&mut db, destroyed), AccountInfo::default());
+        assert_eq!(
+            Database::storage(&mut db, destroyed, U256::one()),
+            U256::zero()
+        );
+
+        drop(db);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -1,11 +1,13 @@
-use super::{DatabaseCommit, DatabaseRef};
+use super::{DatabaseCommit, DatabaseRef, HistoryError};
 use crate::{interpreter::bytecode::Bytecode, Database, KECCAK_EMPTY};
-use crate::{Account, AccountInfo, Log};
+use crate::{Account, AccountInfo, Log, StorageSlot};
 use alloc::{
     collections::btree_map::{self, BTreeMap},
+    sync::Arc,
     vec::Vec,
 };
-use hashbrown::{hash_map::Entry, HashMap as Map};
+use core::fmt;
+use hashbrown::HashMap as Map;
 use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 
@@ -13,23 +15,34 @@ pub type InMemoryDB = CacheDB<EmptyDB>;
 
 impl InMemoryDB {
     pub fn default() -> Self {
-        CacheDB::new(EmptyDB {})
+        CacheDB::new(EmptyDB::new())
     }
 }
 
 /// Memory backend, storing all state values in a `Map` in memory.
+///
+/// All maps are ordered so iteration and serialization are deterministic. The wrapped
+/// `db` is not serialized.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CacheDB<ExtDB: DatabaseRef> {
     /// Dummy account info where `code` is always `None`.
     /// Code bytes can be found in `contracts`.
     pub accounts: BTreeMap<H160, DbAccount>,
-    pub contracts: Map<H256, Bytecode>,
+    pub contracts: BTreeMap<H256, Bytecode>,
+    /// Length of code read with [`Database::code_size`] that is not in `contracts`.
+    #[cfg_attr(feature = "with-serde", serde(skip))]
+    pub code_sizes: BTreeMap<H256, usize>,
     pub logs: Vec<Log>,
-    pub block_hashes: Map<U256, H256>,
+    /// Block hashes read from the wrapped database, kept until evicted with
+    /// [`CacheDB::invalidate_block_hashes`].
+    pub block_hashes: BTreeMap<U256, H256>,
+    #[cfg_attr(feature = "with-serde", serde(skip))]
     pub db: ExtDB,
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DbAccount {
     pub info: AccountInfo,
     /// If account is selfdestructed or newly created, storage will be cleared.
@@ -38,7 +51,8 @@ pub struct DbAccount {
     pub storage: BTreeMap<U256, U256>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AccountState {
     /// EVM touched this account
     EVMTouched,
@@ -49,23 +63,84 @@ pub enum AccountState {
     None,
 }
 
+/// Sizes of [`CacheDB`] maps, see [`CacheDB::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub accounts: usize,
+    /// Storage slots of all accounts.
+    pub storage_slots: usize,
+    /// Distinct non empty contracts.
+    pub contracts: usize,
+    pub block_hashes: usize,
+    pub logs: usize,
+    /// Rough estimate of memory used by cached entries in bytes. Map overhead is not counted.
+    pub approximate_size: usize,
+}
+
+/// Per account overrides, same as the state override object of `eth_call`.
+#[derive(Debug, Clone, Default)]
+pub struct AccountOverride {
+    /// Overrides account balance.
+    pub balance: Option<U256>,
+    /// Overrides account nonce.
+    pub nonce: Option<u64>,
+    /// Overrides account code.
+    pub code: Option<Bytecode>,
+    /// Replaces whole account storage, slots that are not listed are zero.
+    pub state: Option<Map<U256, U256>>,
+    /// Overrides only listed storage slots, rest of the storage is kept.
+    pub state_diff: Option<Map<U256, U256>>,
+}
+
+/// State overrides applied with [`CacheDB::apply_overrides`].
+pub type StateOverride = Map<H160, AccountOverride>;
+
 impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
     pub fn new(db: ExtDB) -> Self {
-        let mut contracts = Map::new();
+        let mut contracts = BTreeMap::new();
         contracts.insert(KECCAK_EMPTY, Bytecode::new());
         contracts.insert(H256::zero(), Bytecode::new());
         Self {
             accounts: BTreeMap::new(),
             contracts,
+            code_sizes: BTreeMap::new(),
             logs: Vec::default(),
-            block_hashes: Map::new(),
+            block_hashes: BTreeMap::new(),
             db,
         }
     }
 
+    /// Create database with given accounts and their storage already inserted. Account code
+    /// is hashed and inserted into `contracts` so that it can be found by `code_by_hash`.
+    pub fn from_accounts(
+        db: ExtDB,
+        accounts: impl IntoIterator<Item = (H160, (AccountInfo, Map<U256, U256>))>,
+    ) -> Self {
+        let mut cache = Self::new(db);
+        for (address, (mut info, storage)) in accounts {
+            cache.insert_contract(&mut info);
+            let account = cache.accounts.entry(address).or_default();
+            account.info = info;
+            account.storage.extend(storage);
+        }
+        cache
+    }
+
+    /// Insert account code into `contracts` and set `code_hash` to its hash.
+    ///
+    /// In debug builds this asserts that an explicitly set `code_hash` (not zero or
+    /// `KECCAK_EMPTY`) matches the code, a mismatch means that the caller has a stale hash.
     pub fn insert_contract(&mut self, account: &mut AccountInfo) {
         if let Some(code) = &account.code {
             if !code.is_empty() {
+                debug_assert!(
+                    account.code_hash.is_zero()
+                        || account.code_hash == KECCAK_EMPTY
+                        || account.code_hash == code.hash(),
+                    "code_hash {:?} does not match hash of the code {:?}",
+                    account.code_hash,
+                    code.hash()
+                );
                 account.code_hash = code.hash();
                 self.contracts
                     .entry(account.code_hash)
@@ -77,6 +152,14 @@ impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
         }
     }
 
+    /// Insert code that is not yet used by any account, returns its hash. Code can be later
+    /// referenced by `code_hash` of an account info without the `code`.
+    pub fn insert_code(&mut self, code: Bytecode) -> H256 {
+        let code_hash = code.hash();
+        self.contracts.entry(code_hash).or_insert(code);
+        code_hash
+    }
+
     /// Insert account info but not override storage
     pub fn insert_account_info(&mut self, address: H160, mut info: AccountInfo) {
         self.insert_contract(&mut info);
@@ -106,10 +189,241 @@ impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
         account.account_state = AccountState::EVMStorageCleared;
         account.storage = storage.into_iter().collect();
     }
-}
 
-impl<ExtDB: DatabaseRef> DatabaseCommit for CacheDB<ExtDB> {
-    fn commit(&mut self, changes: Map<H160, Account>) {
+    /// Iterate over accounts changed by committed transactions, skipping entries that were
+    /// only read through from the wrapped database or inserted directly.
+    pub fn dirty_accounts(&self) -> impl Iterator<Item = (&H160, &DbAccount)> {
+        self.accounts.iter().filter(|(_, account)| {
+            matches!(
+                account.account_state,
+                AccountState::EVMTouched | AccountState::EVMStorageCleared
+            )
+        })
+    }
+
+    /// Commit accounts changed by committed transactions, see [`CacheDB::dirty_accounts`], to
+    /// `target` and mark them clean. Accounts with cleared storage clear it in `target` too.
+    ///
+    /// Original value of a slot is its value in the wrapped database and only slots that
+    /// differ from it are committed, unless storage was cleared. Clean accounts read missing
+    /// storage slots from the wrapped database again, so it should see the flushed state, e.g.
+    /// by being `target` itself.
+    pub fn flush_to(&mut self, target: &mut impl DatabaseCommit) {
+        let mut changes = Map::new();
+        for (address, db_account) in self.dirty_accounts() {
+            let mut info = db_account.info.clone();
+            if info.code.is_none() && info.code_hash != KECCAK_EMPTY {
+                info.code = self.contracts.get(&info.code_hash).cloned();
+            }
+            let mut account = Account::from(info);
+            account.is_touched = true;
+            account.storage_cleared =
+                matches!(db_account.account_state, AccountState::EVMStorageCleared);
+            for (index, value) in &db_account.storage {
+                let original = self.db.storage(*address, *index);
+                if account.storage_cleared || original != *value {
+                    account
+                        .storage
+                        .insert(*index, StorageSlot::new_changed(original, *value));
+                }
+            }
+            changes.insert(*address, account);
+        }
+        for address in changes.keys() {
+            if let Some(account) = self.accounts.get_mut(address) {
+                account.account_state = AccountState::None;
+            }
+        }
+        target.commit(changes);
+    }
+
+    /// Evict cached hashes of blocks `from` and above, so they are read from the wrapped
+    /// database again. Hashes are cached forever, so callers must call this on reorg with
+    /// the first block number that changed.
+    pub fn invalidate_block_hashes(&mut self, from: U256) {
+        self.block_hashes.split_off(&from);
+    }
+
+    /// Number of distinct non empty contracts in the cache.
+    pub fn contract_count(&self) -> usize {
+        self.contracts
+            .values()
+            .filter(|code| !code.is_empty())
+            .count()
+    }
+
+    /// Count cached entries and estimate their memory usage.
+    pub fn stats(&self) -> CacheStats {
+        use core::mem::size_of;
+
+        let mut stats = CacheStats {
+            accounts: self.accounts.len(),
+            block_hashes: self.block_hashes.len(),
+            logs: self.logs.len(),
+            ..Default::default()
+        };
+        stats.approximate_size += stats.accounts * size_of::<(H160, DbAccount)>()
+            + stats.block_hashes * size_of::<(U256, H256)>();
+        for account in self.accounts.values() {
+            stats.storage_slots += account.storage.len();
+        }
+        stats.approximate_size += stats.storage_slots * size_of::<(U256, U256)>();
+        for code in self.contracts.values() {
+            if !code.is_empty() {
+                stats.contracts += 1;
+            }
+            stats.approximate_size += size_of::<(H256, Bytecode)>() + code.bytes().len();
+        }
+        for log in &self.logs {
+            stats.approximate_size +=
+                size_of::<Log>() + log.topics.len() * size_of::<H256>() + log.data.len();
+        }
+        stats
+    }
+
+    /// Check internal invariants of the cache, meant as a debugging aid for code that fills
+    /// or modifies it directly. All found inconsistencies are returned.
+    ///
+    /// Code that is not in `contracts` is reported even though [`Database::code_by_hash`]
+    /// would fetch it from the wrapped database, so the check is exact only for caches over
+    /// [`EmptyDB`] or with all code inserted.
+    pub fn check_invariants(&self) -> Result<(), Vec<Inconsistency>> {
+        let mut inconsistencies = Vec::new();
+        for (code_hash, code) in &self.contracts {
+            // zero hash is mapped to empty code, see `CacheDB::new`.
+            if !code_hash.is_zero() && *code_hash != code.hash() {
+                inconsistencies.push(Inconsistency::ContractHashMismatch {
+                    code_hash: *code_hash,
+                });
+            }
+        }
+        for (address, account) in &self.accounts {
+            let info = &account.info;
+            match &info.code {
+                Some(code) if !code.is_empty() && code.hash() != info.code_hash => {
+                    inconsistencies.push(Inconsistency::CodeHashMismatch { address: *address });
+                }
+                None if info.has_code() && !self.contracts.contains_key(&info.code_hash) => {
+                    inconsistencies.push(Inconsistency::MissingCode {
+                        address: *address,
+                        code_hash: info.code_hash,
+                    });
+                }
+                _ => (),
+            }
+            if matches!(account.account_state, AccountState::EVMStorageCleared)
+                && info.is_empty()
+                && account.storage.values().any(|value| !value.is_zero())
+            {
+                inconsistencies.push(Inconsistency::StaleStorage { address: *address });
+            }
+        }
+        if inconsistencies.is_empty() {
+            Ok(())
+        } else {
+            Err(inconsistencies)
+        }
+    }
+
+    /// Apply `eth_call` style state overrides. Full storage replacement (`state`) is applied
+    /// before `state_diff`.
+    pub fn apply_overrides(&mut self, overrides: &StateOverride) {
+        for (address, account_override) in overrides {
+            let address = *address;
+            let db = &self.db;
+            let account = self.accounts.entry(address).or_insert_with(|| DbAccount {
+                info: db.basic(address),
+                ..Default::default()
+            });
+
+            if let Some(balance) = account_override.balance {
+                account.info.balance = balance;
+            }
+            if let Some(nonce) = account_override.nonce {
+                account.info.nonce = nonce;
+            }
+            if let Some(code) = &account_override.code {
+                account.info.code_hash = code.hash();
+                account.info.code = Some(code.clone());
+                self.contracts
+                    .entry(code.hash())
+                    .or_insert_with(|| code.clone());
+            }
+
+            if let Some(state) = &account_override.state {
+                self.replace_account_storage(address, state.clone());
+            }
+            if let Some(state_diff) = &account_override.state_diff {
+                for (slot, value) in state_diff {
+                    self.insert_account_storage(address, *slot, *value);
+                }
+            }
+        }
+    }
+
+    /// Same as [`DatabaseCommit::commit`] but first checks that changes are sane, nothing is
+    /// applied if they are not. Meant for catching bugs in layers that build changes.
+    ///
+    /// Nonces must not decrease, except for destroyed or newly created accounts. Balance of
+    /// an account must not underflow: subtraction that wrapped around shows up as a balance
+    /// that grew by more than half of the `U256` range, far more than any real credit. Accounts
+    /// are checked in address order, so the same changes always report the same error.
+    pub fn commit_checked(&mut self, changes: Map<H160, Account>) -> Result<(), CommitError> {
+        let changes: BTreeMap<H160, Account> = changes.into_iter().collect();
+        for (address, account) in &changes {
+            let old = match self.accounts.get(address) {
+                Some(db_account) => db_account.info.clone(),
+                None => self.db.basic(*address),
+            };
+            if !account.is_destroyed && !account.storage_cleared && account.info.nonce < old.nonce {
+                return Err(CommitError::NonceDecreased {
+                    address: *address,
+                    old: old.nonce,
+                    new: account.info.nonce,
+                });
+            }
+            let balance = account.info.balance;
+            if !account.is_destroyed
+                && balance > old.balance
+                && balance - old.balance > U256::MAX >> 1
+            {
+                return Err(CommitError::BalanceUnderflow { address: *address });
+            }
+        }
+
+        self.commit_sorted(changes);
+        Ok(())
+    }
+
+    /// Apply changes in address order, so that commit does not depend on the iteration
+    /// order of the hash map returned by the EVM.
+    fn commit_sorted(&mut self, changes: BTreeMap<H160, Account>) {
+        // EVM changes already contain the credited beneficiary, credit it here only for
+        // change sets that do not.
+        let transfers: Vec<(H160, U256)> = changes
+            .iter()
+            .filter(|(_, account)| account.is_destroyed)
+            .filter_map(|(address, account)| {
+                account.selfdestruct_transfer.filter(|(beneficiary, _)| {
+                    beneficiary != address && !changes.contains_key(beneficiary)
+                })
+            })
+            .collect();
+        for (beneficiary, balance) in transfers {
+            let db = &self.db;
+            let db_account = self
+                .accounts
+                .entry(beneficiary)
+                .or_insert_with(|| DbAccount {
+                    info: db.basic(beneficiary),
+                    ..Default::default()
+                });
+            db_account.info.balance = db_account.info.balance.saturating_add(balance);
+            if matches!(db_account.account_state, AccountState::None) {
+                db_account.account_state = AccountState::EVMTouched;
+            }
+        }
+
         for (address, mut account) in changes {
             if account.is_destroyed {
                 let db_account = self.accounts.entry(address).or_default();
@@ -139,11 +453,39 @@ impl<ExtDB: DatabaseRef> DatabaseCommit for CacheDB<ExtDB> {
     }
 }
 
+/// Error returned by [`CacheDB::commit_checked`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitError {
+    /// Nonce of account went down.
+    NonceDecreased { address: H160, old: u64, new: u64 },
+    /// Balance of account wrapped around below zero.
+    BalanceUnderflow { address: H160 },
+}
+
+/// Broken invariant of [`CacheDB`], found by [`CacheDB::check_invariants`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency {
+    /// Account has code hash but the code is neither inline nor in `contracts`.
+    MissingCode { address: H160, code_hash: H256 },
+    /// Inline code of account does not match its `code_hash`.
+    CodeHashMismatch { address: H160 },
+    /// Code in `contracts` is stored under a hash that is not its own.
+    ContractHashMismatch { code_hash: H256 },
+    /// Destroyed account still has non zero storage slots.
+    StaleStorage { address: H160 },
+}
+
+impl<ExtDB: DatabaseRef> DatabaseCommit for CacheDB<ExtDB> {
+    fn commit(&mut self, changes: Map<H160, Account>) {
+        self.commit_sorted(changes.into_iter().collect());
+    }
+}
+
 impl<ExtDB: DatabaseRef> Database for CacheDB<ExtDB> {
     fn block_hash(&mut self, number: U256) -> H256 {
         match self.block_hashes.entry(number) {
-            Entry::Occupied(entry) => *entry.get(),
-            Entry::Vacant(entry) => {
+            btree_map::Entry::Occupied(entry) => *entry.get(),
+            btree_map::Entry::Vacant(entry) => {
                 let hash = self.db.block_hash(number);
                 entry.insert(hash);
                 hash
@@ -155,10 +497,16 @@ impl<ExtDB: DatabaseRef> Database for CacheDB<ExtDB> {
         match self.accounts.entry(address) {
             btree_map::Entry::Occupied(entry) => entry.get().info.clone(),
             btree_map::Entry::Vacant(entry) => {
-                let info = self.db.basic(address);
+                let mut info = self.db.basic(address);
+                // code is kept inline with the account, hash is needed only for EXTCODEHASH.
+                if let Some(code) = &info.code {
+                    if info.code_hash.is_zero() && !code.is_empty() {
+                        info.code_hash = code.hash();
+                    }
+                }
                 entry.insert(DbAccount {
                     info: info.clone(),
-                    account_state: AccountState::EVMTouched,
+                    account_state: AccountState::None,
                     storage: BTreeMap::new(),
                 });
                 info
@@ -202,13 +550,24 @@ impl<ExtDB: DatabaseRef> Database for CacheDB<ExtDB> {
 
     fn code_by_hash(&mut self, code_hash: H256) -> Bytecode {
         match self.contracts.entry(code_hash) {
-            Entry::Occupied(entry) => entry.get().clone(),
-            Entry::Vacant(entry) => {
+            btree_map::Entry::Occupied(entry) => entry.get().clone(),
+            btree_map::Entry::Vacant(entry) => {
                 // if you return code bytes when basic fn is called this function is not needed.
                 entry.insert(self.db.code_by_hash(code_hash)).clone()
             }
         }
     }
+
+    fn code_size(&mut self, code_hash: H256) -> usize {
+        if let Some(code) = self.contracts.get(&code_hash) {
+            return code.len();
+        }
+        let db = &self.db;
+        *self
+            .code_sizes
+            .entry(code_hash)
+            .or_insert_with(|| db.code_size(code_hash))
+    }
 }
 
 impl<ExtDB: DatabaseRef> DatabaseRef for CacheDB<ExtDB> {
@@ -248,11 +607,108 @@ impl<ExtDB: DatabaseRef> DatabaseRef for CacheDB<ExtDB> {
             None => self.db.code_by_hash(code_hash),
         }
     }
+
+    fn code_size(&self, code_hash: H256) -> usize {
+        match (
+            self.contracts.get(&code_hash),
+            self.code_sizes.get(&code_hash),
+        ) {
+            (Some(code), _) => code.len(),
+            (None, Some(size)) => *size,
+            (None, None) => self.db.code_size(code_hash),
+        }
+    }
+
+    /// Cached state is the latest one, history is always read from the wrapped database.
+    fn basic_at(&self, address: H160, number: U256) -> Result<AccountInfo, HistoryError> {
+        self.db.basic_at(address, number)
+    }
+
+    fn storage_at(&self, address: H160, index: U256, number: U256) -> Result<U256, HistoryError> {
+        self.db.storage_at(address, index, number)
+    }
+
+    fn try_basic(&self, address: H160) -> Option<AccountInfo> {
+        match self.accounts.get(&address) {
+            Some(acc) => Some(acc.info.clone()),
+            None => self.db.try_basic(address),
+        }
+    }
+
+    fn try_code_by_hash(&self, code_hash: H256) -> Option<Bytecode> {
+        match self.contracts.get(&code_hash) {
+            Some(entry) => Some(entry.clone()),
+            None => self.db.try_code_by_hash(code_hash),
+        }
+    }
+
+    fn try_storage(&self, address: H160, index: U256) -> Option<U256> {
+        match self.accounts.get(&address) {
+            Some(acc_entry) => match acc_entry.storage.get(&index) {
+                Some(entry) => Some(*entry),
+                None => {
+                    if matches!(acc_entry.account_state, AccountState::EVMStorageCleared) {
+                        Some(U256::zero())
+                    } else {
+                        self.db.try_storage(address, index)
+                    }
+                }
+            },
+            None => self.db.try_storage(address, index),
+        }
+    }
+
+    fn try_block_hash(&self, number: U256) -> Option<H256> {
+        match self.block_hashes.get(&number) {
+            Some(entry) => Some(*entry),
+            None => self.db.try_block_hash(number),
+        }
+    }
+}
+
+/// How [`EmptyDB`] answers block hash queries.
+#[derive(Clone, Default)]
+pub enum BlockHashStrategy {
+    /// Keccak of the big endian block number.
+    #[default]
+    Keccak,
+    /// Zero hash for every block.
+    Zero,
+    /// Hash computed by the given function.
+    Custom(Arc<dyn Fn(U256) -> H256 + Send + Sync>),
+}
+
+impl fmt::Debug for BlockHashStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keccak => f.write_str("Keccak"),
+            Self::Zero => f.write_str("Zero"),
+            Self::Custom(_) => f.write_str("Custom"),
+        }
+    }
 }
 
 /// An empty database that always returns default values when queried.
+///
+/// Block hashes are not default, they are derived from the block number as set by
+/// [`BlockHashStrategy`], keccak of the number by default.
 #[derive(Debug, Default, Clone)]
-pub struct EmptyDB();
+pub struct EmptyDB {
+    block_hash: BlockHashStrategy,
+}
+
+impl EmptyDB {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty database answering block hash queries with `strategy`.
+    pub fn with_block_hash(strategy: BlockHashStrategy) -> Self {
+        Self {
+            block_hash: strategy,
+        }
+    }
+}
 
 impl DatabaseRef for EmptyDB {
     /// Get basic account information.
@@ -270,9 +726,32 @@ impl DatabaseRef for EmptyDB {
 
     // History related
     fn block_hash(&self, number: U256) -> H256 {
-        let mut buffer: [u8; 4 * 8] = [0; 4 * 8];
-        number.to_big_endian(&mut buffer);
-        H256::from_slice(&Keccak256::digest(&buffer))
+        match &self.block_hash {
+            BlockHashStrategy::Keccak => {
+                let mut buffer: [u8; 4 * 8] = [0; 4 * 8];
+                number.to_big_endian(&mut buffer);
+                H256::from_slice(&Keccak256::digest(&buffer))
+            }
+            BlockHashStrategy::Zero => H256::zero(),
+            BlockHashStrategy::Custom(block_hash) => block_hash(number),
+        }
+    }
+
+    // EmptyDB holds no data, so layered lookups always fall through it.
+    fn try_basic(&self, _address: H160) -> Option<AccountInfo> {
+        None
+    }
+
+    fn try_code_by_hash(&self, _code_hash: H256) -> Option<Bytecode> {
+        None
+    }
+
+    fn try_storage(&self, _address: H160, _index: U256) -> Option<U256> {
+        None
+    }
+
+    fn try_block_hash(&self, _number: U256) -> Option<H256> {
+        None
     }
 }
 
@@ -323,9 +802,16 @@ impl Database for BenchmarkDB {
 mod tests {
     use primitive_types::H160;
 
-    use crate::{AccountInfo, Database};
+    use crate::db::DatabaseRef;
+    use crate::{Account, AccountInfo, Bytecode, Database, DatabaseCommit, StorageSlot};
+    use bytes::Bytes;
+    use hashbrown::HashMap as Map;
+    use primitive_types::U256;
 
-    use super::{CacheDB, EmptyDB};
+    use super::{
+        AccountOverride, AccountState, BlockHashStrategy, CacheDB, CacheStats, CommitError,
+        EmptyDB, Inconsistency, StateOverride,
+    };
 
     #[test]
     pub fn test_insert_account_storage() {
@@ -348,6 +834,428 @@ mod tests {
         assert_eq!(new_state.storage(account, key), value);
     }
 
+    #[test]
+    pub fn test_empty_db_block_hash() {
+        use crate::db::DatabaseRef;
+        use alloc::sync::Arc;
+        use primitive_types::H256;
+        use sha3::{Digest, Keccak256};
+
+        let number = U256::from(7);
+        let mut buffer = [0u8; 32];
+        buffer[31] = 7;
+        assert_eq!(
+            EmptyDB::new().block_hash(number),
+            H256::from_slice(&Keccak256::digest(buffer))
+        );
+        assert_eq!(
+            EmptyDB::with_block_hash(BlockHashStrategy::Zero).block_hash(number),
+            H256::zero()
+        );
+        let custom = EmptyDB::with_block_hash(BlockHashStrategy::Custom(Arc::new(|number| {
+            H256::from_low_u64_be(number.as_u64() + 1)
+        })));
+        assert_eq!(custom.block_hash(number), H256::from_low_u64_be(8));
+    }
+
+    #[test]
+    pub fn test_insert_code() {
+        let mut state = CacheDB::new(EmptyDB::default());
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        let code_hash = state.insert_code(code.clone());
+        assert_eq!(code_hash, code.hash());
+        assert_eq!(
+            Database::code_by_hash(&mut state, code_hash).bytes(),
+            code.bytes()
+        );
+
+        let address = H160::from_low_u64_be(1);
+        state.insert_account_info(
+            address,
+            AccountInfo {
+                code_hash,
+                code: None,
+                ..Default::default()
+            },
+        );
+        let info = Database::basic(&mut state, address);
+        assert_eq!(info.code_hash, code_hash);
+        assert_eq!(state.contract_count(), 1);
+    }
+
+    #[test]
+    pub fn test_inline_code() {
+        use crate::db::DatabaseRef;
+        use crate::{Return, TransactTo};
+        use primitive_types::H256;
+
+        /// Backend that stores code with the account and has no code by hash lookup.
+        struct InlineCodeDB;
+
+        impl DatabaseRef for InlineCodeDB {
+            fn basic(&self, address: H160) -> AccountInfo {
+                if address != H160::from_low_u64_be(0x42) {
+                    return AccountInfo::default();
+                }
+                // PUSH1 0x01, PUSH1 0x00, SSTORE, STOP
+                AccountInfo {
+                    code_hash: H256::zero(),
+                    code: Some(Bytecode::new_raw(
+                        vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00].into(),
+                    )),
+                    ..Default::default()
+                }
+            }
+            fn code_by_hash(&self, _code_hash: H256) -> Bytecode {
+                panic!("code is inline with the account")
+            }
+            fn storage(&self, _address: H160, _index: U256) -> U256 {
+                U256::zero()
+            }
+            fn block_hash(&self, _number: U256) -> H256 {
+                H256::zero()
+            }
+        }
+
+        let contract = H160::from_low_u64_be(0x42);
+        let mut evm = crate::new();
+        evm.database(CacheDB::new(InlineCodeDB));
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 100_000;
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(
+            state[&contract].storage[&U256::zero()].present_value(),
+            U256::one()
+        );
+
+        let cache = evm.db().unwrap();
+        let info = Database::basic(cache, contract);
+        assert_eq!(info.code_hash, info.code.unwrap().hash());
+        assert_eq!(cache.contract_count(), 0);
+    }
+
+    #[test]
+    pub fn test_code_size() {
+        use crate::{opcode, BytecodeBuilder, Return, TransactTo};
+        use core::cell::Cell;
+        use primitive_types::H256;
+
+        let contract = H160::from_low_u64_be(0x42);
+        let target = H160::from_low_u64_be(0x43);
+
+        /// Backend that knows only length of the code of `target`.
+        struct SizeOnlyDB {
+            code: Bytecode,
+            size_queries: Cell<usize>,
+        }
+
+        impl DatabaseRef for SizeOnlyDB {
+            fn basic(&self, address: H160) -> AccountInfo {
+                match address.to_low_u64_be() {
+                    0x42 => AccountInfo::from_code(self.code.clone()),
+                    0x43 => AccountInfo {
+                        code_hash: H256::repeat_byte(1),
+                        code: None,
+                        ..Default::default()
+                    },
+                    _ => AccountInfo::default(),
+                }
+            }
+            fn code_by_hash(&self, _code_hash: H256) -> Bytecode {
+                panic!("only code size is known")
+            }
+            fn code_size(&self, code_hash: H256) -> usize {
+                assert_eq!(code_hash, H256::repeat_byte(1));
+                self.size_queries.set(self.size_queries.get() + 1);
+                1234
+            }
+            fn storage(&self, _address: H160, _index: U256) -> U256 {
+                U256::zero()
+            }
+            fn block_hash(&self, _number: U256) -> H256 {
+                H256::zero()
+            }
+        }
+
+        let code = BytecodeBuilder::new()
+            .push(U256::from_big_endian(target.as_bytes()))
+            .op(opcode::EXTCODESIZE)
+            .push(0)
+            .op(opcode::SSTORE)
+            .op(opcode::STOP)
+            .build();
+        let mut evm = crate::new();
+        evm.database(CacheDB::new(SizeOnlyDB {
+            code,
+            size_queries: Cell::new(0),
+        }));
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 100_000;
+        for _ in 0..2 {
+            let (result, state) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Stop);
+            assert_eq!(
+                state[&contract].storage[&U256::zero()].present_value(),
+                U256::from(1234)
+            );
+        }
+
+        // size is cached, code of the target is never loaded.
+        let cache = evm.db().unwrap();
+        assert_eq!(cache.db.size_queries.get(), 1);
+        assert_eq!(cache.code_sizes.get(&H256::repeat_byte(1)), Some(&1234));
+        assert!(!cache.contracts.contains_key(&H256::repeat_byte(1)));
+    }
+
+    #[test]
+    pub fn test_history_passes_through() {
+        use crate::db::HistoryError;
+        use primitive_types::H256;
+
+        /// Backend where balance and slot 0 of every account at a past block are equal to the
+        /// block number.
+        struct HistoryDB;
+
+        impl DatabaseRef for HistoryDB {
+            fn basic(&self, _address: H160) -> AccountInfo {
+                AccountInfo::from_balance(U256::from(100))
+            }
+            fn code_by_hash(&self, _code_hash: H256) -> Bytecode {
+                Bytecode::new()
+            }
+            fn storage(&self, _address: H160, _index: U256) -> U256 {
+                U256::from(100)
+            }
+            fn block_hash(&self, _number: U256) -> H256 {
+                H256::zero()
+            }
+            fn basic_at(&self, _address: H160, number: U256) -> Result<AccountInfo, HistoryError> {
+                if number > U256::from(100) {
+                    return Err(HistoryError::Unavailable(number));
+                }
+                Ok(AccountInfo::from_balance(number))
+            }
+            fn storage_at(
+                &self,
+                _address: H160,
+                index: U256,
+                number: U256,
+            ) -> Result<U256, HistoryError> {
+                Ok(if index.is_zero() {
+                    number
+                } else {
+                    U256::zero()
+                })
+            }
+        }
+
+        let account = H160::from_low_u64_be(42);
+        let mut state = CacheDB::new(HistoryDB);
+        // latest state is cached, history is not affected by it.
+        state.insert_account_info(account, AccountInfo::from_balance(U256::from(7)));
+        state.insert_account_storage(account, U256::zero(), U256::from(7));
+        for number in [10u64, 20] {
+            let number = U256::from(number);
+            assert_eq!(state.basic_at(account, number).unwrap().balance, number);
+            assert_eq!(state.storage_at(account, U256::zero(), number), Ok(number));
+        }
+        assert_eq!(DatabaseRef::basic(&state, account).balance, U256::from(7));
+        assert_eq!(
+            state.basic_at(account, U256::from(101)),
+            Err(HistoryError::Unavailable(U256::from(101)))
+        );
+
+        let latest_only = CacheDB::new(EmptyDB::default());
+        assert_eq!(
+            latest_only.basic_at(account, U256::one()),
+            Err(HistoryError::Unsupported)
+        );
+        assert_eq!(
+            latest_only.storage_at(account, U256::zero(), U256::one()),
+            Err(HistoryError::Unsupported)
+        );
+    }
+
+    #[test]
+    pub fn test_stats() {
+        let mut state = CacheDB::new(EmptyDB::default());
+        assert_eq!(state.stats().contracts, 0);
+
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        state.insert_account_info(
+            H160::from_low_u64_be(1),
+            AccountInfo::from_code(code.clone()),
+        );
+        state.insert_account_info(H160::from_low_u64_be(2), AccountInfo::from_code(code));
+        state.insert_account_storage(H160::from_low_u64_be(2), U256::from(1), U256::from(1));
+        state.insert_account_storage(H160::from_low_u64_be(3), U256::from(1), U256::from(1));
+        state.insert_account_storage(H160::from_low_u64_be(3), U256::from(2), U256::from(1));
+        Database::block_hash(&mut state, U256::from(1));
+
+        let stats = state.stats();
+        assert_eq!(state.contract_count(), 1);
+        assert_eq!(
+            stats,
+            CacheStats {
+                accounts: 3,
+                storage_slots: 3,
+                contracts: 1,
+                block_hashes: 1,
+                logs: 0,
+                approximate_size: stats.approximate_size,
+            }
+        );
+        assert!(stats.approximate_size > 3 * 64 + 3);
+    }
+
+    #[test]
+    pub fn test_invalidate_block_hashes() {
+        use primitive_types::H256;
+
+        let mut node = CacheDB::new(EmptyDB::default());
+        for number in 1..4u64 {
+            node.block_hashes
+                .insert(number.into(), H256::from_low_u64_be(number));
+        }
+        let mut state = CacheDB::new(node);
+        for number in 1..4u64 {
+            Database::block_hash(&mut state, number.into());
+        }
+
+        // reorg of blocks 2 and 3 is not seen until invalidated.
+        for number in 2..4u64 {
+            state
+                .db
+                .block_hashes
+                .insert(number.into(), H256::from_low_u64_be(number + 10));
+        }
+        assert_eq!(
+            Database::block_hash(&mut state, 2u64.into()),
+            H256::from_low_u64_be(2)
+        );
+
+        state.invalidate_block_hashes(2u64.into());
+        assert_eq!(state.block_hashes.len(), 1);
+        assert_eq!(
+            Database::block_hash(&mut state, 1u64.into()),
+            H256::from_low_u64_be(1)
+        );
+        assert_eq!(
+            Database::block_hash(&mut state, 2u64.into()),
+            H256::from_low_u64_be(12)
+        );
+        assert_eq!(
+            Database::block_hash(&mut state, 3u64.into()),
+            H256::from_low_u64_be(13)
+        );
+    }
+
+    #[test]
+    pub fn test_dirty_accounts() {
+        let read = H160::from_low_u64_be(42);
+        let written = H160::from_low_u64_be(43);
+        let mut wrapped = CacheDB::new(EmptyDB::default());
+        wrapped.insert_account_info(read, AccountInfo::from_balance(U256::from(1)));
+        let mut state = CacheDB::new(wrapped);
+        assert_eq!(Database::basic(&mut state, read).balance, U256::from(1));
+        Database::storage(&mut state, read, U256::one());
+        assert!(state.accounts.contains_key(&read));
+        assert_eq!(state.dirty_accounts().count(), 0);
+
+        let mut account: Account = AccountInfo::from_balance(U256::from(2)).into();
+        account.is_touched = true;
+        state.commit(Map::from([(written, account)]));
+        let dirty: Vec<_> = state
+            .dirty_accounts()
+            .map(|(address, _)| *address)
+            .collect();
+        assert_eq!(dirty, vec![written]);
+    }
+
+    #[test]
+    pub fn test_flush_to() {
+        let touched = H160::from_low_u64_be(42);
+        let cleared = H160::from_low_u64_be(43);
+        let mut base = CacheDB::new(EmptyDB::default());
+        for address in [touched, cleared] {
+            base.insert_account_storage(address, U256::zero(), U256::one());
+            base.insert_account_storage(address, U256::one(), U256::one());
+        }
+
+        let mut overlay = base.clone();
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x00, 0x00]));
+        let mut account: Account = AccountInfo::new(U256::from(2), 1, code.clone()).into();
+        account.storage.insert(
+            U256::one(),
+            StorageSlot::new_changed(U256::one(), U256::from(5)),
+        );
+        let mut destroyed: Account = AccountInfo::default().into();
+        destroyed.is_destroyed = true;
+        overlay.commit(Map::from([(touched, account), (cleared, destroyed)]));
+        let mut recreated: Account = AccountInfo::from_balance(U256::from(3)).into();
+        recreated.storage_cleared = true;
+        recreated.storage.insert(
+            U256::from(2),
+            StorageSlot::new_changed(U256::zero(), U256::from(7)),
+        );
+        overlay.commit(Map::from([(cleared, recreated)]));
+
+        overlay.flush_to(&mut base);
+        assert_eq!(overlay.dirty_accounts().count(), 0);
+        assert_eq!(DatabaseRef::basic(&base, touched).balance, U256::from(2));
+        assert_eq!(Database::code_by_hash(&mut base, code.hash()), code);
+        assert_eq!(
+            DatabaseRef::storage(&base, touched, U256::zero()),
+            U256::one()
+        );
+        assert_eq!(
+            DatabaseRef::storage(&base, touched, U256::one()),
+            U256::from(5)
+        );
+        assert_eq!(DatabaseRef::basic(&base, cleared).balance, U256::from(3));
+        assert_eq!(
+            DatabaseRef::storage(&base, cleared, U256::one()),
+            U256::zero()
+        );
+        assert_eq!(
+            DatabaseRef::storage(&base, cleared, U256::from(2)),
+            U256::from(7)
+        );
+        assert_eq!(
+            base.accounts[&cleared].account_state,
+            AccountState::EVMStorageCleared
+        );
+    }
+
+    #[test]
+    pub fn test_flush_to_skips_read_accounts() {
+        let account = H160::from_low_u64_be(42);
+        let mut wrapped = CacheDB::new(EmptyDB::default());
+        wrapped.insert_account_info(account, AccountInfo::from_balance(U256::from(1)));
+        wrapped.insert_account_storage(account, U256::one(), U256::from(2));
+        let mut overlay = CacheDB::new(wrapped);
+        Database::basic(&mut overlay, account);
+        Database::storage(&mut overlay, account, U256::one());
+        Database::storage(&mut overlay, account, U256::from(2));
+
+        let mut target = CacheDB::new(EmptyDB::default());
+        overlay.flush_to(&mut target);
+        assert!(target.accounts.is_empty());
+
+        // slot that was only read is not flushed with a changed one.
+        let mut changed: Account = AccountInfo::from_balance(U256::from(1)).into();
+        changed.storage.insert(
+            U256::from(2),
+            StorageSlot::new_changed(U256::zero(), U256::from(3)),
+        );
+        overlay.commit(Map::from([(account, changed)]));
+        overlay.flush_to(&mut target);
+        let flushed = &target.accounts[&account];
+        assert_eq!(flushed.storage.len(), 1);
+        assert_eq!(flushed.storage[&U256::from(2)], U256::from(3));
+    }
+
     #[test]
     pub fn test_replace_account_storage() {
         let account = H160::from_low_u64_be(42);
@@ -372,4 +1280,288 @@ mod tests {
         assert_eq!(new_state.storage(account, key0), 0.into());
         assert_eq!(new_state.storage(account, key1), value1);
     }
+
+    fn overrides_state() -> (H160, CacheDB<CacheDB<EmptyDB>>) {
+        let account = H160::from_low_u64_be(42);
+        let mut init_state = CacheDB::new(EmptyDB::default());
+        init_state.insert_account_info(
+            account,
+            AccountInfo {
+                nonce: 1,
+                balance: 100u64.into(),
+                ..Default::default()
+            },
+        );
+        init_state.insert_account_storage(account, 1u64.into(), 10u64.into());
+        init_state.insert_account_storage(account, 2u64.into(), 20u64.into());
+        (account, CacheDB::new(init_state))
+    }
+
+    #[test]
+    pub fn test_override_balance_and_nonce() {
+        let (account, mut state) = overrides_state();
+        let overrides: StateOverride = [(
+            account,
+            AccountOverride {
+                balance: Some(5u64.into()),
+                nonce: Some(7),
+                ..Default::default()
+            },
+        )]
+        .into();
+        state.apply_overrides(&overrides);
+
+        let info = state.basic(account);
+        assert_eq!(info.balance, 5u64.into());
+        assert_eq!(info.nonce, 7);
+        assert_eq!(state.storage(account, 1u64.into()), 10u64.into());
+    }
+
+    #[test]
+    pub fn test_override_code() {
+        let (account, mut state) = overrides_state();
+        let code = Bytecode::new_raw(vec![0x60, 0x00, 0x00].into());
+        let overrides: StateOverride = [(
+            account,
+            AccountOverride {
+                code: Some(code.clone()),
+                ..Default::default()
+            },
+        )]
+        .into();
+        state.apply_overrides(&overrides);
+
+        let info = state.basic(account);
+        assert_eq!(info.code_hash, code.hash());
+        assert_eq!(info.nonce, 1);
+        assert_eq!(state.code_by_hash(code.hash()).bytes(), code.bytes());
+    }
+
+    #[test]
+    pub fn test_override_state() {
+        let (account, mut state) = overrides_state();
+        let overrides: StateOverride = [(
+            account,
+            AccountOverride {
+                state: Some([(2u64.into(), 22u64.into())].into()),
+                ..Default::default()
+            },
+        )]
+        .into();
+        state.apply_overrides(&overrides);
+
+        assert!(matches!(
+            state.accounts[&account].account_state,
+            AccountState::EVMStorageCleared
+        ));
+        assert_eq!(state.storage(account, 1u64.into()), 0.into());
+        assert_eq!(state.storage(account, 2u64.into()), 22u64.into());
+        assert_eq!(state.basic(account).balance, 100u64.into());
+    }
+
+    #[test]
+    pub fn test_override_state_diff() {
+        let (account, mut state) = overrides_state();
+        let overrides: StateOverride = [(
+            account,
+            AccountOverride {
+                state_diff: Some([(2u64.into(), 22u64.into())].into()),
+                ..Default::default()
+            },
+        )]
+        .into();
+        state.apply_overrides(&overrides);
+
+        assert_eq!(state.storage(account, 1u64.into()), 10u64.into());
+        assert_eq!(state.storage(account, 2u64.into()), 22u64.into());
+    }
+
+    #[test]
+    #[cfg(feature = "with-serde")]
+    pub fn test_serialization_is_deterministic() {
+        use primitive_types::H256;
+
+        let accounts: Vec<_> = (1..20u64).map(H160::from_low_u64_be).collect();
+        let build = |order: &mut dyn Iterator<Item = &H160>| {
+            let mut state = CacheDB::new(EmptyDB::default());
+            for account in order {
+                let code = Bytecode::new_raw(account.as_bytes().to_vec().into());
+                state.insert_account_info(*account, AccountInfo::new(1.into(), 0, code));
+                state.insert_account_storage(*account, account.to_low_u64_be().into(), 1.into());
+                state.block_hashes.insert(
+                    account.to_low_u64_be().into(),
+                    H256::from_low_u64_be(account.to_low_u64_be()),
+                );
+            }
+            serde_json::to_string(&state).unwrap()
+        };
+
+        let forward = build(&mut accounts.iter());
+        let backward = build(&mut accounts.iter().rev());
+        assert_eq!(forward, backward);
+
+        let restored: CacheDB<EmptyDB> = serde_json::from_str(&forward).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), forward);
+    }
+
+    #[test]
+    pub fn test_from_accounts() {
+        let account = H160::from_low_u64_be(42);
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        let info = AccountInfo {
+            nonce: 1,
+            code: Some(code.clone()),
+            ..Default::default()
+        };
+        let storage = [(1u64.into(), 10u64.into()), (2u64.into(), 20u64.into())].into();
+
+        let state = CacheDB::from_accounts(EmptyDB::default(), [(account, (info, storage))]);
+        let info = state.basic(account);
+        assert_eq!(info.nonce, 1);
+        assert_eq!(info.code_hash, code.hash());
+        assert_eq!(state.code_by_hash(code.hash()), code);
+        assert_eq!(state.storage(account, 1u64.into()), 10u64.into());
+        assert_eq!(state.storage(account, 2u64.into()), 20u64.into());
+        assert_eq!(state.storage(account, 3u64.into()), 0u64.into());
+    }
+
+    fn transfer(
+        state: &CacheDB<CacheDB<EmptyDB>>,
+        from: (H160, u64, u64),
+        to: (H160, u64),
+    ) -> Map<H160, Account> {
+        let mut changes = Map::new();
+        let mut sender = Account::from(super::DatabaseRef::basic(state, from.0));
+        sender.info.nonce = from.1;
+        sender.info.balance = from.2.into();
+        changes.insert(from.0, sender);
+        let mut receiver = Account::from(super::DatabaseRef::basic(state, to.0));
+        receiver.info.balance = to.1.into();
+        changes.insert(to.0, receiver);
+        changes
+    }
+
+    #[test]
+    pub fn test_commit_checked() {
+        let (account, mut state) = overrides_state();
+        let receiver = H160::from_low_u64_be(43);
+
+        // nonce regression.
+        let changes = transfer(&state, (account, 0, 60), (receiver, 40));
+        assert_eq!(
+            state.commit_checked(changes),
+            Err(CommitError::NonceDecreased {
+                address: account,
+                old: 1,
+                new: 0
+            })
+        );
+
+        // 100 - 120 wrapped around.
+        let (wrapped, _) = U256::from(100).overflowing_sub(U256::from(120));
+        let mut changes = transfer(&state, (account, 2, 0), (receiver, 120));
+        changes.get_mut(&account).unwrap().info.balance = wrapped;
+        assert_eq!(
+            state.commit_checked(changes),
+            Err(CommitError::BalanceUnderflow { address: account })
+        );
+        // nothing was applied.
+        assert_eq!(state.basic(account).nonce, 1);
+        assert_eq!(state.basic(receiver).balance, 0u64.into());
+
+        let changes = transfer(&state, (account, 2, 60), (receiver, 40));
+        assert_eq!(state.commit_checked(changes), Ok(()));
+        assert_eq!(state.basic(account).nonce, 2);
+        assert_eq!(state.basic(account).balance, 60u64.into());
+        assert_eq!(state.basic(receiver).balance, 40u64.into());
+
+        // minted balance, e.g. a block reward or a deposit, is fine.
+        let changes = transfer(&state, (account, 2, 60), (receiver, 1_000_040));
+        assert_eq!(state.commit_checked(changes), Ok(()));
+        assert_eq!(state.basic(receiver).balance, 1_000_040u64.into());
+    }
+
+    #[test]
+    pub fn test_check_invariants() {
+        let account = H160::from_low_u64_be(42);
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        let mut state = CacheDB::new(EmptyDB::default());
+        state.insert_account_info(account, AccountInfo::from_code(code.clone()));
+        assert_eq!(state.check_invariants(), Ok(()));
+
+        // code hash without code.
+        let info = &mut state.accounts.get_mut(&account).unwrap().info;
+        info.code = None;
+        assert_eq!(state.check_invariants(), Ok(()));
+        state.contracts.remove(&code.hash());
+        assert_eq!(
+            state.check_invariants(),
+            Err(vec![Inconsistency::MissingCode {
+                address: account,
+                code_hash: code.hash()
+            }])
+        );
+
+        // destroyed account with storage left over.
+        let mut state = CacheDB::new(EmptyDB::default());
+        let mut destroyed = Account::from(AccountInfo::from_balance(U256::one()));
+        destroyed.is_destroyed = true;
+        state.commit(Map::from([(account, destroyed)]));
+        assert_eq!(state.check_invariants(), Ok(()));
+        state.insert_account_storage(account, U256::one(), U256::one());
+        assert_eq!(
+            state.check_invariants(),
+            Err(vec![Inconsistency::StaleStorage { address: account }])
+        );
+    }
+
+    #[test]
+    pub fn test_commit_in_address_order() {
+        let low = H160::from_low_u64_be(1);
+        let high = H160::from_low_u64_be(2);
+        let mut state = CacheDB::new(EmptyDB::new());
+        for address in [low, high] {
+            state.insert_account_info(address, AccountInfo::new(U256::zero(), 5, Bytecode::new()));
+        }
+
+        // both accounts regress, the lowest address is reported whatever the map order is.
+        for order in [[low, high], [high, low]] {
+            let mut changes = Map::new();
+            for address in order {
+                let mut account = Account::from(AccountInfo::default());
+                account.info.nonce = 1;
+                changes.insert(address, account);
+            }
+            assert_eq!(
+                state.commit_checked(changes),
+                Err(CommitError::NonceDecreased {
+                    address: low,
+                    old: 5,
+                    new: 1
+                })
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_insert_code_from_code() {
+        let account = H160::from_low_u64_be(42);
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        let info = AccountInfo::from_code(code.clone());
+        assert_eq!(info.code_hash, code.hash());
+
+        let mut state = CacheDB::new(EmptyDB::default());
+        state.insert_account_info(account, info);
+        assert_eq!(state.basic(account).code_hash, code.hash());
+        assert_eq!(state.code_by_hash(code.hash()), code);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "does not match hash of the code")]
+    pub fn test_insert_code_with_stale_hash() {
+        let mut info = AccountInfo::from_code(Bytecode::new_raw(vec![0x00].into()));
+        info.code = Some(Bytecode::new_raw(vec![0x01, 0x00].into()));
+        CacheDB::new(EmptyDB::default()).insert_account_info(H160::zero(), info);
+    }
 }
@@ -0,0 +1,98 @@
+use super::DatabaseRef;
+use crate::{interpreter::bytecode::Bytecode, AccountInfo};
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use primitive_types::{H160, H256, U256};
+
+/// Database wrapper that sleeps for `latency` on every query and counts queries.
+///
+/// Meant for benchmarking, wrapped in [`super::CacheDB`] it simulates a slow backend and
+/// [`LatencyDB::hits`] tells how many queries were not served from the cache.
+#[derive(Debug)]
+pub struct LatencyDB<DB: DatabaseRef> {
+    pub db: DB,
+    pub latency: Duration,
+    hits: AtomicU64,
+}
+
+impl<DB: DatabaseRef> LatencyDB<DB> {
+    pub fn new(db: DB, latency: Duration) -> Self {
+        Self {
+            db,
+            latency,
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of queries that reached this database.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Set query counter back to zero.
+    pub fn reset_hits(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+    }
+
+    fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        if !self.latency.is_zero() {
+            std::thread::sleep(self.latency);
+        }
+    }
+}
+
+impl<DB: DatabaseRef> DatabaseRef for LatencyDB<DB> {
+    fn basic(&self, address: H160) -> AccountInfo {
+        self.hit();
+        self.db.basic(address)
+    }
+
+    fn code_by_hash(&self, code_hash: H256) -> Bytecode {
+        self.hit();
+        self.db.code_by_hash(code_hash)
+    }
+
+    fn storage(&self, address: H160, index: U256) -> U256 {
+        self.hit();
+        self.db.storage(address, index)
+    }
+
+    fn block_hash(&self, number: U256) -> H256 {
+        self.hit();
+        self.db.block_hash(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CacheDB, EmptyDB};
+    use crate::Database;
+    use std::time::Instant;
+
+    #[test]
+    fn cache_hides_latency() {
+        let latency = Duration::from_millis(5);
+        let mut cache = CacheDB::new(LatencyDB::new(EmptyDB::default(), latency));
+        let address = H160::from_low_u64_be(0x42);
+
+        let timer = Instant::now();
+        Database::basic(&mut cache, address);
+        Database::storage(&mut cache, address, U256::one());
+        assert!(timer.elapsed() >= 2 * latency);
+        assert_eq!(cache.db.hits(), 2);
+
+        for _ in 0..10 {
+            Database::basic(&mut cache, address);
+            Database::storage(&mut cache, address, U256::one());
+        }
+        assert_eq!(cache.db.hits(), 2);
+
+        cache.db.reset_hits();
+        Database::storage(&mut cache, address, U256::from(2));
+        assert_eq!(cache.db.hits(), 1);
+    }
+}
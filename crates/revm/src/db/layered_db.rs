@@ -0,0 +1,114 @@
+use super::DatabaseRef;
+use crate::{interpreter::bytecode::Bytecode, AccountInfo};
+use primitive_types::{H160, H256, U256};
+
+/// Database that layers `Top` over `Bottom`.
+///
+/// Reads are served by `Top` when it has the value (see [`DatabaseRef::try_basic`] and friends),
+/// otherwise they fall through to `Bottom`. Useful for state overrides, e.g. layering an
+/// [`crate::InMemoryDB`] with overridden accounts over the real state for `eth_call`.
+#[derive(Debug, Clone)]
+pub struct LayeredDB<Top: DatabaseRef, Bottom: DatabaseRef> {
+    pub top: Top,
+    pub bottom: Bottom,
+}
+
+impl<Top: DatabaseRef, Bottom: DatabaseRef> LayeredDB<Top, Bottom> {
+    pub fn new(top: Top, bottom: Bottom) -> Self {
+        Self { top, bottom }
+    }
+}
+
+impl<Top: DatabaseRef, Bottom: DatabaseRef> DatabaseRef for LayeredDB<Top, Bottom> {
+    fn basic(&self, address: H160) -> AccountInfo {
+        self.top
+            .try_basic(address)
+            .unwrap_or_else(|| self.bottom.basic(address))
+    }
+
+    fn code_by_hash(&self, code_hash: H256) -> Bytecode {
+        self.top
+            .try_code_by_hash(code_hash)
+            .unwrap_or_else(|| self.bottom.code_by_hash(code_hash))
+    }
+
+    fn storage(&self, address: H160, index: U256) -> U256 {
+        self.top
+            .try_storage(address, index)
+            .unwrap_or_else(|| self.bottom.storage(address, index))
+    }
+
+    fn block_hash(&self, number: U256) -> H256 {
+        self.top
+            .try_block_hash(number)
+            .unwrap_or_else(|| self.bottom.block_hash(number))
+    }
+
+    fn try_basic(&self, address: H160) -> Option<AccountInfo> {
+        self.top
+            .try_basic(address)
+            .or_else(|| self.bottom.try_basic(address))
+    }
+
+    fn try_code_by_hash(&self, code_hash: H256) -> Option<Bytecode> {
+        self.top
+            .try_code_by_hash(code_hash)
+            .or_else(|| self.bottom.try_code_by_hash(code_hash))
+    }
+
+    fn try_storage(&self, address: H160, index: U256) -> Option<U256> {
+        self.top
+            .try_storage(address, index)
+            .or_else(|| self.bottom.try_storage(address, index))
+    }
+
+    fn try_block_hash(&self, number: U256) -> Option<H256> {
+        self.top
+            .try_block_hash(number)
+            .or_else(|| self.bottom.try_block_hash(number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDB;
+
+    #[test]
+    fn reads_fall_through_to_bottom() {
+        let overridden = H160::from_low_u64_be(1);
+        let untouched = H160::from_low_u64_be(2);
+
+        let mut bottom = InMemoryDB::default();
+        for address in [overridden, untouched] {
+            bottom.insert_account_info(
+                address,
+                AccountInfo {
+                    nonce: 1,
+                    ..Default::default()
+                },
+            );
+            bottom.insert_account_storage(address, U256::zero(), U256::one());
+            bottom.insert_account_storage(address, U256::one(), U256::one());
+        }
+
+        let mut top = InMemoryDB::default();
+        top.insert_account_info(
+            overridden,
+            AccountInfo {
+                nonce: 5,
+                ..Default::default()
+            },
+        );
+        top.insert_account_storage(overridden, U256::zero(), U256::from(42));
+
+        let db = LayeredDB::new(top, bottom);
+        assert_eq!(db.basic(overridden).nonce, 5);
+        assert_eq!(db.basic(untouched).nonce, 1);
+        assert_eq!(db.storage(overridden, U256::zero()), U256::from(42));
+        assert_eq!(db.storage(overridden, U256::one()), U256::one());
+        assert_eq!(db.storage(untouched, U256::zero()), U256::one());
+        assert_eq!(db.try_basic(H160::from_low_u64_be(3)), None);
+        assert_eq!(db.basic(H160::from_low_u64_be(3)), AccountInfo::default());
+    }
+}
@@ -0,0 +1,204 @@
+use super::DatabaseRef;
+use crate::{interpreter::bytecode::Bytecode, Account, AccountInfo};
+use hashbrown::HashMap as Map;
+use primitive_types::{H160, H256, U256};
+
+/// Read only database that overlays changes of not yet mined transactions, as returned by
+/// [`crate::EVM::transact`], over `Base`.
+///
+/// Reads are answered from the overlay first and fall through to `Base` for everything it does
+/// not change. Unlike [`crate::db::CacheDB`] nothing is ever written to it by execution, drop
+/// it to discard the pending state.
+#[derive(Debug, Clone)]
+pub struct PendingOverlayDB<Base: DatabaseRef> {
+    pub base: Base,
+    pub changes: Map<H160, Account>,
+}
+
+impl<Base: DatabaseRef> PendingOverlayDB<Base> {
+    pub fn new(base: Base, changes: Map<H160, Account>) -> Self {
+        Self { base, changes }
+    }
+
+    /// Overlay changes of another pending transaction on top of the existing ones.
+    pub fn push(&mut self, changes: Map<H160, Account>) {
+        for (address, account) in changes {
+            match self.changes.get_mut(&address) {
+                Some(pending) if !account.is_destroyed && !account.storage_cleared => {
+                    // account destroyed by earlier changes is recreated without its storage.
+                    if pending.is_destroyed {
+                        pending.storage.clear();
+                        pending.storage_cleared = true;
+                        pending.is_destroyed = false;
+                        pending.selfdestruct_transfer = None;
+                    }
+                    pending.info = account.info;
+                    pending.is_touched |= account.is_touched;
+                    pending.storage.extend(account.storage);
+                }
+                _ => {
+                    self.changes.insert(address, account);
+                }
+            }
+        }
+    }
+}
+
+impl<Base: DatabaseRef> DatabaseRef for PendingOverlayDB<Base> {
+    fn basic(&self, address: H160) -> AccountInfo {
+        match self.changes.get(&address) {
+            Some(account) if account.is_destroyed => AccountInfo::default(),
+            Some(account) => account.info.clone(),
+            None => self.base.basic(address),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: H256) -> Bytecode {
+        self.changes
+            .values()
+            .filter(|account| account.info.code_hash == code_hash)
+            .find_map(|account| account.info.code.clone())
+            .unwrap_or_else(|| self.base.code_by_hash(code_hash))
+    }
+
+    fn storage(&self, address: H160, index: U256) -> U256 {
+        match self.changes.get(&address) {
+            Some(account) if account.is_destroyed => U256::zero(),
+            Some(account) => match account.storage.get(&index) {
+                Some(slot) => slot.present_value(),
+                None if account.storage_cleared => U256::zero(),
+                None => self.base.storage(address, index),
+            },
+            None => self.base.storage(address, index),
+        }
+    }
+
+    fn block_hash(&self, number: U256) -> H256 {
+        self.base.block_hash(number)
+    }
+
+    fn try_basic(&self, address: H160) -> Option<AccountInfo> {
+        match self.changes.get(&address) {
+            Some(account) if account.is_destroyed => Some(AccountInfo::default()),
+            Some(account) => Some(account.info.clone()),
+            None => self.base.try_basic(address),
+        }
+    }
+
+    fn try_code_by_hash(&self, code_hash: H256) -> Option<Bytecode> {
+        self.changes
+            .values()
+            .filter(|account| account.info.code_hash == code_hash)
+            .find_map(|account| account.info.code.clone())
+            .or_else(|| self.base.try_code_by_hash(code_hash))
+    }
+
+    fn try_storage(&self, address: H160, index: U256) -> Option<U256> {
+        match self.changes.get(&address) {
+            Some(account) if account.is_destroyed => Some(U256::zero()),
+            Some(account) => match account.storage.get(&index) {
+                Some(slot) => Some(slot.present_value()),
+                None if account.storage_cleared => Some(U256::zero()),
+                None => self.base.try_storage(address, index),
+            },
+            None => self.base.try_storage(address, index),
+        }
+    }
+
+    fn try_block_hash(&self, number: U256) -> Option<H256> {
+        self.base.try_block_hash(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{EmptyDB, LayeredDB},
+        InMemoryDB, StorageSlot,
+    };
+
+    #[test]
+    fn overlay_takes_precedence() {
+        let pending = H160::from_low_u64_be(1);
+        let untouched = H160::from_low_u64_be(2);
+        let mut base = InMemoryDB::default();
+        for address in [pending, untouched] {
+            base.insert_account_info(
+                address,
+                AccountInfo::new(U256::from(10), 1, Bytecode::new()),
+            );
+            base.insert_account_storage(address, U256::zero(), U256::one());
+            base.insert_account_storage(address, U256::one(), U256::one());
+        }
+
+        let mut account = Account::from(AccountInfo::new(U256::from(7), 2, Bytecode::new()));
+        account.storage.insert(
+            U256::zero(),
+            StorageSlot::new_changed(U256::one(), U256::from(42)),
+        );
+        let db = PendingOverlayDB::new(&base, vec![(pending, account)].into_iter().collect());
+
+        assert_eq!(db.basic(pending).balance, U256::from(7));
+        assert_eq!(db.basic(untouched).balance, U256::from(10));
+        assert_eq!(db.storage(pending, U256::zero()), U256::from(42));
+        assert_eq!(db.storage(pending, U256::one()), U256::one());
+        assert_eq!(db.storage(untouched, U256::zero()), U256::one());
+        // base is never written to
+        assert_eq!(base.basic(pending).balance, U256::from(10));
+
+        let mut db = db;
+        let mut destroyed = Account::from(AccountInfo::default());
+        destroyed.is_destroyed = true;
+        db.push(vec![(pending, destroyed)].into_iter().collect());
+        assert_eq!(db.basic(pending), AccountInfo::default());
+        assert_eq!(db.storage(pending, U256::one()), U256::zero());
+    }
+
+    #[test]
+    fn destroyed_account_is_recreated() {
+        let address = H160::from_low_u64_be(1);
+        let mut base = InMemoryDB::default();
+        base.insert_account_info(address, AccountInfo::from_balance(U256::from(10)));
+        base.insert_account_storage(address, U256::zero(), U256::one());
+
+        let mut destroyed = Account::from(AccountInfo::default());
+        destroyed.is_destroyed = true;
+        destroyed.storage.insert(
+            U256::one(),
+            StorageSlot::new_changed(U256::zero(), U256::from(5)),
+        );
+        let mut db = PendingOverlayDB::new(&base, vec![(address, destroyed)].into_iter().collect());
+
+        let mut recreated = Account::from(AccountInfo::from_balance(U256::from(3)));
+        recreated.storage.insert(
+            U256::from(2),
+            StorageSlot::new_changed(U256::zero(), U256::from(7)),
+        );
+        db.push(vec![(address, recreated)].into_iter().collect());
+        assert_eq!(db.basic(address).balance, U256::from(3));
+        assert_eq!(db.storage(address, U256::from(2)), U256::from(7));
+        // storage from before it was destroyed is gone.
+        assert_eq!(db.storage(address, U256::zero()), U256::zero());
+        assert_eq!(db.storage(address, U256::one()), U256::zero());
+    }
+
+    #[test]
+    fn falls_through_when_layered() {
+        let pending = H160::from_low_u64_be(1);
+        let untouched = H160::from_low_u64_be(2);
+        let mut bottom = InMemoryDB::default();
+        bottom.insert_account_info(untouched, AccountInfo::from_balance(U256::from(10)));
+        bottom.insert_account_storage(untouched, U256::zero(), U256::one());
+
+        let account = Account::from(AccountInfo::from_balance(U256::from(7)));
+        let top = PendingOverlayDB::new(
+            EmptyDB::default(),
+            vec![(pending, account)].into_iter().collect(),
+        );
+        let db = LayeredDB::new(top, &bottom);
+        assert_eq!(db.basic(pending).balance, U256::from(7));
+        assert_eq!(db.basic(untouched).balance, U256::from(10));
+        assert_eq!(db.storage(untouched, U256::zero()), U256::one());
+    }
+}
@@ -0,0 +1,226 @@
+use super::Database;
+use crate::{interpreter::bytecode::Bytecode, AccountInfo};
+use alloc::vec::Vec;
+use hashbrown::HashMap as Map;
+use primitive_types::{H160, H256, U256};
+
+/// Single database query together with its answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DbQuery {
+    Basic {
+        address: H160,
+        info: AccountInfo,
+    },
+    CodeByHash {
+        code_hash: H256,
+        code: Bytecode,
+    },
+    Storage {
+        address: H160,
+        index: U256,
+        value: U256,
+    },
+    BlockHash {
+        number: U256,
+        hash: H256,
+    },
+}
+
+/// Database wrapper that records every query made to `DB` and its answer.
+///
+/// The recorded journal can be serialized and later served by [`ReplayDB`] to reproduce an
+/// execution without the original backend.
+#[derive(Debug, Clone)]
+pub struct RecorderDB<DB: Database> {
+    pub db: DB,
+    pub journal: Vec<DbQuery>,
+}
+
+impl<DB: Database> RecorderDB<DB> {
+    pub fn new(db: DB) -> Self {
+        Self {
+            db,
+            journal: Vec::new(),
+        }
+    }
+
+    /// Consume the recorder and return the recorded queries in the order they were made.
+    pub fn into_journal(self) -> Vec<DbQuery> {
+        self.journal
+    }
+}
+
+impl<DB: Database> Database for RecorderDB<DB> {
+    fn basic(&mut self, address: H160) -> AccountInfo {
+        let info = self.db.basic(address);
+        self.journal.push(DbQuery::Basic {
+            address,
+            info: info.clone(),
+        });
+        info
+    }
+
+    fn code_by_hash(&mut self, code_hash: H256) -> Bytecode {
+        let code = self.db.code_by_hash(code_hash);
+        self.journal.push(DbQuery::CodeByHash {
+            code_hash,
+            code: code.clone(),
+        });
+        code
+    }
+
+    fn storage(&mut self, address: H160, index: U256) -> U256 {
+        let value = self.db.storage(address, index);
+        self.journal.push(DbQuery::Storage {
+            address,
+            index,
+            value,
+        });
+        value
+    }
+
+    fn block_hash(&mut self, number: U256) -> H256 {
+        let hash = self.db.block_hash(number);
+        self.journal.push(DbQuery::BlockHash { number, hash });
+        hash
+    }
+}
+
+/// Database that serves answers from a journal recorded by [`RecorderDB`].
+///
+/// Panics on any query that was not recorded, which means that execution diverged from
+/// the recorded one.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayDB {
+    accounts: Map<H160, AccountInfo>,
+    contracts: Map<H256, Bytecode>,
+    storage: Map<(H160, U256), U256>,
+    block_hashes: Map<U256, H256>,
+}
+
+impl ReplayDB {
+    pub fn new(journal: impl IntoIterator<Item = DbQuery>) -> Self {
+        let mut db = Self::default();
+        for query in journal {
+            match query {
+                DbQuery::Basic { address, info } => {
+                    db.accounts.insert(address, info);
+                }
+                DbQuery::CodeByHash { code_hash, code } => {
+                    db.contracts.insert(code_hash, code);
+                }
+                DbQuery::Storage {
+                    address,
+                    index,
+                    value,
+                } => {
+                    db.storage.insert((address, index), value);
+                }
+                DbQuery::BlockHash { number, hash } => {
+                    db.block_hashes.insert(number, hash);
+                }
+            }
+        }
+        db
+    }
+}
+
+impl Database for ReplayDB {
+    fn basic(&mut self, address: H160) -> AccountInfo {
+        match self.accounts.get(&address) {
+            Some(info) => info.clone(),
+            None => panic!("ReplayDB: unexpected basic query for {:?}", address),
+        }
+    }
+
+    fn code_by_hash(&mut self, code_hash: H256) -> Bytecode {
+        match self.contracts.get(&code_hash) {
+            Some(code) => code.clone(),
+            None => panic!(
+                "ReplayDB: unexpected code_by_hash query for {:?}",
+                code_hash
+            ),
+        }
+    }
+
+    fn storage(&mut self, address: H160, index: U256) -> U256 {
+        match self.storage.get(&(address, index)) {
+            Some(value) => *value,
+            None => panic!(
+                "ReplayDB: unexpected storage query for {:?} at {}",
+                address, index
+            ),
+        }
+    }
+
+    fn block_hash(&mut self, number: U256) -> H256 {
+        match self.block_hashes.get(&number) {
+            Some(hash) => *hash,
+            None => panic!("ReplayDB: unexpected block_hash query for {}", number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDB, Return, TransactOut, TransactTo};
+    use bytes::Bytes;
+
+    #[test]
+    fn record_and_replay() {
+        let contract = H160::from_low_u64_be(0x42);
+        let mut db = InMemoryDB::default();
+        // PUSH1 0x00, SLOAD, PUSH1 0x01, BLOCKHASH, ADD, PUSH1 0x00, MSTORE, PUSH1 0x20,
+        // PUSH1 0x00, RETURN
+        db.insert_account_info(
+            contract,
+            AccountInfo::new(
+                U256::zero(),
+                0,
+                Bytecode::new_raw(Bytes::from(vec![
+                    0x60, 0x00, 0x54, 0x60, 0x01, 0x40, 0x01, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60,
+                    0x00, 0xf3,
+                ])),
+            ),
+        );
+        db.insert_account_storage(contract, U256::zero(), U256::from(7));
+
+        let mut evm = crate::new();
+        evm.database(RecorderDB::new(db));
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 1_000_000;
+        evm.env.block.number = U256::from(2);
+        let (recorded, _) = evm.transact();
+        assert_eq!(recorded.exit_reason, Return::Return);
+        let journal = evm.db.take().unwrap().into_journal();
+        assert!(journal.contains(&DbQuery::Storage {
+            address: contract,
+            index: U256::zero(),
+            value: U256::from(7),
+        }));
+
+        let mut evm = crate::new();
+        evm.database(ReplayDB::new(journal));
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 1_000_000;
+        evm.env.block.number = U256::from(2);
+        let (replayed, _) = evm.transact();
+        assert_eq!(replayed.exit_reason, recorded.exit_reason);
+        assert_eq!(replayed.gas_used, recorded.gas_used);
+        match (recorded.out, replayed.out) {
+            (TransactOut::Call(recorded), TransactOut::Call(replayed)) => {
+                assert_eq!(recorded, replayed)
+            }
+            _ => panic!("expected call output"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected storage query")]
+    fn replay_panics_on_unexpected_query() {
+        let mut db = ReplayDB::new(Vec::new());
+        db.storage(H160::zero(), U256::zero());
+    }
+}
@@ -0,0 +1,187 @@
+//! Differences between cached state of two [`CacheDB`]s.
+
+use super::{AccountState, CacheDB, DatabaseRef, DbAccount};
+use crate::{interpreter::bytecode::Bytecode, AccountInfo};
+use alloc::collections::BTreeMap;
+use primitive_types::{H160, H256, U256};
+
+/// Changes that turn cached state of one [`CacheDB`] into another. Created by
+/// [`CacheDB::diff`] and applied with [`CacheDB::apply_diff`] or undone with
+/// [`CacheDB::revert_diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// Accounts that differ, by address.
+    pub accounts: BTreeMap<H160, AccountDiff>,
+    /// Code that is cached only in the target state.
+    pub contracts: BTreeMap<H256, Bytecode>,
+}
+
+/// Change of a single cached account. `None` means that account or slot is not cached on
+/// that side.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub old: Option<(AccountInfo, AccountState)>,
+    pub new: Option<(AccountInfo, AccountState)>,
+    /// Storage slots that differ, as `(old, new)`.
+    pub storage: BTreeMap<U256, (Option<U256>, Option<U256>)>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.contracts.is_empty()
+    }
+}
+
+impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
+    /// Diff of cached accounts, storage and contracts from `self` to `target`. Logs, block
+    /// hashes and the wrapped databases are not compared.
+    pub fn diff<OtherDB: DatabaseRef>(&self, target: &CacheDB<OtherDB>) -> StateDiff {
+        let mut diff = StateDiff::default();
+        for (address, account) in &self.accounts {
+            let target_account = target.accounts.get(address);
+            if let Some(account_diff) = diff_account(Some(account), target_account) {
+                diff.accounts.insert(*address, account_diff);
+            }
+        }
+        for (address, account) in &target.accounts {
+            if !self.accounts.contains_key(address) {
+                if let Some(account_diff) = diff_account(None, Some(account)) {
+                    diff.accounts.insert(*address, account_diff);
+                }
+            }
+        }
+        for (hash, code) in &target.contracts {
+            if !self.contracts.contains_key(hash) {
+                diff.contracts.insert(*hash, code.clone());
+            }
+        }
+        diff
+    }
+
+    /// Apply changes of `diff`, turning the base state of [`CacheDB::diff`] into the target.
+    pub fn apply_diff(&mut self, diff: &StateDiff) {
+        for (hash, code) in &diff.contracts {
+            self.contracts.insert(*hash, code.clone());
+        }
+        for (address, account_diff) in &diff.accounts {
+            let storage = account_diff
+                .storage
+                .iter()
+                .map(|(index, (_, new))| (*index, *new));
+            self.set_account(*address, account_diff.new.as_ref(), storage);
+        }
+    }
+
+    /// Undo changes of `diff`, turning the target state of [`CacheDB::diff`] into the base.
+    /// Contracts are kept, code by hash never changes.
+    pub fn revert_diff(&mut self, diff: &StateDiff) {
+        for (address, account_diff) in &diff.accounts {
+            let storage = account_diff
+                .storage
+                .iter()
+                .map(|(index, (old, _))| (*index, *old));
+            self.set_account(*address, account_diff.old.as_ref(), storage);
+        }
+    }
+
+    fn set_account(
+        &mut self,
+        address: H160,
+        account: Option<&(AccountInfo, AccountState)>,
+        storage: impl Iterator<Item = (U256, Option<U256>)>,
+    ) {
+        let (info, account_state) = match account {
+            Some(account) => account,
+            None => {
+                self.accounts.remove(&address);
+                return;
+            }
+        };
+        let db_account = self.accounts.entry(address).or_default();
+        db_account.info = info.clone();
+        db_account.account_state = account_state.clone();
+        for (index, value) in storage {
+            match value {
+                Some(value) => db_account.storage.insert(index, value),
+                None => db_account.storage.remove(&index),
+            };
+        }
+    }
+}
+
+fn diff_account(old: Option<&DbAccount>, new: Option<&DbAccount>) -> Option<AccountDiff> {
+    let header = |account: &DbAccount| (account.info.clone(), account.account_state.clone());
+    let mut diff = AccountDiff {
+        old: old.map(header),
+        new: new.map(header),
+        storage: BTreeMap::new(),
+    };
+    let empty = BTreeMap::new();
+    let old_storage = old.map_or(&empty, |account| &account.storage);
+    let new_storage = new.map_or(&empty, |account| &account.storage);
+    for (index, value) in old_storage {
+        let new_value = new_storage.get(index).copied();
+        if new_value != Some(*value) {
+            diff.storage.insert(*index, (Some(*value), new_value));
+        }
+    }
+    for (index, value) in new_storage {
+        if !old_storage.contains_key(index) {
+            diff.storage.insert(*index, (None, Some(*value)));
+        }
+    }
+
+    // `AccountInfo` equality ignores inline code, cached accounts never have it.
+    if diff.old == diff.new && diff.storage.is_empty() {
+        None
+    } else {
+        Some(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryDB;
+    use bytes::Bytes;
+
+    fn base() -> InMemoryDB {
+        let mut db = InMemoryDB::default();
+        for i in 1..4u64 {
+            let address = H160::from_low_u64_be(i);
+            db.insert_account_info(address, AccountInfo::new(U256::from(i), i, Bytecode::new()));
+            db.insert_account_storage(address, U256::from(i), U256::from(i * 10));
+        }
+        db
+    }
+
+    #[test]
+    fn diff_round_trip() {
+        let base = base();
+        let mut target = base.clone();
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x60, 0x00, 0x00]));
+        target.insert_account_info(
+            H160::from_low_u64_be(1),
+            AccountInfo::new(U256::from(100), 7, code),
+        );
+        target.insert_account_storage(H160::from_low_u64_be(2), U256::from(2), U256::from(5));
+        target.insert_account_storage(H160::from_low_u64_be(2), U256::from(9), U256::from(9));
+        target.accounts.remove(&H160::from_low_u64_be(3));
+        target.insert_account_info(H160::from_low_u64_be(4), AccountInfo::default());
+
+        let diff = base.diff(&target);
+        assert_eq!(diff.accounts.len(), 4);
+        assert_eq!(diff.contracts.len(), 1);
+
+        let mut applied = base.clone();
+        applied.apply_diff(&diff);
+        assert_eq!(applied.serialize_cache(), target.serialize_cache());
+        assert!(applied.diff(&target).is_empty());
+
+        applied.revert_diff(&diff);
+        let mut base_with_code = base.clone();
+        base_with_code.contracts.extend(diff.contracts.clone());
+        assert_eq!(applied.serialize_cache(), base_with_code.serialize_cache());
+        assert!(base.diff(&base).is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+use super::DatabaseRef;
+use crate::{interpreter::bytecode::Bytecode, AccountInfo, KECCAK_EMPTY};
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+use rlp::{Decodable, Rlp};
+use sha3::{Digest, Keccak256};
+
+/// Root of an empty trie, `keccak256(rlp(""))`.
+pub const EMPTY_ROOT: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Database that reads state from Merkle Patricia Trie nodes.
+///
+/// `nodes` resolves a node hash to its RLP encoding and `code` resolves a code hash to the
+/// contract code. Accounts and storage are read by walking the tries from `state_root`. Block
+/// hashes are not part of the state, `block_hash` always returns zero.
+///
+/// Panics if a node that is referenced by the trie can't be resolved or is malformed.
+pub struct TrieDB<N, C>
+where
+    N: Fn(H256) -> Option<Vec<u8>>,
+    C: Fn(H256) -> Option<Bytecode>,
+{
+    pub state_root: H256,
+    nodes: N,
+    code: C,
+}
+
+impl<N, C> TrieDB<N, C>
+where
+    N: Fn(H256) -> Option<Vec<u8>>,
+    C: Fn(H256) -> Option<Bytecode>,
+{
+    pub fn new(state_root: H256, nodes: N, code: C) -> Self {
+        Self {
+            state_root,
+            nodes,
+            code,
+        }
+    }
+
+    /// Storage root of the account, or [`EMPTY_ROOT`] if account does not exist.
+    pub fn storage_root(&self, address: H160) -> H256 {
+        match self.account(address) {
+            Some((_, storage_root)) => storage_root,
+            None => EMPTY_ROOT,
+        }
+    }
+
+    fn account(&self, address: H160) -> Option<(AccountInfo, H256)> {
+        let leaf = self.get(self.state_root, &Keccak256::digest(address.as_bytes()))?;
+        let account = Rlp::new(&leaf);
+        let info = AccountInfo {
+            nonce: field(&account, 0),
+            balance: field(&account, 1),
+            code_hash: field(&account, 3),
+            code: None,
+        };
+        Some((info, field(&account, 2)))
+    }
+
+    /// Returns value of the leaf at `key` in trie with `root`.
+    fn get(&self, root: H256, key: &[u8]) -> Option<Vec<u8>> {
+        if root == EMPTY_ROOT {
+            return None;
+        }
+        let nibbles: Vec<u8> = key
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0x0f])
+            .collect();
+        let mut path = &nibbles[..];
+        let mut node = self.resolve(root);
+        loop {
+            let rlp = Rlp::new(&node);
+            let next = match rlp.item_count() {
+                Ok(17) => match path.split_first() {
+                    Some((nibble, rest)) => {
+                        path = rest;
+                        item(&rlp, *nibble as usize)
+                    }
+                    None => {
+                        let value = data(&item(&rlp, 16));
+                        return (!value.is_empty()).then(|| value.to_vec());
+                    }
+                },
+                Ok(2) => {
+                    let (partial, is_leaf) = decode_path(data(&item(&rlp, 0)));
+                    if is_leaf {
+                        return (partial == path).then(|| data(&item(&rlp, 1)).to_vec());
+                    }
+                    if !path.starts_with(&partial) {
+                        return None;
+                    }
+                    path = &path[partial.len()..];
+                    item(&rlp, 1)
+                }
+                _ => panic!("TrieDB: malformed trie node"),
+            };
+            node = if next.is_list() {
+                // nodes shorter than 32 bytes are embedded in their parent.
+                next.as_raw().to_vec()
+            } else {
+                match data(&next) {
+                    [] => return None,
+                    hash if hash.len() == 32 => self.resolve(H256::from_slice(hash)),
+                    _ => panic!("TrieDB: malformed trie node reference"),
+                }
+            };
+        }
+    }
+
+    fn resolve(&self, hash: H256) -> Vec<u8> {
+        match (self.nodes)(hash) {
+            Some(node) => node,
+            None => panic!("TrieDB: missing trie node {:?}", hash),
+        }
+    }
+}
+
+fn field<T: Decodable>(account: &Rlp, index: usize) -> T {
+    account
+        .val_at(index)
+        .unwrap_or_else(|_| panic!("TrieDB: malformed account"))
+}
+
+fn item<'a>(rlp: &Rlp<'a>, index: usize) -> Rlp<'a> {
+    rlp.at(index)
+        .unwrap_or_else(|_| panic!("TrieDB: malformed trie node"))
+}
+
+fn data<'a>(rlp: &Rlp<'a>) -> &'a [u8] {
+    rlp.data()
+        .unwrap_or_else(|_| panic!("TrieDB: malformed trie node"))
+}
+
+/// Decodes hex prefix encoded path, returns its nibbles and whether node is a leaf.
+fn decode_path(encoded: &[u8]) -> (Vec<u8>, bool) {
+    let (flag, rest) = match encoded.split_first() {
+        Some(split) => split,
+        None => panic!("TrieDB: malformed trie node path"),
+    };
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 0x10 != 0 {
+        nibbles.push(flag & 0x0f);
+    }
+    nibbles.extend(rest.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]));
+    (nibbles, flag & 0x20 != 0)
+}
+
+impl<N, C> DatabaseRef for TrieDB<N, C>
+where
+    N: Fn(H256) -> Option<Vec<u8>>,
+    C: Fn(H256) -> Option<Bytecode>,
+{
+    fn basic(&self, address: H160) -> AccountInfo {
+        self.account(address)
+            .map(|(info, _)| info)
+            .unwrap_or_default()
+    }
+
+    fn code_by_hash(&self, code_hash: H256) -> Bytecode {
+        if code_hash == KECCAK_EMPTY {
+            return Bytecode::new();
+        }
+        match (self.code)(code_hash) {
+            Some(code) => code,
+            None => panic!("TrieDB: missing code {:?}", code_hash),
+        }
+    }
+
+    fn storage(&self, address: H160, index: U256) -> U256 {
+        let mut key = [0u8; 32];
+        index.to_big_endian(&mut key);
+        match self.get(self.storage_root(address), &Keccak256::digest(key)) {
+            Some(value) => Rlp::new(&value)
+                .as_val()
+                .unwrap_or_else(|_| panic!("TrieDB: malformed storage value")),
+            None => U256::zero(),
+        }
+    }
+
+    fn block_hash(&self, _number: U256) -> H256 {
+        H256::zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use hashbrown::HashMap as Map;
+    use rlp::RlpStream;
+
+    /// Builds a trie from `(key nibbles, value)` pairs, stores hashed nodes in `nodes` and
+    /// returns RLP of the root node.
+    fn build(items: &[(Vec<u8>, Vec<u8>)], nodes: &mut Map<H256, Vec<u8>>) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+        if let [(path, value)] = items {
+            stream.begin_list(2);
+            stream.append(&encode_path(path, true));
+            stream.append(value);
+            return stream.out().to_vec();
+        }
+
+        let prefix_len = (0..)
+            .take_while(|&i| {
+                items
+                    .iter()
+                    .all(|(path, _)| path.len() > i && path[i] == items[0].0[i])
+            })
+            .count();
+        if prefix_len > 0 {
+            let rest: Vec<_> = items
+                .iter()
+                .map(|(path, value)| (path[prefix_len..].to_vec(), value.clone()))
+                .collect();
+            stream.begin_list(2);
+            stream.append(&encode_path(&items[0].0[..prefix_len], false));
+            append_child(&mut stream, build(&rest, nodes), nodes);
+            return stream.out().to_vec();
+        }
+
+        stream.begin_list(17);
+        for nibble in 0..16 {
+            let children: Vec<_> = items
+                .iter()
+                .filter(|(path, _)| path.first() == Some(&nibble))
+                .map(|(path, value)| (path[1..].to_vec(), value.clone()))
+                .collect();
+            if children.is_empty() {
+                stream.append_empty_data();
+            } else {
+                append_child(&mut stream, build(&children, nodes), nodes);
+            }
+        }
+        match items.iter().find(|(path, _)| path.is_empty()) {
+            Some((_, value)) => stream.append(value),
+            None => stream.append_empty_data(),
+        };
+        stream.out().to_vec()
+    }
+
+    fn append_child(stream: &mut RlpStream, node: Vec<u8>, nodes: &mut Map<H256, Vec<u8>>) {
+        if node.len() < 32 {
+            stream.append_raw(&node, 1);
+        } else {
+            stream.append(&insert_node(node, nodes).as_bytes());
+        }
+    }
+
+    fn insert_node(node: Vec<u8>, nodes: &mut Map<H256, Vec<u8>>) -> H256 {
+        let hash = H256::from_slice(&Keccak256::digest(&node));
+        nodes.insert(hash, node);
+        hash
+    }
+
+    fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+        let flag = if is_leaf { 0x20 } else { 0x00 };
+        let mut out = if nibbles.len() % 2 == 1 {
+            vec![flag | 0x10 | nibbles[0]]
+        } else {
+            vec![flag]
+        };
+        let even = &nibbles[nibbles.len() % 2..];
+        out.extend(even.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+        out
+    }
+
+    fn nibbles(key: &[u8]) -> Vec<u8> {
+        key.iter()
+            .flat_map(|byte| [byte >> 4, byte & 0x0f])
+            .collect()
+    }
+
+    fn build_root(items: &[(Vec<u8>, Vec<u8>)], nodes: &mut Map<H256, Vec<u8>>) -> H256 {
+        let items: Vec<_> = items
+            .iter()
+            .map(|(key, value)| (nibbles(key), value.clone()))
+            .collect();
+        let root = build(&items, nodes);
+        insert_node(root, nodes)
+    }
+
+    fn no_code(_: H256) -> Option<Bytecode> {
+        None
+    }
+
+    #[test]
+    fn walks_hand_built_trie() {
+        // trie from the Ethereum wiki, checks that test trie builder matches the spec.
+        let mut nodes = Map::new();
+        let root = build_root(
+            &[
+                (b"doe".to_vec(), b"reindeer".to_vec()),
+                (b"dog".to_vec(), b"puppy".to_vec()),
+                (b"dogglesworth".to_vec(), b"cat".to_vec()),
+            ],
+            &mut nodes,
+        );
+        assert_eq!(
+            root,
+            "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"
+                .parse()
+                .unwrap()
+        );
+        let db = TrieDB::new(root, |hash| nodes.get(&hash).cloned(), no_code);
+        assert_eq!(db.get(root, b"dog"), Some(b"puppy".to_vec()));
+        assert_eq!(db.get(root, b"dogglesworth"), Some(b"cat".to_vec()));
+        assert_eq!(db.get(root, b"doe"), Some(b"reindeer".to_vec()));
+        assert_eq!(db.get(root, b"do"), None);
+        assert_eq!(db.get(root, b"cat"), None);
+    }
+
+    #[test]
+    fn reads_accounts_and_storage() {
+        let mut nodes = Map::new();
+        let storage_slots = [(U256::from(1), U256::from(7)), (U256::MAX, U256::from(8))];
+        let storage: Vec<_> = storage_slots
+            .iter()
+            .map(|(index, value)| {
+                let mut key = [0u8; 32];
+                index.to_big_endian(&mut key);
+                (Keccak256::digest(key).to_vec(), rlp::encode(value).to_vec())
+            })
+            .collect();
+        let storage_root = build_root(&storage, &mut nodes);
+
+        let code = Bytecode::new_raw(Bytes::from(vec![0x60, 0x01, 0x00]));
+        let contract = H160::from_low_u64_be(0x42);
+        let eoa = H160::from_low_u64_be(0x43);
+        let account = |nonce: u64, balance: u64, storage_root: H256, code_hash: H256| {
+            let mut stream = RlpStream::new_list(4);
+            stream.append(&nonce);
+            stream.append(&U256::from(balance));
+            stream.append(&storage_root);
+            stream.append(&code_hash);
+            stream.out().to_vec()
+        };
+        let state = [
+            (
+                Keccak256::digest(contract.as_bytes()).to_vec(),
+                account(1, 100, storage_root, code.hash()),
+            ),
+            (
+                Keccak256::digest(eoa.as_bytes()).to_vec(),
+                account(5, 200, EMPTY_ROOT, KECCAK_EMPTY),
+            ),
+        ];
+        let state_root = build_root(&state, &mut nodes);
+
+        let code_store = code.clone();
+        let db = TrieDB::new(
+            state_root,
+            |hash| nodes.get(&hash).cloned(),
+            move |hash| (hash == code_store.hash()).then(|| code_store.clone()),
+        );
+
+        let info = db.basic(contract);
+        assert_eq!((info.nonce, info.balance), (1, U256::from(100)));
+        assert_eq!(db.code_by_hash(info.code_hash), code);
+        assert_eq!(db.storage(contract, U256::from(1)), U256::from(7));
+        assert_eq!(db.storage(contract, U256::MAX), U256::from(8));
+        assert_eq!(db.storage(contract, U256::from(2)), U256::zero());
+
+        let info = db.basic(eoa);
+        assert_eq!((info.nonce, info.balance), (5, U256::from(200)));
+        assert_eq!(info.code_hash, KECCAK_EMPTY);
+        assert_eq!(db.storage(eoa, U256::from(1)), U256::zero());
+
+        assert_eq!(
+            db.basic(H160::from_low_u64_be(0x44)),
+            AccountInfo::default()
+        );
+    }
+}
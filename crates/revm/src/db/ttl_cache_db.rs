@@ -0,0 +1,120 @@
+use super::{DatabaseRef, HistoryError};
+use crate::{interpreter::bytecode::Bytecode, AccountInfo};
+use core::{hash::Hash, time::Duration};
+use hashbrown::HashMap as Map;
+use primitive_types::{H160, H256, U256};
+use std::{sync::Mutex, time::Instant};
+
+/// Read through cache over `DB` whose entries expire `ttl` after they were fetched.
+///
+/// Meant for forks of a backend whose state can still change, e.g. a pending block. Accounts,
+/// code and storage slots expire independently and are fetched again from `DB` on the first
+/// read after they expired. Block hashes and historical reads are passed through uncached.
+#[derive(Debug)]
+pub struct TtlCacheDB<DB: DatabaseRef> {
+    pub db: DB,
+    pub ttl: Duration,
+    accounts: Mutex<Map<H160, (Instant, AccountInfo)>>,
+    contracts: Mutex<Map<H256, (Instant, Bytecode)>>,
+    storage: Mutex<Map<(H160, U256), (Instant, U256)>>,
+}
+
+impl<DB: DatabaseRef> TtlCacheDB<DB> {
+    pub fn with_ttl(db: DB, ttl: Duration) -> Self {
+        Self {
+            db,
+            ttl,
+            accounts: Mutex::new(Map::new()),
+            contracts: Mutex::new(Map::new()),
+            storage: Mutex::new(Map::new()),
+        }
+    }
+
+    /// Drop all cached entries, the next reads are fetched from `DB`.
+    pub fn clear(&self) {
+        self.accounts.lock().unwrap().clear();
+        self.contracts.lock().unwrap().clear();
+        self.storage.lock().unwrap().clear();
+    }
+
+    fn cached<K: Eq + Hash, V: Clone>(
+        &self,
+        cache: &Mutex<Map<K, (Instant, V)>>,
+        key: K,
+        fetch: impl FnOnce() -> V,
+    ) -> V {
+        if let Some((fetched, value)) = cache.lock().unwrap().get(&key) {
+            if fetched.elapsed() < self.ttl {
+                return value.clone();
+            }
+        }
+        // lock is not held while fetching, so a slow backend does not block other reads.
+        let value = fetch();
+        cache
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), value.clone()));
+        value
+    }
+}
+
+impl<DB: DatabaseRef> DatabaseRef for TtlCacheDB<DB> {
+    fn basic(&self, address: H160) -> AccountInfo {
+        self.cached(&self.accounts, address, || self.db.basic(address))
+    }
+
+    fn code_by_hash(&self, code_hash: H256) -> Bytecode {
+        self.cached(&self.contracts, code_hash, || {
+            self.db.code_by_hash(code_hash)
+        })
+    }
+
+    fn storage(&self, address: H160, index: U256) -> U256 {
+        self.cached(&self.storage, (address, index), || {
+            self.db.storage(address, index)
+        })
+    }
+
+    fn block_hash(&self, number: U256) -> H256 {
+        self.db.block_hash(number)
+    }
+
+    fn basic_at(&self, address: H160, number: U256) -> Result<AccountInfo, HistoryError> {
+        self.db.basic_at(address, number)
+    }
+
+    fn storage_at(&self, address: H160, index: U256, number: U256) -> Result<U256, HistoryError> {
+        self.db.storage_at(address, index, number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{EmptyDB, LatencyDB};
+
+    #[test]
+    fn expired_entry_is_fetched_again() {
+        let ttl = Duration::from_millis(50);
+        let db = TtlCacheDB::with_ttl(LatencyDB::new(EmptyDB::default(), Duration::ZERO), ttl);
+        let address = H160::from_low_u64_be(0x42);
+
+        db.basic(address);
+        db.storage(address, U256::one());
+        db.basic(address);
+        db.storage(address, U256::one());
+        assert_eq!(db.db.hits(), 2);
+
+        std::thread::sleep(ttl);
+        // only the read entry is fetched again, the storage slot expires on its own.
+        db.basic(address);
+        db.basic(address);
+        assert_eq!(db.db.hits(), 3);
+        db.storage(address, U256::one());
+        assert_eq!(db.db.hits(), 4);
+
+        db.clear();
+        db.basic(address);
+        assert_eq!(db.db.hits(), 5);
+    }
+}
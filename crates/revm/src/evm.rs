@@ -1,11 +1,13 @@
 use crate::{
-    db::{Database, DatabaseCommit, DatabaseRef, RefDBWrapper},
+    db::{CacheDB, Database, DatabaseCommit, DatabaseRef, RefDBWrapper},
     evm_impl::{EVMImpl, Transact},
     journaled_state::State,
-    BerlinSpec, ByzantiumSpec, Env, ExecutionResult, Inspector, IstanbulSpec, LatestSpec,
-    LondonSpec, MergeSpec, NoOpInspector, Spec, SpecId,
+    BerlinSpec, ByzantiumSpec, CancunSpec, ConstantinopleSpec, Env, ExecutionResult, FrontierSpec,
+    HomesteadSpec, Inspector, IstanbulSpec, LatestSpec, LondonSpec, MergeSpec, MuirGlacierSpec,
+    NoOpInspector, PetersburgSpec, PragueSpec, Return, ShanghaiSpec, Spec, SpecId,
+    SpuriousDragonSpec, TangerineSpec, TxEnv,
 };
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 use revm_precompiles::Precompiles;
 
 /// Struct that takes Database and enabled transact to update state directly to database.
@@ -29,6 +31,8 @@ use revm_precompiles::Precompiles;
 pub struct EVM<DB> {
     pub env: Env,
     pub db: Option<DB>,
+    /// Gas that committed transactions can still spend in total, `None` if there is no limit.
+    session_gas: Option<u64>,
 }
 
 pub fn new<DB>() -> EVM<DB> {
@@ -42,22 +46,55 @@ impl<DB> Default for EVM<DB> {
 }
 
 impl<DB: Database + DatabaseCommit> EVM<DB> {
-    /// Execute transaction and apply result to database
+    /// Execute transaction and apply result to database.
+    ///
+    /// Same as calling [`EVM::transact`] and passing the returned [`State`] to
+    /// [`DatabaseCommit::commit`].
+    ///
+    /// Gas used is charged to the session gas, see [`EVM::set_session_gas_limit`].
     pub fn transact_commit(&mut self) -> ExecutionResult {
-        let (exec_result, state) = self.transact();
+        let (exec_result, state) = self.session_transact(|evm| evm.transact());
         self.db.as_mut().unwrap().commit(state);
         exec_result
     }
     /// Inspect transaction and commit changes to database.
     pub fn inspect_commit<INSP: Inspector<DB>>(&mut self, inspector: INSP) -> ExecutionResult {
-        let (exec_result, state) = self.inspect(inspector);
+        let (exec_result, state) = self.session_transact(|evm| evm.inspect(inspector));
         self.db.as_mut().unwrap().commit(state);
         exec_result
     }
+
+    /// Run `transact` with the transaction gas limit capped to the remaining session gas and
+    /// charge the gas it used to the session.
+    fn session_transact(
+        &mut self,
+        transact: impl FnOnce(&mut Self) -> (ExecutionResult, State),
+    ) -> (ExecutionResult, State) {
+        let remaining = match self.session_gas {
+            Some(remaining) => remaining,
+            None => return transact(self),
+        };
+        let gas_limit = self.env.tx.gas_limit;
+        let capped = remaining < gas_limit;
+        self.env.tx.gas_limit = gas_limit.min(remaining);
+        let (mut exec_result, state) = transact(self);
+        self.env.tx.gas_limit = gas_limit;
+
+        // gas ran out only because of the cap.
+        if capped && exec_result.exit_reason == Return::OutOfGas {
+            exec_result.exit_reason = Return::SessionGasExhausted;
+        }
+        self.session_gas = Some(remaining.saturating_sub(exec_result.gas_used));
+        (exec_result, state)
+    }
 }
 
 impl<DB: Database> EVM<DB> {
     /// Execute transaction without writing to DB, return change state.
+    ///
+    /// Database is only used for reads (its cache may be warmed), none of the changes are written
+    /// to it. Returned [`State`] contains all touched accounts and can be inspected and later
+    /// applied with [`DatabaseCommit::commit`], or dropped for dry-run/`eth_call` semantics.
     pub fn transact(&mut self) -> (ExecutionResult, State) {
         if let Some(db) = self.db.as_mut() {
             let mut noop = NoOpInspector {};
@@ -79,10 +116,66 @@ impl<DB: Database> EVM<DB> {
             panic!("Database needs to be set");
         }
     }
+
+    /// Find the lowest gas limit under which `tx` succeeds in block and config of `env`,
+    /// searching up to `tx.gas_limit`. Meant for `eth_estimateGas`. `env.tx` is ignored.
+    ///
+    /// Transaction is executed multiple times with different gas limits, changed state is
+    /// dropped and nothing is committed to database. Required limit is usually higher than gas
+    /// used, because of refunds and because only 63/64 of the remaining gas is passed to calls
+    /// (EIP-150). Intrinsic gas, including the access list, is part of the estimate.
+    pub fn estimate_gas(&mut self, tx: TxEnv, mut env: Env) -> Result<u64, EstimateError> {
+        let db = self.db.as_mut().expect("Database needs to be set");
+        let mut high = tx.gas_limit;
+        env.tx = tx;
+        let mut run = |gas_limit: u64| {
+            env.tx.gas_limit = gas_limit;
+            let mut noop = NoOpInspector {};
+            let (result, _) = evm_inner::<DB, false>(&mut env, db, &mut noop).transact();
+            result
+        };
+        let succeeded = |result: &ExecutionResult| {
+            matches!(
+                result.exit_reason,
+                Return::Continue | Return::Stop | Return::Return | Return::SelfDestruct
+            )
+        };
+
+        let result = run(high);
+        if !succeeded(&result) {
+            return Err(EstimateError::Failed(Box::new(result)));
+        }
+        // gas limit below gas used before refund can't succeed.
+        let mut low = (result.gas_used + result.gas_refunded).saturating_sub(1);
+
+        // enough for most transactions, with room for the call stipend and the 1/64 kept by
+        // every call. Checking it first skips most of the search.
+        let optimistic =
+            (result.gas_used + result.gas_refunded + crate::gas::CALL_STIPEND) * 64 / 63;
+        if optimistic < high {
+            if succeeded(&run(optimistic)) {
+                high = optimistic;
+            } else {
+                low = optimistic;
+            }
+        }
+
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if succeeded(&run(middle)) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        Ok(high)
+    }
 }
 
 impl<'a, DB: DatabaseRef> EVM<DB> {
     /// Execute transaction without writing to DB, return change state.
+    ///
+    /// Same as [`EVM::transact`] but only needs shared access to database, env is cloned.
     pub fn transact_ref(&self) -> (ExecutionResult, State) {
         if let Some(db) = self.db.as_ref() {
             let mut noop = NoOpInspector {};
@@ -113,14 +206,81 @@ impl<'a, DB: DatabaseRef> EVM<DB> {
     }
 }
 
+impl<DB: DatabaseRef> EVM<DB> {
+    /// Cache for [`EVM::inspect_trace`] over the database of this EVM.
+    pub fn trace_cache(&self) -> CacheDB<&DB> {
+        CacheDB::new(self.db.as_ref().expect("Database needs to be set"))
+    }
+
+    /// Execute transaction only to collect a trace with `inspector`, for tracing RPCs. Nothing
+    /// is written to database and changed state is dropped.
+    ///
+    /// Reads go through `cache`, which never gets commits and so stays valid for all calls on
+    /// the same base state. Reusing it for repeated calls skips database reads and reallocating
+    /// its maps.
+    pub fn inspect_trace<'a, INSP: Inspector<CacheDB<&'a DB>>>(
+        &self,
+        cache: &mut CacheDB<&'a DB>,
+        mut inspector: INSP,
+    ) -> ExecutionResult {
+        let mut env = self.env.clone();
+        let (result, _) =
+            evm_inner::<CacheDB<&'a DB>, true>(&mut env, cache, &mut inspector).transact();
+        result
+    }
+
+    /// Execute `txs` as independent `eth_call`s in this env, for multicall RPCs. Results are
+    /// in order of `txs`.
+    ///
+    /// Every call sees the base state, changes of earlier calls are dropped. Database reads go
+    /// through one cache shared by all calls, so state is loaded from database only once.
+    pub fn call_many(&self, txs: Vec<TxEnv>) -> Vec<ExecutionResult> {
+        let mut cache = self.trace_cache();
+        let mut env = self.env.clone();
+        txs.into_iter()
+            .map(|tx| {
+                env.tx = tx;
+                let mut noop = NoOpInspector {};
+                let (result, _) =
+                    evm_inner::<CacheDB<&DB>, false>(&mut env, &mut cache, &mut noop).transact();
+                result
+            })
+            .collect()
+    }
+}
+
+/// Error returned by [`EVM::estimate_gas`].
+#[derive(Clone, Debug)]
+pub enum EstimateError {
+    /// Transaction does not succeed even with the gas limit set in `env.tx`.
+    Failed(Box<ExecutionResult>),
+}
+
 impl<DB> EVM<DB> {
     pub fn new() -> Self {
         Self {
             env: Env::default(),
             db: None,
+            session_gas: None,
         }
     }
 
+    /// Cap gas that all committed transactions can spend in total to `limit`, `None` removes
+    /// the cap. Useful for sandboxing untrusted contracts over many calls.
+    ///
+    /// Gas limit of every committed transaction is capped to the remaining session gas and gas
+    /// it used is subtracted from it. Transaction that runs out of gas because of the cap halts
+    /// with [`Return::SessionGasExhausted`]. Transactions that are not committed, like
+    /// [`EVM::transact`], neither are capped nor spend session gas.
+    pub fn set_session_gas_limit(&mut self, limit: Option<u64>) {
+        self.session_gas = limit;
+    }
+
+    /// Gas that committed transactions can still spend in total, `None` if there is no limit.
+    pub fn remaining_session_gas(&self) -> Option<u64> {
+        self.session_gas
+    }
+
     pub fn database(&mut self, db: DB) {
         self.db = Some(db);
     }
@@ -152,11 +312,1800 @@ pub fn evm_inner<'a, DB: Database, const INSPECT: bool>(
 ) -> Box<dyn Transact + 'a> {
     match env.cfg.spec_id {
         SpecId::LATEST => create_evm!(LatestSpec, db, env, insp),
+        SpecId::PRAGUE => create_evm!(PragueSpec, db, env, insp),
+        SpecId::CANCUN => create_evm!(CancunSpec, db, env, insp),
+        SpecId::SHANGHAI => create_evm!(ShanghaiSpec, db, env, insp),
         SpecId::MERGE => create_evm!(MergeSpec, db, env, insp),
         SpecId::LONDON => create_evm!(LondonSpec, db, env, insp),
         SpecId::BERLIN => create_evm!(BerlinSpec, db, env, insp),
         SpecId::ISTANBUL => create_evm!(IstanbulSpec, db, env, insp),
+        SpecId::MUIRGLACIER => create_evm!(MuirGlacierSpec, db, env, insp),
+        SpecId::PETERSBURG => create_evm!(PetersburgSpec, db, env, insp),
+        SpecId::CONSTANTINOPLE => create_evm!(ConstantinopleSpec, db, env, insp),
         SpecId::BYZANTIUM => create_evm!(ByzantiumSpec, db, env, insp),
-        _ => panic!("Spec Not supported"),
+        SpecId::SPURIOUS_DRAGON => create_evm!(SpuriousDragonSpec, db, env, insp),
+        SpecId::TANGERINE => create_evm!(TangerineSpec, db, env, insp),
+        SpecId::HOMESTEAD => create_evm!(HomesteadSpec, db, env, insp),
+        SpecId::FRONTIER => create_evm!(FrontierSpec, db, env, insp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        opcode, AccountInfo, BlockEnv, Bytecode, BytecodeBuilder, BytecodeState, CfgEnv,
+        CreateResult, EstimateError, InMemoryDB, Return, TransactOut, TransactTo, KECCAK_EMPTY,
+    };
+    use bytes::Bytes;
+    use primitive_types::{H160, H256, U256};
+
+    fn evm_with_code(contract: H160, code: Vec<u8>) -> EVM<InMemoryDB> {
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(
+            contract,
+            AccountInfo::new(U256::zero(), 0, Bytecode::new_raw(Bytes::from(code))),
+        );
+
+        let mut evm = new();
+        evm.database(db);
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 1_000_000;
+        evm
+    }
+
+    #[test]
+    #[cfg(feature = "memory_limit")]
+    fn memory_limit_exceeded() {
+        let contract = H160::from_low_u64_be(0x42);
+        // PUSH1 0x01, PUSH2 0x1000, MSTORE, STOP
+        let mut evm = evm_with_code(contract, vec![0x60, 0x01, 0x61, 0x10, 0x00, 0x52, 0x00]);
+        assert_eq!(evm.env.cfg.memory_limit, u64::MAX);
+
+        evm.env.cfg.memory_limit = 1024;
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::MemoryLimitOOG);
+
+        evm.env.cfg.memory_limit = 0x2000;
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+    }
+
+    #[test]
+    fn transact_does_not_commit() {
+        let contract = H160::from_low_u64_be(0x42);
+        // SSTORE(0, 1)
+        let mut evm = evm_with_code(contract, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(
+            state[&contract].storage[&U256::zero()].present_value(),
+            U256::one()
+        );
+        assert_eq!(
+            evm.db().unwrap().storage(contract, U256::zero()),
+            U256::zero()
+        );
+
+        let (_, state) = evm.transact_ref();
+        assert!(state.contains_key(&contract));
+        assert_eq!(
+            evm.db().unwrap().storage(contract, U256::zero()),
+            U256::zero()
+        );
+
+        evm.db().unwrap().commit(state);
+        assert_eq!(
+            evm.db().unwrap().storage(contract, U256::zero()),
+            U256::one()
+        );
+    }
+
+    #[test]
+    fn call_many_isolates_writes() {
+        let contract = H160::from_low_u64_be(0x42);
+        // SSTORE(0, SLOAD(0) + 1), return the loaded value
+        let code = BytecodeBuilder::new()
+            .push(0)
+            .op(opcode::SLOAD)
+            .op(opcode::DUP1)
+            .push(1)
+            .op(opcode::ADD)
+            .push(0)
+            .op(opcode::SSTORE)
+            .push(0)
+            .op(opcode::MSTORE)
+            .push(0x20)
+            .push(0)
+            .op(opcode::RETURN)
+            .build();
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(contract, AccountInfo::from_code(code));
+        db.insert_account_storage(contract, U256::zero(), U256::from(5));
+        let mut evm = new();
+        evm.database(crate::db::LatencyDB::new(db, core::time::Duration::ZERO));
+
+        let tx = TxEnv {
+            transact_to: TransactTo::Call(contract),
+            gas_limit: 100_000,
+            ..Default::default()
+        };
+        let results = evm.call_many(vec![tx.clone(), tx.clone(), tx]);
+        assert_eq!(results.len(), 3);
+        for result in &results {
+            assert_eq!(result.exit_reason, Return::Return);
+            match &result.out {
+                TransactOut::Call(out) => assert_eq!(U256::from(out.as_ref()), U256::from(5)),
+                _ => panic!("expected call output"),
+            }
+        }
+        // later calls read from the cache, three calls hit database as often as one.
+        let hits = evm.db.as_ref().unwrap().hits();
+        evm.db.as_ref().unwrap().reset_hits();
+        evm.call_many(vec![TxEnv {
+            transact_to: TransactTo::Call(contract),
+            gas_limit: 100_000,
+            ..Default::default()
+        }]);
+        assert_eq!(evm.db.as_ref().unwrap().hits(), hits);
+        // base state is not changed.
+        assert_eq!(
+            evm.db.as_ref().unwrap().db.storage(contract, U256::zero()),
+            U256::from(5)
+        );
+    }
+
+    #[test]
+    fn inspect_trace_does_not_commit() {
+        let contract = H160::from_low_u64_be(0x42);
+        // SSTORE(0, SLOAD(0) + 1)
+        let code = BytecodeBuilder::new()
+            .push(1)
+            .push(0)
+            .op(opcode::SLOAD)
+            .op(opcode::ADD)
+            .push(0)
+            .op(opcode::SSTORE)
+            .op(opcode::STOP)
+            .into_bytes();
+        let evm = evm_with_code(contract, code);
+
+        let mut cache = evm.trace_cache();
+        let first = evm.inspect_trace(&mut cache, NoOpInspector());
+        assert_eq!(first.exit_reason, Return::Stop);
+        assert!(cache.accounts.contains_key(&contract));
+
+        // same base state, the store from the first call is not visible.
+        let second = evm.inspect_trace(&mut cache, NoOpInspector());
+        assert_eq!(second.gas_used, first.gas_used);
+        assert_eq!(cache.storage(contract, U256::zero()), U256::zero());
+        assert_eq!(
+            evm.db.as_ref().unwrap().storage(contract, U256::zero()),
+            U256::zero()
+        );
+    }
+
+    #[test]
+    fn storage_hook_mirrors_writes() {
+        struct MirrorHook;
+
+        impl crate::StorageHook for MirrorHook {
+            fn sstore(
+                &self,
+                storage: &mut crate::HookStorage<'_>,
+                address: H160,
+                index: U256,
+                value: U256,
+            ) -> U256 {
+                storage.sstore(address, index + 0x1000, value);
+                value
+            }
+
+            fn sstore_cost(&self, _address: H160, _index: U256, cost: u64) -> u64 {
+                cost + 100
+            }
+        }
+
+        let contract = H160::from_low_u64_be(0x42);
+        // SSTORE(1, 7), SLOAD(0x1001)
+        let code = BytecodeBuilder::new()
+            .push(7)
+            .push(1)
+            .op(opcode::SSTORE)
+            .push(0x1001)
+            .op(opcode::SLOAD)
+            .op(opcode::STOP)
+            .into_bytes();
+        let mut evm = evm_with_code(contract, code);
+        let (plain, _) = evm.transact();
+
+        evm.env.cfg.storage_hook = Some(crate::StorageHookRef::new(MirrorHook));
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        let storage = &state[&contract].storage;
+        assert_eq!(storage[&U256::one()].present_value(), U256::from(7));
+        assert_eq!(storage[&U256::from(0x1001)].present_value(), U256::from(7));
+        // the mirrored slot was warmed by the hook, SLOAD of it is warm.
+        assert_eq!(
+            result.gas_used,
+            plain.gas_used + 100 - (crate::gas::SLOAD_COLD - crate::gas::STORAGE_READ_WARM)
+        );
+    }
+
+    #[test]
+    fn expanded_memory_is_zeroed() {
+        let contract = H160::from_low_u64_be(0x42);
+        // MSTORE(0x40, -1), MLOAD(0x80) is zero, return memory 0..0xc0 with the loaded word
+        // stored at 0xa0.
+        let code = BytecodeBuilder::new()
+            .push(U256::MAX)
+            .push(0x40)
+            .op(opcode::MSTORE)
+            .push(0x80)
+            .op(opcode::MLOAD)
+            .push(0xa0)
+            .op(opcode::MSTORE)
+            .push(0xc0)
+            .push(0)
+            .op(opcode::RETURN)
+            .into_bytes();
+        let mut evm = evm_with_code(contract, code);
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Return);
+        let out = match result.out {
+            TransactOut::Call(out) => out,
+            _ => panic!("expected call output"),
+        };
+        let mut expected = vec![0u8; 0xc0];
+        expected[0x40..0x60].copy_from_slice(&[0xff; 32]);
+        assert_eq!(out.as_ref(), expected.as_slice());
+    }
+
+    #[test]
+    fn sstore_reset_to_original_refund() {
+        let contract = H160::from_low_u64_be(0x42);
+        // SSTORE(0, 2), SSTORE(0, 1)
+        let code = vec![
+            0x60, 0x02, 0x60, 0x00, 0x55, 0x60, 0x01, 0x60, 0x00, 0x55, 0x00,
+        ];
+        let mut evm = evm_with_code(contract, code);
+        evm.db()
+            .unwrap()
+            .insert_account_storage(contract, U256::zero(), U256::one());
+        evm.env.cfg.spec_id = SpecId::LONDON;
+
+        for _ in 0..2 {
+            let result = evm.transact_commit();
+            assert_eq!(result.exit_reason, Return::Stop);
+            // SSTORE_RESET - SLOAD_COLD - WARM_STORAGE_READ
+            assert_eq!(result.gas_refunded, 2800);
+            assert_eq!(
+                evm.db().unwrap().storage(contract, U256::zero()),
+                U256::one()
+            );
+        }
+
+        let (_, state) = evm.transact();
+        let slot = &state[&contract].storage[&U256::zero()];
+        assert_eq!(slot.original_value(), U256::one());
+        assert_eq!(slot.present_value(), U256::one());
+        assert!(!slot.is_changed());
+    }
+
+    #[test]
+    fn refund_cap_across_specs() {
+        let contract = H160::from_low_u64_be(0x42);
+        // SSTORE(0, 0) clearing a non zero slot.
+        let code = vec![0x60, 0x00, 0x60, 0x00, 0x55, 0x00];
+        let mut evm = evm_with_code(contract, code);
+        evm.db()
+            .unwrap()
+            .insert_account_storage(contract, U256::zero(), U256::one());
+
+        // 21000 intrinsic + 2 * PUSH1 + cold SSTORE_RESET.
+        let spend = 21_000 + 6 + 5_000;
+
+        // refund of 15000 is capped to half of spend.
+        evm.env.cfg.spec_id = SpecId::BERLIN;
+        let (result, _) = evm.transact();
+        assert_eq!(result.gas_refunded, spend / 2);
+        assert_eq!(result.gas_used, spend - spend / 2);
+
+        // EIP-3529 lowers the refund to 4800 which is under the cap of spend / 5.
+        evm.env.cfg.spec_id = SpecId::LONDON;
+        let (result, _) = evm.transact();
+        assert_eq!(result.gas_refunded, 4_800);
+        assert_eq!(result.gas_used, spend - 4_800);
+    }
+
+    #[test]
+    fn block_env_opcodes() {
+        let contract = H160::from_low_u64_be(0x42);
+        let read = |opcode: u8, spec_id: SpecId, block: &BlockEnv| {
+            // OPCODE, PUSH1 0x00, MSTORE, PUSH1 0x20, PUSH1 0x00, RETURN
+            let code = vec![opcode, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+            let mut evm = evm_with_code(contract, code);
+            evm.env.cfg.spec_id = spec_id;
+            evm.env.block = block.clone();
+            evm.env.tx.gas_price = block.basefee;
+            let caller = evm.env.tx.caller;
+            evm.db()
+                .unwrap()
+                .insert_account_info(caller, AccountInfo::from_balance(U256::from(10_000_000)));
+            let (result, _) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Return);
+            match result.out {
+                TransactOut::Call(out) => U256::from_big_endian(&out),
+                _ => panic!("expected call output"),
+            }
+        };
+
+        let block = BlockEnv {
+            number: U256::from(1),
+            coinbase: H160::from_low_u64_be(2),
+            timestamp: U256::from(3),
+            difficulty: U256::from(4),
+            prevrandao: Some(H256::from_low_u64_be(5)),
+            basefee: U256::from(6),
+            gas_limit: U256::from(7_000_000),
+            blob_excess_gas: None,
+        };
+        assert_eq!(read(opcode::NUMBER, SpecId::LATEST, &block), U256::from(1));
+        assert_eq!(
+            read(opcode::COINBASE, SpecId::LATEST, &block),
+            U256::from(2)
+        );
+        assert_eq!(
+            read(opcode::TIMESTAMP, SpecId::LATEST, &block),
+            U256::from(3)
+        );
+        assert_eq!(read(opcode::BASEFEE, SpecId::LATEST, &block), U256::from(6));
+        assert_eq!(
+            read(opcode::GASLIMIT, SpecId::LATEST, &block),
+            U256::from(7_000_000)
+        );
+
+        // DIFFICULTY returns prevrandao since the Merge.
+        assert_eq!(
+            read(opcode::DIFFICULTY, SpecId::LONDON, &block),
+            U256::from(4)
+        );
+        assert_eq!(
+            read(opcode::DIFFICULTY, SpecId::MERGE, &block),
+            U256::from(5)
+        );
+        let block = BlockEnv {
+            prevrandao: None,
+            ..block
+        };
+        assert_eq!(
+            read(opcode::DIFFICULTY, SpecId::MERGE, &block),
+            U256::from(4)
+        );
+    }
+
+    /// Sign authorization to delegate to `address` with a fixed key, returns the authority.
+    fn sign_authorization(address: H160, nonce: u64) -> (H160, crate::Authorization) {
+        use secp256k1::{Message, Secp256k1, SecretKey};
+        use sha3::{Digest, Keccak256};
+
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[0x11; 32]).unwrap();
+        let public = key.public_key(&secp).serialize_uncompressed();
+        let authority = H160::from_slice(&Keccak256::digest(&public[1..])[12..]);
+
+        let mut authorization = crate::Authorization {
+            chain_id: U256::one(),
+            address,
+            nonce,
+            ..Default::default()
+        };
+        let message = Message::from_slice(authorization.signature_hash().as_bytes()).unwrap();
+        let (recovery_id, signature) = secp
+            .sign_ecdsa_recoverable(&message, &key)
+            .serialize_compact();
+        authorization.y_parity = recovery_id.to_i32() as u8;
+        authorization.r = U256::from_big_endian(&signature[..32]);
+        authorization.s = U256::from_big_endian(&signature[32..]);
+        (authority, authorization)
+    }
+
+    #[test]
+    fn authorization_delegates_code() {
+        let delegate = H160::from_low_u64_be(0x42);
+        // PUSH1 0x01, PUSH1 0x00, SSTORE, STOP
+        let mut evm = evm_with_code(delegate, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+        evm.env.tx.caller = H160::from_low_u64_be(0x1000);
+
+        let (authority, authorization) = sign_authorization(delegate, 0);
+        assert_eq!(authorization.authority(), Some(authority));
+        evm.db()
+            .unwrap()
+            .insert_account_info(authority, AccountInfo::from_balance(U256::from(1)));
+        evm.env.tx.transact_to = TransactTo::Call(authority);
+        evm.env.tx.authorization_list = vec![authorization.clone()];
+
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        let account = &state[&authority];
+        assert_eq!(
+            account.info.code.as_ref().unwrap().delegation_address(),
+            Some(delegate)
+        );
+        assert_eq!(account.info.nonce, 1);
+        // delegated code ran in the context of the authority.
+        assert_eq!(account.storage[&U256::zero()].present_value(), U256::one());
+        assert!(!state
+            .get(&delegate)
+            .is_some_and(|account| account.storage.contains_key(&U256::zero())));
+
+        // authorizations are invalid before Prague and in create transactions.
+        evm.env.cfg.spec_id = SpecId::CANCUN;
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::InvalidAuthorizationList);
+        assert!(state.is_empty());
+        evm.env.cfg.spec_id = SpecId::LATEST;
+        evm.env.tx.transact_to = TransactTo::create();
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::InvalidAuthorizationList);
+    }
+
+    #[test]
+    fn delegated_sender_can_transact() {
+        let delegate = H160::from_low_u64_be(0x42);
+        let mut evm = evm_with_code(delegate, vec![0x00]);
+        evm.env.tx.caller = H160::from_low_u64_be(0x1000);
+        let (authority, authorization) = sign_authorization(delegate, 0);
+        evm.env.tx.transact_to = TransactTo::Call(authority);
+        evm.env.tx.authorization_list = vec![authorization];
+        assert_eq!(evm.transact_commit().exit_reason, Return::Stop);
+
+        // EIP-3607 does not reject senders with delegated code.
+        evm.env.tx.caller = authority;
+        evm.env.tx.authorization_list = Vec::new();
+        evm.env.tx.transact_to = TransactTo::Call(delegate);
+        assert_eq!(evm.transact_commit().exit_reason, Return::Stop);
+        assert_eq!(evm.db().unwrap().basic(authority).nonce, 2);
+    }
+
+    #[test]
+    fn session_gas_runs_out_mid_call() {
+        let stop = H160::from_low_u64_be(0x42);
+        let looping = H160::from_low_u64_be(0x43);
+        // JUMPDEST, PUSH1 0x00, JUMP
+        let mut evm = evm_with_code(looping, vec![0x5b, 0x60, 0x00, 0x56]);
+        evm.db().unwrap().insert_account_info(
+            stop,
+            AccountInfo::from_code(Bytecode::new_raw(Bytes::from(vec![0x00]))),
+        );
+        evm.set_session_gas_limit(Some(100_000));
+
+        evm.env.tx.transact_to = TransactTo::Call(stop);
+        let result = evm.transact_commit();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(result.gas_used, 21_000);
+        assert_eq!(evm.remaining_session_gas(), Some(79_000));
+
+        // transactions that are not committed do not spend session gas.
+        evm.env.tx.transact_to = TransactTo::Call(looping);
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::OutOfGas);
+        assert_eq!(result.gas_used, 1_000_000);
+        assert_eq!(evm.remaining_session_gas(), Some(79_000));
+
+        let result = evm.transact_commit();
+        assert_eq!(result.exit_reason, Return::SessionGasExhausted);
+        assert_eq!(result.gas_used, 79_000);
+        assert_eq!(evm.remaining_session_gas(), Some(0));
+        assert_eq!(evm.env.tx.gas_limit, 1_000_000);
+
+        evm.env.tx.transact_to = TransactTo::Call(stop);
+        let result = evm.transact_commit();
+        assert_eq!(result.exit_reason, Return::SessionGasExhausted);
+
+        // running out of the transaction gas limit is a normal out of gas.
+        evm.set_session_gas_limit(Some(1_000_000));
+        evm.env.tx.gas_limit = 100_000;
+        evm.env.tx.transact_to = TransactTo::Call(looping);
+        let result = evm.transact_commit();
+        assert_eq!(result.exit_reason, Return::OutOfGas);
+        assert_eq!(evm.remaining_session_gas(), Some(900_000));
+    }
+
+    #[test]
+    fn delegate_access_is_charged_once() {
+        let delegate = H160::from_low_u64_be(0x42);
+        let authority = H160::from_low_u64_be(0x43);
+        let caller = H160::from_low_u64_be(0x44);
+        // STOP
+        let mut evm = evm_with_code(delegate, vec![0x00]);
+        evm.env.tx.caller = H160::from_low_u64_be(0x1000);
+        evm.db().unwrap().insert_account_info(
+            authority,
+            AccountInfo::new(U256::zero(), 1, Bytecode::new_delegation(delegate)),
+        );
+
+        // delegate of the transaction target is warmed for free.
+        evm.env.tx.transact_to = TransactTo::Call(authority);
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(result.gas_used, 21_000);
+
+        // CALL pays for the authority and its delegate, cold then warm.
+        // (PUSH1 0x00 x5, PUSH20 authority, GAS, CALL, POP) x2, STOP
+        let mut call = [0x60, 0x00].repeat(5);
+        call.push(0x73);
+        call.extend_from_slice(authority.as_bytes());
+        call.extend_from_slice(&[opcode::GAS, opcode::CALL, opcode::POP]);
+        let code = [call.clone(), call, vec![opcode::STOP]].concat();
+        evm.db().unwrap().insert_account_info(
+            caller,
+            AccountInfo::from_code(Bytecode::new_raw(code.into())),
+        );
+        evm.env.tx.transact_to = TransactTo::Call(caller);
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        let opcodes = 5 * 3 + 3 + 2 + 2;
+        assert_eq!(result.gas_used, 21_000 + 2 * opcodes + 2 * 2_600 + 2 * 100);
+    }
+
+    #[test]
+    fn authorization_with_wrong_nonce_is_skipped() {
+        let delegate = H160::from_low_u64_be(0x42);
+        let mut evm = evm_with_code(delegate, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+        evm.env.tx.caller = H160::from_low_u64_be(0x1000);
+
+        let (authority, authorization) = sign_authorization(delegate, 5);
+        evm.env.tx.transact_to = TransactTo::Call(authority);
+        evm.env.tx.authorization_list = vec![authorization];
+
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        // intrinsic cost of the authorization is still paid.
+        assert_eq!(result.gas_used, 21_000 + 25_000);
+        let account = &state[&authority];
+        assert!(account.info.code.as_ref().unwrap().is_empty());
+        assert_eq!(account.info.nonce, 0);
+        assert!(account.storage.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "optimism")]
+    fn optimism_deposit_mints_value() {
+        let caller = H160::from_low_u64_be(0x1000);
+        let to = H160::from_low_u64_be(0x2000);
+        let mut evm = new();
+        evm.database(InMemoryDB::default());
+        evm.env.block.basefee = U256::from(10);
+        evm.env.tx.caller = caller;
+        evm.env.tx.transact_to = TransactTo::Call(to);
+        evm.env.tx.gas_limit = 100_000;
+        evm.env.tx.value = U256::from(100);
+        evm.env.tx.optimism.source_hash = Some(H256::from_low_u64_be(1));
+        evm.env.tx.optimism.mint = Some(U256::from(1000));
+        // ignored for deposits.
+        evm.env.tx.optimism.l1_cost = U256::from(5);
+
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(result.gas_used, 21_000);
+        assert_eq!(state[&caller].info.balance, U256::from(900));
+        assert_eq!(state[&to].info.balance, U256::from(100));
+        assert!(!state.contains_key(&crate::L1_FEE_RECIPIENT));
+    }
+
+    #[test]
+    #[cfg(feature = "optimism")]
+    fn optimism_deposit_runs_code() {
+        let contract = H160::from_low_u64_be(0x42);
+        // PUSH1 0x01, PUSH1 0x00, SSTORE, STOP
+        let mut evm = evm_with_code(contract, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+        // deposits can come from accounts with code, aliased L1 contracts.
+        evm.env.tx.caller = contract;
+        evm.env.tx.gas_price = U256::from(1);
+        evm.env.tx.optimism.source_hash = Some(H256::from_low_u64_be(1));
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(
+            state[&contract].storage[&U256::zero()].present_value(),
+            U256::one()
+        );
+        assert!(state[&contract].info.balance.is_zero());
+
+        // regular transaction pays gas and the L1 cost.
+        let caller = H160::from_low_u64_be(0x1000);
+        evm.env.tx.caller = caller;
+        evm.env.tx.optimism.source_hash = None;
+        evm.env.tx.optimism.l1_cost = U256::from(1000);
+        evm.db()
+            .unwrap()
+            .insert_account_info(caller, AccountInfo::from_balance(U256::from(2_000_000)));
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(
+            state[&caller].info.balance,
+            U256::from(2_000_000 - 1000 - result.gas_used)
+        );
+        assert_eq!(
+            state[&crate::L1_FEE_RECIPIENT].info.balance,
+            U256::from(1000)
+        );
+    }
+
+    #[test]
+    fn eip1559_effective_gas_price() {
+        let contract = H160::from_low_u64_be(0x42);
+        let caller = H160::from_low_u64_be(0x1000);
+        let coinbase = H160::from_low_u64_be(0xc0);
+        let balance = 10_000_000u64;
+        // (max fee, max priority fee, effective price)
+        for (max_fee, priority_fee, effective) in [
+            (30, Some(5), 15),
+            (20, Some(15), 20),
+            (30, Some(0), 10),
+            (12, None, 12),
+        ] {
+            // STOP
+            let mut evm = evm_with_code(contract, vec![0x00]);
+            evm.env.cfg.spec_id = SpecId::LONDON;
+            evm.env.block.basefee = U256::from(10);
+            evm.env.block.coinbase = coinbase;
+            evm.env.tx.caller = caller;
+            evm.env.tx.gas_limit = 100_000;
+            evm.env.tx.gas_price = U256::from(max_fee);
+            evm.env.tx.gas_priority_fee = priority_fee.map(U256::from);
+            evm.db()
+                .unwrap()
+                .insert_account_info(caller, AccountInfo::from_balance(U256::from(balance)));
+            assert_eq!(evm.env.effective_gas_price(), U256::from(effective));
+
+            let (result, state) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Stop);
+            assert_eq!(result.gas_used, 21_000);
+            // unused gas is refunded at the effective price.
+            assert_eq!(
+                state[&caller].info.balance,
+                U256::from(balance - 21_000 * effective)
+            );
+            // basefee is burned, the rest goes to the coinbase.
+            let coinbase_balance = state
+                .get(&coinbase)
+                .map_or(U256::zero(), |account| account.info.balance);
+            assert_eq!(coinbase_balance, U256::from(21_000 * (effective - 10)));
+        }
+    }
+
+    #[test]
+    fn unused_gas_refunded_to_sender() {
+        let contract = H160::from_low_u64_be(0x42);
+        let caller = H160::from_low_u64_be(0x1000);
+        let balance = 10_000_000u64;
+        let revert = BytecodeBuilder::new()
+            .push(0)
+            .push(0)
+            .op(opcode::REVERT)
+            .into_bytes();
+        // (code, exit reason, gas used)
+        for (code, exit_reason, gas_used) in [
+            (vec![0x00], Return::Stop, 21_000),
+            (revert, Return::Revert, 21_006),
+        ] {
+            let mut evm = evm_with_code(contract, code);
+            evm.env.tx.caller = caller;
+            evm.env.tx.gas_limit = 42_000;
+            evm.env.tx.gas_price = U256::from(10);
+            evm.db()
+                .unwrap()
+                .insert_account_info(caller, AccountInfo::from_balance(U256::from(balance)));
+
+            let (result, state) = evm.transact();
+            assert_eq!(result.exit_reason, exit_reason);
+            assert_eq!(result.gas_used, gas_used);
+            // sender paid only for the gas that was used, also on revert.
+            assert_eq!(
+                state[&caller].info.balance,
+                U256::from(balance - gas_used * 10)
+            );
+        }
+    }
+
+    #[test]
+    fn extcodehash_of_empty_and_nonexistent_accounts() {
+        let contract = H160::from_low_u64_be(0x42);
+        let target = H160::from_low_u64_be(0x1234);
+        let code_hash = |info: Option<AccountInfo>| {
+            // PUSH2 target, EXTCODEHASH, PUSH1 0x00, MSTORE, PUSH1 0x20, PUSH1 0x00, RETURN
+            let code = BytecodeBuilder::new()
+                .push(0x1234)
+                .op(opcode::EXTCODEHASH)
+                .push(0)
+                .op(opcode::MSTORE)
+                .push(0x20)
+                .push(0)
+                .op(opcode::RETURN)
+                .into_bytes();
+            let mut evm = evm_with_code(contract, code);
+            if let Some(info) = info {
+                evm.db().unwrap().insert_account_info(target, info);
+            }
+            let (result, _) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Return);
+            // intrinsic, pushes, cold account access, MSTORE with memory expansion
+            assert_eq!(result.gas_used, 21_000 + 3 * 4 + 2_600 + 6);
+            match result.out {
+                TransactOut::Call(out) => H256::from_slice(&out),
+                _ => panic!("expected call output"),
+            }
+        };
+
+        assert_eq!(code_hash(None), H256::zero());
+        assert_eq!(code_hash(Some(AccountInfo::default())), H256::zero());
+        let balance_only = AccountInfo::from_balance(U256::one());
+        assert_eq!(code_hash(Some(balance_only)), KECCAK_EMPTY);
+        let code = Bytecode::new_raw(vec![0x00].into());
+        assert_eq!(
+            code_hash(Some(AccountInfo::from_code(code.clone()))),
+            code.hash()
+        );
+    }
+
+    #[test]
+    fn max_returndata_size() {
+        let contract = H160::from_low_u64_be(0x42);
+        let callee = H160::from_low_u64_be(0x43);
+        // CALL callee, return RETURNDATASIZE
+        let code = BytecodeBuilder::new()
+            .push(0)
+            .push(0)
+            .push(0)
+            .push(0)
+            .push(0)
+            .push(0x43)
+            .op(opcode::GAS)
+            .op(opcode::CALL)
+            .op(opcode::POP)
+            .op(opcode::RETURNDATASIZE)
+            .push(0)
+            .op(opcode::MSTORE)
+            .push(0x20)
+            .push(0)
+            .op(opcode::RETURN)
+            .into_bytes();
+        let mut evm = evm_with_code(contract, code);
+        // RETURN 64 zero bytes
+        let callee_code = BytecodeBuilder::new()
+            .push(0x40)
+            .push(0)
+            .op(opcode::RETURN)
+            .build();
+        evm.db()
+            .unwrap()
+            .insert_account_info(callee, AccountInfo::from_code(callee_code));
+        let mut returndata_size = |cfg: CfgEnv| {
+            evm.env.cfg = cfg;
+            let (result, _) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Return);
+            match result.out {
+                TransactOut::Call(out) => (U256::from(out.as_ref()), result.returndata_truncated),
+                _ => panic!("expected call output"),
+            }
+        };
+
+        assert_eq!(returndata_size(CfgEnv::default()), (U256::from(64), false));
+        let limited = CfgEnv {
+            max_returndata_size: Some(32),
+            ..Default::default()
+        };
+        assert_eq!(returndata_size(limited.clone()), (U256::from(32), true));
+        // halted call returns nothing.
+        let halting = CfgEnv {
+            halt_on_returndata_limit: true,
+            ..limited
+        };
+        assert_eq!(returndata_size(halting), (U256::zero(), false));
+    }
+
+    #[test]
+    fn intrinsic_gas() {
+        let contract = H160::from_low_u64_be(0x42);
+        let intrinsic = |spec_id: SpecId, data: Vec<u8>| {
+            // STOP
+            let mut evm = evm_with_code(contract, vec![0x00]);
+            evm.env.cfg.spec_id = spec_id;
+            evm.env.tx.data = Bytes::from(data);
+            let (result, _) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Stop);
+            assert_eq!(result.gas_used, result.intrinsic_gas);
+            result.intrinsic_gas
+        };
+
+        assert_eq!(intrinsic(SpecId::LATEST, Vec::new()), 21_000);
+        let data = vec![0, 0, 0, 1, 2, 3, 4, 5];
+        assert_eq!(
+            intrinsic(SpecId::LATEST, data.clone()),
+            21_000 + 3 * 4 + 5 * 16
+        );
+        // EIP-2028 reduced cost of non zero calldata bytes.
+        assert_eq!(intrinsic(SpecId::BYZANTIUM, data), 21_000 + 3 * 4 + 5 * 68);
+
+        // PUSH1 0x01, PUSH1 0x00, SSTORE, STOP
+        let mut evm = evm_with_code(contract, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+        evm.env.tx.access_list = vec![(contract, vec![U256::zero()])];
+        let (result, _) = evm.transact();
+        assert_eq!(result.intrinsic_gas, 21_000 + 2400 + 1900);
+        assert_eq!(result.gas_used, result.intrinsic_gas + 3 + 3 + 20_000);
+    }
+
+    #[test]
+    fn access_list_warms_accounts() {
+        let contract = H160::from_low_u64_be(0x42);
+        let other = H160::from_low_u64_be(0x1234);
+        // PUSH2 0x1234, BALANCE, STOP
+        let code = BytecodeBuilder::new()
+            .push(0x1234)
+            .op(opcode::BALANCE)
+            .op(opcode::STOP)
+            .into_bytes();
+        let mut evm = evm_with_code(contract, code);
+        let (result, _) = evm.transact();
+        assert_eq!(result.gas_used, 21_000 + 3 + 2_600);
+
+        // duplicate addresses and slots are charged for every entry.
+        evm.env.tx.access_list = vec![
+            (other, vec![U256::zero(), U256::zero()]),
+            (other, vec![U256::one()]),
+        ];
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(result.intrinsic_gas, 21_000 + 2 * 2_400 + 3 * 1_900);
+        assert_eq!(result.gas_used, result.intrinsic_gas + 3 + 100);
+
+        // no warm accounts before Berlin, BALANCE has a flat cost.
+        evm.env.cfg.spec_id = SpecId::ISTANBUL;
+        let (result, _) = evm.transact();
+        assert_eq!(result.intrinsic_gas, 21_000);
+        assert_eq!(result.gas_used, 21_000 + 3 + 700);
+    }
+
+    #[test]
+    fn gas_per_opcode() {
+        struct LastPc(Option<usize>);
+
+        impl<DB: Database> Inspector<DB> for LastPc {
+            fn step(
+                &mut self,
+                interp: &mut crate::Interpreter,
+                _data: &mut crate::EVMData<'_, DB>,
+                _is_static: bool,
+            ) -> Return {
+                self.0 = Some(interp.program_counter());
+                Return::Continue
+            }
+        }
+
+        let contract = H160::from_low_u64_be(0x42);
+        // PUSH1 0x01, PUSH1 0x01, ADD, PUSH1 0x01, ADD, STOP
+        let mut evm = evm_with_code(
+            contract,
+            vec![0x60, 0x01, 0x60, 0x01, 0x01, 0x60, 0x01, 0x01, 0x00],
+        );
+        let run = |evm: &mut EVM<InMemoryDB>, gas_per_opcode: bool| {
+            evm.env.cfg.gas_per_opcode = gas_per_opcode;
+            let mut last_pc = LastPc(None);
+            let (result, _) = evm.inspect(&mut last_pc);
+            (result.exit_reason, result.gas_used, last_pc.0)
+        };
+
+        // same gas when there is enough of it.
+        assert_eq!(run(&mut evm, false), (Return::Stop, 21_015, Some(8)));
+        assert_eq!(run(&mut evm, true), (Return::Stop, 21_015, Some(8)));
+
+        // enough gas for the first three opcodes only.
+        evm.env.tx.gas_limit = 21_009;
+        // whole block is charged before the first opcode.
+        assert_eq!(run(&mut evm, false), (Return::OutOfGas, 21_009, None));
+        // runs out at the second PUSH1, as in Geth.
+        assert_eq!(run(&mut evm, true), (Return::OutOfGas, 21_009, Some(5)));
+
+        // jumps and loops use the same gas.
+        let code = crate::BytecodeBuilder::new()
+            .push(3)
+            .jumpdest("loop")
+            .push(1)
+            .op(opcode::SWAP1)
+            .op(opcode::SUB)
+            .op(opcode::DUP1)
+            .jumpi("loop")
+            .op(opcode::STOP)
+            .build();
+        let mut evm = evm_with_code(contract, Vec::new());
+        evm.db()
+            .unwrap()
+            .insert_account_info(contract, AccountInfo::from_code(code));
+        let (block, per_opcode) = (run(&mut evm, false), run(&mut evm, true));
+        assert_eq!(block.0, Return::Stop);
+        assert_eq!(block, per_opcode);
+    }
+
+    #[test]
+    fn estimate_gas() {
+        let contract = H160::from_low_u64_be(0x42);
+        let callee = H160::from_low_u64_be(0x43);
+        // PUSH1 0x01, PUSH1 0x00, SSTORE, STOP
+        let store = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00];
+        let mut evm = evm_with_code(contract, store.clone());
+        evm.env.tx.caller = H160::from_low_u64_be(0x1000);
+        let estimate = |evm: &mut EVM<InMemoryDB>| {
+            let (tx, env) = (evm.env.tx.clone(), evm.env.clone());
+            evm.estimate_gas(tx, env).unwrap()
+        };
+
+        // cold SSTORE of new value.
+        assert_eq!(estimate(&mut evm), 21_000 + 6 + 22_100);
+        // access list pays for warming up the slot.
+        evm.env.tx.access_list = vec![(contract, vec![U256::zero()])];
+        assert_eq!(estimate(&mut evm), 21_000 + 2_400 + 1_900 + 6 + 20_000);
+        evm.env.tx.access_list = Vec::new();
+
+        // plain transfer.
+        evm.env.tx.transact_to = TransactTo::Call(H160::from_low_u64_be(0x100));
+        assert_eq!(estimate(&mut evm), 21_000);
+
+        // call with all gas, callee only gets 63/64 of it. Fails if the call fails.
+        // PUSH1 0x00 x5, PUSH20 callee, GAS, CALL, PUSH1 0x25, JUMPI, INVALID, JUMPDEST, STOP
+        let mut code = [0x60, 0x00].repeat(5);
+        code.push(0x73);
+        code.extend_from_slice(callee.as_bytes());
+        code.extend_from_slice(&[
+            opcode::GAS,
+            opcode::CALL,
+            0x60,
+            0x25,
+            0x57,
+            0xfe,
+            0x5b,
+            0x00,
+        ]);
+        evm.db().unwrap().insert_account_info(
+            contract,
+            AccountInfo::from_code(Bytecode::new_raw(code.into())),
+        );
+        evm.db().unwrap().insert_account_info(
+            callee,
+            AccountInfo::from_code(Bytecode::new_raw(store.into())),
+        );
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        let limit = estimate(&mut evm);
+        let mut at_limit = evm.clone();
+        at_limit.env.tx.gas_limit = limit;
+        let result = at_limit.transact_ref().0;
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert!(limit > result.gas_used);
+        at_limit.env.tx.gas_limit = limit - 1;
+        assert_eq!(at_limit.transact_ref().0.exit_reason, Return::InvalidOpcode);
+
+        // failing transaction has no estimate.
+        // PUSH1 0x00, PUSH1 0x00, REVERT
+        evm.db().unwrap().insert_account_info(
+            contract,
+            AccountInfo::from_code(Bytecode::new_raw(vec![0x60, 0x00, 0x60, 0x00, 0xfd].into())),
+        );
+        let (tx, env) = (evm.env.tx.clone(), evm.env.clone());
+        assert!(matches!(
+            evm.estimate_gas(tx, env),
+            Err(EstimateError::Failed(result)) if result.exit_reason == Return::Revert
+        ));
+    }
+
+    #[test]
+    fn disabled_opcode_halts() {
+        let contract = H160::from_low_u64_be(0x42);
+        // PUSH1 0x00, SELFDESTRUCT
+        let code = vec![0x60, 0x00, opcode::SELFDESTRUCT];
+        let mut evm = evm_with_code(contract, code);
+        assert_eq!(evm.transact().0.exit_reason, Return::SelfDestruct);
+
+        evm.env.cfg.disabled_opcodes[opcode::SELFDESTRUCT as usize] = true;
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::OpcodeDisabled);
+        assert_eq!(result.gas_used, 1_000_000);
+        assert!(!state
+            .get(&contract)
+            .is_some_and(|account| account.is_destroyed));
+    }
+
+    #[test]
+    fn instruction_limit() {
+        struct StepCounter(usize);
+
+        impl<DB: Database> Inspector<DB> for StepCounter {
+            fn step(
+                &mut self,
+                _interp: &mut crate::Interpreter,
+                _data: &mut crate::EVMData<'_, DB>,
+                _is_static: bool,
+            ) -> Return {
+                self.0 += 1;
+                Return::Continue
+            }
+        }
+
+        let contract = H160::from_low_u64_be(0x42);
+        // JUMPDEST, PUSH1 0x00, JUMP
+        let mut evm = evm_with_code(contract, vec![0x5b, 0x60, 0x00, 0x56]);
+        assert_eq!(evm.transact().0.exit_reason, Return::OutOfGas);
+
+        evm.env.cfg.instruction_limit = Some(30);
+        let mut counter = StepCounter(0);
+        let (result, _) = evm.inspect(&mut counter);
+        assert_eq!(result.exit_reason, Return::InstructionLimitReached);
+        assert_eq!(
+            result.exit_reason.halt(),
+            Some(crate::Halt::InstructionLimitReached)
+        );
+        assert_eq!(counter.0, 30);
+    }
+
+    #[test]
+    fn call_to_eoa_does_not_load_code() {
+        let eoa = H160::from_low_u64_be(0x42);
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(
+            eoa,
+            AccountInfo {
+                code: None,
+                ..AccountInfo::from_balance(U256::from(1))
+            },
+        );
+        assert!(!db.accounts[&eoa].info.has_code());
+        let mut evm = new();
+        evm.database(crate::db::RecorderDB::new(db));
+        evm.env.tx.transact_to = TransactTo::Call(eoa);
+
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        let queries = &evm.db().unwrap().journal;
+        assert!(!queries
+            .iter()
+            .any(|query| matches!(query, crate::db::DbQuery::CodeByHash { .. })));
+    }
+
+    #[test]
+    fn spec_selected_at_runtime() {
+        let contract = H160::from_low_u64_be(0x42);
+        // PUSH1 0x00, SLOAD, STOP
+        let mut evm = evm_with_code(contract, vec![0x60, 0x00, 0x54, 0x00]);
+        let mut sload_gas = |fork: &str| {
+            evm.env.cfg.spec_id = SpecId::from(fork);
+            let (result, _) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Stop);
+            result.gas_used - 21_000 - 3
+        };
+
+        assert_eq!(sload_gas("Petersburg"), 200);
+        assert_eq!(sload_gas("Istanbul"), 800);
+        assert_eq!(sload_gas("Berlin"), 2100);
+        assert!(!SpecId::ISTANBUL.is_eip_enabled(2929));
+        assert!(SpecId::BERLIN.is_eip_enabled(2929));
+        assert!(SpecId::BERLIN.is_eip_enabled(1884));
+        assert_eq!(SpecId::eip_activation(3855), Some(SpecId::SHANGHAI));
+        assert_eq!(SpecId::eip_activation(7702), Some(SpecId::PRAGUE));
+        // not implemented by this crate.
+        assert_eq!(SpecId::eip_activation(1153), None);
+        assert_eq!(SpecId::eip_activation(1), None);
+    }
+
+    #[test]
+    fn stack_limit() {
+        let contract = H160::from_low_u64_be(0x42);
+        let run = |pushes: usize, stack_limit: usize| {
+            // PUSH1 0x01 repeated, STOP
+            let mut code = [0x60, 0x01].repeat(pushes);
+            code.push(0x00);
+            let mut evm = evm_with_code(contract, code);
+            evm.env.cfg.stack_limit = stack_limit;
+            evm.transact().0.exit_reason
+        };
+
+        assert_eq!(CfgEnv::default().stack_limit, 1024);
+        assert_eq!(run(1024, 1024), Return::Stop);
+        assert_eq!(run(1025, 1024), Return::StackOverflow);
+        assert_eq!(run(4, 4), Return::Stop);
+        assert_eq!(run(5, 4), Return::StackOverflow);
+        assert_eq!(run(1025, 2048), Return::Stop);
+    }
+
+    #[test]
+    fn code_size_limit_exemption() {
+        let caller = H160::from_low_u64_be(0x1000);
+        let created = crate::create_address(caller, 0);
+        let create = |exempt: H160| {
+            let mut evm = new();
+            evm.database(InMemoryDB::default());
+            evm.env.tx.caller = caller;
+            evm.env.tx.transact_to = TransactTo::create();
+            // PUSH2 0x6001, PUSH1 0x00, RETURN
+            evm.env.tx.data = Bytes::from(vec![0x61, 0x60, 0x01, 0x60, 0x00, 0xf3]);
+            evm.env.tx.gas_limit = 10_000_000;
+            evm.env.cfg.contract_code_size_exempt.insert(exempt);
+            let (result, _) = evm.transact();
+            result.create_result().unwrap()
+        };
+
+        assert_eq!(create(created), CreateResult::Created(created));
+        assert_eq!(
+            create(H160::from_low_u64_be(0x42)),
+            CreateResult::CodeTooLarge
+        );
+    }
+
+    #[test]
+    fn create_result_reports_failure() {
+        let caller = H160::from_low_u64_be(0x1000);
+        let created = crate::create_address(caller, 0);
+        let create = |init_code: Vec<u8>, setup: &dyn Fn(&mut EVM<InMemoryDB>)| {
+            let mut evm = new();
+            evm.database(InMemoryDB::default());
+            evm.env.tx.caller = caller;
+            evm.env.tx.transact_to = TransactTo::create();
+            evm.env.tx.data = Bytes::from(init_code);
+            evm.env.tx.gas_limit = 100_000;
+            setup(&mut evm);
+            let (result, _) = evm.transact();
+            result.create_result().unwrap()
+        };
+        // PUSH1 0x02, PUSH1 0x00, RETURN
+        let return_two_bytes = vec![0x60, 0x02, 0x60, 0x00, 0xf3];
+
+        assert_eq!(
+            create(return_two_bytes.clone(), &|_| {}),
+            CreateResult::Created(created)
+        );
+        // PUSH1 0x00, PUSH1 0x00, REVERT
+        assert_eq!(
+            create(vec![0x60, 0x00, 0x60, 0x00, 0xfd], &|_| {}),
+            CreateResult::Reverted
+        );
+        // JUMPDEST, PUSH1 0x00, JUMP
+        assert_eq!(
+            create(vec![0x5b, 0x60, 0x00, 0x56], &|_| {}),
+            CreateResult::OutOfGas
+        );
+        assert_eq!(
+            create(return_two_bytes.clone(), &|evm| {
+                evm.db().unwrap().insert_account_info(
+                    created,
+                    AccountInfo::new(U256::zero(), 1, Bytecode::new()),
+                );
+            }),
+            CreateResult::Collision
+        );
+        // init code is over the EIP-3860 limit since Shanghai.
+        assert_eq!(
+            create(return_two_bytes, &|evm| {
+                evm.env.cfg.spec_id = SpecId::MERGE;
+                evm.env.cfg.limit_contract_code_size = 1;
+            }),
+            CreateResult::CodeTooLarge
+        );
+        // PUSH1 0xEF, PUSH1 0x00, MSTORE8, PUSH1 0x01, PUSH1 0x00, RETURN
+        assert_eq!(
+            create(
+                vec![0x60, 0xef, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3],
+                &|_| {}
+            ),
+            CreateResult::InvalidCode
+        );
+
+        // calls have no create result.
+        let mut evm = evm_with_code(H160::from_low_u64_be(0x42), vec![0x00]);
+        assert_eq!(evm.transact().0.create_result(), None);
+    }
+
+    #[test]
+    fn create_with_max_nonce_fails() {
+        let contract = H160::from_low_u64_be(0x42);
+        // PUSH1 0x00, DUP1, DUP1, CREATE, ISZERO, PUSH1 0x00, SSTORE, STOP
+        let code = vec![0x60, 0x00, 0x80, 0x80, 0xf0, 0x15, 0x60, 0x00, 0x55, 0x00];
+        let mut evm = new();
+        evm.database(InMemoryDB::default());
+        evm.db().unwrap().insert_account_info(
+            contract,
+            AccountInfo::new(U256::zero(), u64::MAX, Bytecode::new_raw(Bytes::from(code))),
+        );
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 1_000_000;
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        let account = &state[&contract];
+        assert_eq!(account.info.nonce, u64::MAX);
+        assert_eq!(account.storage[&U256::zero()].present_value(), U256::one());
+
+        // top level create from a caller at max nonce halts.
+        let caller = H160::from_low_u64_be(0x1000);
+        let mut evm = new();
+        evm.database(InMemoryDB::default());
+        evm.db().unwrap().insert_account_info(
+            caller,
+            AccountInfo::new(U256::zero(), u64::MAX, Bytecode::new()),
+        );
+        evm.env.tx.caller = caller;
+        evm.env.tx.transact_to = TransactTo::create();
+        evm.env.tx.gas_limit = 100_000;
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::NonceOverflow);
+        assert_eq!(
+            result.create_result(),
+            Some(CreateResult::Failed(Return::NonceOverflow))
+        );
+
+        let mut info = AccountInfo::new(U256::zero(), u64::MAX, Bytecode::new());
+        assert_eq!(info.inc_nonce(), None);
+        assert_eq!(info.nonce, u64::MAX);
+    }
+
+    #[test]
+    fn coinbase_is_warm_since_shanghai() {
+        let contract = H160::from_low_u64_be(0x42);
+        let coinbase = H160::from_low_u64_be(0xc0);
+        let balance_gas = |spec_id: SpecId| {
+            // PUSH20 coinbase, BALANCE, STOP
+            let mut code = vec![0x73];
+            code.extend_from_slice(coinbase.as_bytes());
+            code.extend_from_slice(&[opcode::BALANCE, 0x00]);
+            let mut evm = evm_with_code(contract, code);
+            evm.env.cfg.spec_id = spec_id;
+            evm.env.block.coinbase = coinbase;
+            let (result, _) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Stop);
+            result.gas_used
+        };
+
+        // cold account access before Shanghai, warm after.
+        assert_eq!(balance_gas(SpecId::MERGE), 21_000 + 3 + 2600);
+        assert_eq!(balance_gas(SpecId::SHANGHAI), 21_000 + 3 + 100);
+        assert_eq!(balance_gas(SpecId::LATEST), 21_000 + 3 + 100);
+    }
+
+    #[test]
+    fn push0_since_shanghai() {
+        let contract = H160::from_low_u64_be(0x42);
+        let run = |spec_id: SpecId| {
+            // PUSH0, STOP
+            let mut evm = evm_with_code(contract, vec![opcode::PUSH0, 0x00]);
+            evm.env.cfg.spec_id = spec_id;
+            let (result, _) = evm.transact();
+            (result.exit_reason, result.gas_used)
+        };
+
+        assert_eq!(run(SpecId::MERGE).0, Return::NotActivated);
+        assert_eq!(run(SpecId::SHANGHAI), (Return::Stop, 21_000 + 2));
+    }
+
+    #[test]
+    fn initcode_metered_and_limited_since_shanghai() {
+        let max_initcode_size = CfgEnv::default().max_initcode_size();
+        let create_tx = |spec_id: SpecId, len: usize| {
+            let mut evm = new();
+            evm.database(InMemoryDB::default());
+            evm.env.cfg.spec_id = spec_id;
+            evm.env.tx.transact_to = TransactTo::create();
+            evm.env.tx.data = Bytes::from(vec![0x00; len]);
+            evm.env.tx.gas_limit = 1_000_000;
+            let (result, _) = evm.transact();
+            result
+        };
+
+        // 33 bytes of init code are two words.
+        let merge = create_tx(SpecId::MERGE, 33);
+        assert!(merge.create_result().unwrap().address().is_some());
+        assert_eq!(merge.gas_used, 53_000 + 33 * 4);
+        assert_eq!(
+            create_tx(SpecId::SHANGHAI, 33).gas_used,
+            merge.gas_used + 2 * 2
+        );
+        assert_eq!(
+            create_tx(SpecId::SHANGHAI, max_initcode_size + 1).exit_reason,
+            Return::CreateInitcodeSizeLimit
+        );
+
+        let contract = H160::from_low_u64_be(0x42);
+        let create_opcode = |spec_id: SpecId, len: usize| {
+            // PUSH3 len, PUSH1 0x00, PUSH1 0x00, CREATE, STOP
+            let len = (len as u32).to_be_bytes();
+            let code = vec![
+                0x62, len[1], len[2], len[3], 0x60, 0x00, 0x60, 0x00, 0xf0, 0x00,
+            ];
+            let mut evm = evm_with_code(contract, code);
+            evm.env.cfg.spec_id = spec_id;
+            let (result, _) = evm.transact();
+            (result.exit_reason, result.gas_used)
+        };
+
+        let (exit_reason, merge_gas) = create_opcode(SpecId::MERGE, 33);
+        assert_eq!(exit_reason, Return::Stop);
+        assert_eq!(
+            create_opcode(SpecId::SHANGHAI, 33),
+            (Return::Stop, merge_gas + 2 * 2)
+        );
+        assert_eq!(
+            create_opcode(SpecId::MERGE, max_initcode_size + 1).0,
+            Return::Stop
+        );
+        assert_eq!(
+            create_opcode(SpecId::SHANGHAI, max_initcode_size + 1).0,
+            Return::CreateInitcodeSizeLimit
+        );
+    }
+
+    #[test]
+    fn chainid_and_selfbalance() {
+        let contract = H160::from_low_u64_be(0x42);
+        let read = |opcode: u8, chain_id: U256| {
+            // OPCODE, PUSH1 0x00, MSTORE, PUSH1 0x20, PUSH1 0x00, RETURN
+            let code = vec![opcode, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+            let mut evm = evm_with_code(contract, code.clone());
+            evm.env.cfg.chain_id = chain_id;
+            evm.db().unwrap().insert_account_info(
+                contract,
+                AccountInfo::new(U256::from(1234), 0, Bytecode::new_raw(Bytes::from(code))),
+            );
+            let (result, _) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Return);
+            match result.out {
+                TransactOut::Call(out) => (U256::from_big_endian(&out), result.gas_used),
+                _ => panic!("expected call output"),
+            }
+        };
+
+        assert_eq!(CfgEnv::default().chain_id, U256::one());
+        assert_eq!(read(opcode::CHAINID, U256::from(10)).0, U256::from(10));
+        assert_eq!(
+            read(opcode::CHAINID, U256::from(31337)),
+            (U256::from(31337), 21_017)
+        );
+        // SELFBALANCE costs LOW, there is no cold account access.
+        assert_eq!(
+            read(opcode::SELFBALANCE, U256::one()),
+            (U256::from(1234), 21_020)
+        );
+    }
+
+    #[test]
+    fn custom_gas_table() {
+        let contract = H160::from_low_u64_be(0x42);
+        // PUSH1 0x00, SLOAD, STOP
+        let code = Bytecode::new_raw(Bytes::from(vec![0x60, 0x00, 0x54, 0x00]));
+        let mut table = *crate::spec_opcode_gas(SpecId::LATEST);
+        table[opcode::SLOAD as usize] = table[opcode::SLOAD as usize].with_gas(100);
+        let custom = code.clone().to_analysed_with_table(&table);
+        let default = code.to_analysed::<crate::LatestSpec>();
+        match (custom.state(), default.state()) {
+            (
+                BytecodeState::Analysed {
+                    jumptable: custom, ..
+                },
+                BytecodeState::Analysed {
+                    jumptable: default, ..
+                },
+            ) => assert_eq!(custom.first_gas_block, default.first_gas_block + 100),
+            _ => panic!("expected analysed bytecode"),
+        }
+
+        let gas_used = |code: Bytecode| {
+            let mut evm = evm_with_code(contract, Vec::new());
+            evm.db()
+                .unwrap()
+                .insert_account_info(contract, AccountInfo::from_code(code));
+            let (result, _) = evm.transact();
+            assert_eq!(result.exit_reason, Return::Stop);
+            result.gas_used
+        };
+        assert_eq!(gas_used(custom), gas_used(default) + 100);
+    }
+
+    #[test]
+    fn blob_opcodes() {
+        let contract = H160::from_low_u64_be(0x42);
+        let read = |code: Vec<u8>, spec_id: SpecId| {
+            let mut code = code;
+            // PUSH1 0x00, MSTORE, PUSH1 0x20, PUSH1 0x00, RETURN
+            code.extend_from_slice(&[0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+            let mut evm = evm_with_code(contract, code);
+            evm.env.cfg.spec_id = spec_id;
+            evm.env.tx.blob_hashes = vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+            evm.env.block.blob_excess_gas = Some(50_000_000);
+            let (result, _) = evm.transact();
+            match result.out {
+                TransactOut::Call(out) if result.exit_reason == Return::Return => {
+                    Ok(U256::from_big_endian(&out))
+                }
+                _ => Err(result.exit_reason),
+            }
+        };
+
+        // PUSH1 index, BLOBHASH
+        let blob_hash = |index: u8| vec![0x60, index, opcode::BLOBHASH];
+        assert_eq!(read(blob_hash(0), SpecId::CANCUN), Ok(U256::from(1)));
+        assert_eq!(read(blob_hash(1), SpecId::CANCUN), Ok(U256::from(2)));
+        // out of range index returns zero.
+        assert_eq!(read(blob_hash(2), SpecId::CANCUN), Ok(U256::zero()));
+        // PUSH32 0xff..ff, BLOBHASH
+        let mut code = vec![0x7f];
+        code.extend_from_slice(&[0xff; 32]);
+        code.push(opcode::BLOBHASH);
+        assert_eq!(read(code, SpecId::CANCUN), Ok(U256::zero()));
+
+        assert_eq!(
+            read(vec![opcode::BLOBBASEFEE], SpecId::CANCUN),
+            Ok(U256::from(3_194_333u64))
+        );
+        assert_eq!(
+            read(vec![opcode::BLOBBASEFEE], SpecId::MERGE),
+            Err(Return::NotActivated)
+        );
+    }
+
+    #[test]
+    fn call_depth_limit() {
+        let contract = H160::from_low_u64_be(0x42);
+        let callee = H160::from_low_u64_be(0x43);
+        // CALL(0xffff, callee, 0, 0, 0, 0, 0), then SSTORE(0, success + 1)
+        let mut code = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+        ];
+        code.extend_from_slice(callee.as_bytes());
+        code.extend_from_slice(&[
+            0x61, 0xff, 0xff, 0xf1, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00,
+        ]);
+        let mut evm = evm_with_code(contract, code);
+
+        // the nested call is at depth 1, which exceeds the limit.
+        evm.env.cfg.call_depth_limit = 0;
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        let slot = state[&contract].storage[&U256::zero()].present_value();
+        assert_eq!(slot, U256::from(1));
+
+        evm.env.cfg.call_depth_limit = 1;
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        let slot = state[&contract].storage[&U256::zero()].present_value();
+        assert_eq!(slot, U256::from(2));
+    }
+
+    fn selfdestruct_code(beneficiary: H160) -> Vec<u8> {
+        // PUSH20 beneficiary, SELFDESTRUCT
+        let mut code = vec![0x73];
+        code.extend_from_slice(beneficiary.as_bytes());
+        code.push(0xff);
+        code
+    }
+
+    #[test]
+    fn selfdestruct_credits_beneficiary() {
+        let contract = H160::from_low_u64_be(0x42);
+        let beneficiary = H160::from_low_u64_be(0x43);
+        let mut evm = evm_with_code(contract, Vec::new());
+        evm.db().unwrap().insert_account_info(
+            contract,
+            AccountInfo::new(
+                U256::from(1000),
+                0,
+                Bytecode::new_raw(Bytes::from(selfdestruct_code(beneficiary))),
+            ),
+        );
+        // since Cancun only accounts created in the same transaction are removed.
+        evm.env.cfg.spec_id = SpecId::SHANGHAI;
+
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::SelfDestruct);
+        assert!(state[&contract].is_destroyed);
+        assert_eq!(
+            state[&contract].selfdestruct_transfer,
+            Some((beneficiary, U256::from(1000)))
+        );
+        assert_eq!(state[&beneficiary].info.balance, U256::from(1000));
+
+        // change set without the beneficiary gets it credited on commit.
+        let mut db = evm.db.clone().unwrap();
+        let mut without_beneficiary = state.clone();
+        without_beneficiary.remove(&beneficiary);
+        db.commit(without_beneficiary);
+        assert_eq!(db.accounts[&beneficiary].info.balance, U256::from(1000));
+
+        evm.db().unwrap().commit(state);
+        let db = evm.db.unwrap();
+        assert_eq!(db.accounts[&beneficiary].info.balance, U256::from(1000));
+        assert_eq!(db.accounts[&contract].info, AccountInfo::default());
+    }
+
+    #[test]
+    fn selfdestruct_to_self_burns_balance() {
+        let contract = H160::from_low_u64_be(0x42);
+        let mut evm = evm_with_code(contract, Vec::new());
+        evm.db().unwrap().insert_account_info(
+            contract,
+            AccountInfo::new(
+                U256::from(1000),
+                0,
+                Bytecode::new_raw(Bytes::from(selfdestruct_code(contract))),
+            ),
+        );
+        // since Cancun only accounts created in the same transaction are removed.
+        evm.env.cfg.spec_id = SpecId::SHANGHAI;
+
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::SelfDestruct);
+        assert_eq!(
+            state[&contract].selfdestruct_transfer,
+            Some((contract, U256::from(1000)))
+        );
+        assert_eq!(state[&contract].info.balance, U256::zero());
+        evm.db().unwrap().commit(state);
+        let db = evm.db.unwrap();
+        assert_eq!(db.accounts[&contract].info.balance, U256::zero());
+    }
+
+    #[test]
+    fn cancun_selfdestruct_removes_only_created_accounts() {
+        let contract = H160::from_low_u64_be(0x42);
+        let beneficiary = H160::from_low_u64_be(0x43);
+        let code = Bytecode::new_raw(Bytes::from(selfdestruct_code(beneficiary)));
+        let mut evm = evm_with_code(contract, Vec::new());
+        evm.env.cfg.spec_id = SpecId::CANCUN;
+        evm.db().unwrap().insert_account_info(
+            contract,
+            AccountInfo::new(U256::from(1000), 0, code.clone()),
+        );
+
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::SelfDestruct);
+        assert!(!state[&contract].is_destroyed);
+        assert_eq!(state[&contract].info.balance, U256::zero());
+        assert_eq!(state[&contract].info.code_hash, code.hash());
+        assert_eq!(state[&beneficiary].info.balance, U256::from(1000));
+        evm.db().unwrap().commit(state);
+        let db = evm.db().unwrap();
+        assert_eq!(db.accounts[&contract].info.code_hash, code.hash());
+        assert_eq!(db.accounts[&beneficiary].info.balance, U256::from(1000));
+
+        // init code that selfdestructs removes the account it is creating.
+        let caller = H160::from_low_u64_be(0x1000);
+        let created = crate::create_address(caller, 0);
+        evm.env.tx.caller = caller;
+        evm.env.tx.transact_to = TransactTo::create();
+        evm.env.tx.value = U256::from(10);
+        evm.env.tx.gas_price = U256::zero();
+        evm.db()
+            .unwrap()
+            .insert_account_info(caller, AccountInfo::from_balance(U256::from(10)));
+        evm.env.tx.data = Bytes::from(selfdestruct_code(beneficiary));
+        let (result, state) = evm.transact();
+        assert_eq!(result.create_result(), Some(CreateResult::Created(created)));
+        assert!(state[&created].is_created);
+        assert!(state[&created].is_destroyed);
+        assert_eq!(state[&beneficiary].info.balance, U256::from(1010));
+    }
+
+    #[test]
+    fn state_change_inside_static_call_halts() {
+        struct CallResults(Vec<(H160, Return)>);
+
+        impl<DB: Database> Inspector<DB> for CallResults {
+            fn call_end(
+                &mut self,
+                _data: &mut crate::EVMData<'_, DB>,
+                inputs: &crate::CallInputs,
+                remaining_gas: crate::Gas,
+                ret: Return,
+                out: Bytes,
+                _is_static: bool,
+            ) -> (Return, crate::Gas, Bytes) {
+                self.0.push((inputs.contract, ret));
+                (ret, remaining_gas, out)
+            }
+        }
+
+        // halted call consumes all gas given to it, so give each call a fixed amount.
+        let static_call = |code: BytecodeBuilder, to: u64| {
+            code.push(0)
+                .op(opcode::DUP1)
+                .op(opcode::DUP1)
+                .op(opcode::DUP1)
+                .push(to)
+                .push(100_000)
+                .op(opcode::STATICCALL)
+        };
+        let contract = H160::from_low_u64_be(0x42);
+        let sstore = H160::from_low_u64_be(0x43);
+        let sload = H160::from_low_u64_be(0x44);
+        let nested = H160::from_low_u64_be(0x45);
+        let code = static_call(BytecodeBuilder::new(), 0x43)
+            .push(0)
+            .op(opcode::SSTORE);
+        let code = static_call(code, 0x44).push(1).op(opcode::SSTORE);
+        let code = static_call(code, 0x45)
+            .push(2)
+            .op(opcode::SSTORE)
+            .op(opcode::STOP);
+        let mut evm = evm_with_code(contract, code.into_bytes());
+        let db = evm.db().unwrap();
+        db.insert_account_info(
+            sstore,
+            AccountInfo::from_code(
+                BytecodeBuilder::new()
+                    .push(1)
+                    .push(0)
+                    .op(opcode::SSTORE)
+                    .op(opcode::STOP)
+                    .build(),
+            ),
+        );
+        db.insert_account_info(
+            sload,
+            AccountInfo::from_code(
+                BytecodeBuilder::new()
+                    .push(0)
+                    .op(opcode::SLOAD)
+                    .op(opcode::STOP)
+                    .build(),
+            ),
+        );
+        // plain CALL without value from a static context is still static.
+        db.insert_account_info(
+            nested,
+            AccountInfo::from_code(
+                BytecodeBuilder::new()
+                    .push(0)
+                    .op(opcode::DUP1)
+                    .op(opcode::DUP1)
+                    .op(opcode::DUP1)
+                    .op(opcode::DUP1)
+                    .push(0x43)
+                    .op(opcode::GAS)
+                    .op(opcode::CALL)
+                    .op(opcode::STOP)
+                    .build(),
+            ),
+        );
+
+        let mut results = CallResults(Vec::new());
+        let (result, state) = evm.inspect(&mut results);
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(
+            results.0,
+            vec![
+                (sstore, Return::CallNotAllowedInsideStatic),
+                (sload, Return::Stop),
+                (sstore, Return::CallNotAllowedInsideStatic),
+                (nested, Return::Stop),
+                (contract, Return::Stop),
+            ]
+        );
+        assert_eq!(
+            Return::CallNotAllowedInsideStatic.halt(),
+            Some(crate::Halt::StateChangeDuringStaticCall)
+        );
+        let storage = &state[&contract].storage;
+        assert_eq!(storage[&U256::zero()].present_value(), U256::zero());
+        assert_eq!(storage[&U256::one()].present_value(), U256::one());
+        assert_eq!(storage[&U256::from(2)].present_value(), U256::one());
+    }
+
+    #[test]
+    fn inspector_reads_stack_and_memory() {
+        struct StepRecorder(Vec<(usize, Vec<U256>, Vec<u8>)>);
+
+        impl<DB: Database> Inspector<DB> for StepRecorder {
+            fn step(
+                &mut self,
+                interp: &mut crate::Interpreter,
+                _data: &mut crate::EVMData<'_, DB>,
+                _is_static: bool,
+            ) -> Return {
+                self.0.push((
+                    interp.program_counter(),
+                    interp.stack().to_vec(),
+                    interp.memory().to_vec(),
+                ));
+                Return::Continue
+            }
+        }
+
+        let contract = H160::from_low_u64_be(0x42);
+        // PUSH1 0x2a, PUSH1 0x00, MSTORE, STOP
+        let mut evm = evm_with_code(contract, vec![0x60, 0x2a, 0x60, 0x00, 0x52, 0x00]);
+        let mut recorder = StepRecorder(Vec::new());
+        let (result, _) = evm.inspect(&mut recorder);
+        assert_eq!(result.exit_reason, Return::Stop);
+
+        let steps = recorder.0;
+        let pcs: Vec<usize> = steps.iter().map(|(pc, _, _)| *pc).collect();
+        assert_eq!(pcs, vec![0, 2, 4, 5]);
+        assert_eq!(steps[2].1, vec![U256::from(0x2a), U256::zero()]);
+        assert!(steps[2].2.is_empty());
+        assert!(steps[3].1.is_empty());
+        assert_eq!(steps[3].2.len(), 32);
+        assert_eq!(steps[3].2[31], 0x2a);
+    }
+
+    #[test]
+    fn reverted_logs_are_kept_separately() {
+        let contract = H160::from_low_u64_be(0x42);
+        let callee = H160::from_low_u64_be(0x43);
+        // CALL callee, LOG1 with topic 1, STOP
+        let code = BytecodeBuilder::new()
+            .push(0)
+            .op(opcode::DUP1)
+            .op(opcode::DUP1)
+            .op(opcode::DUP1)
+            .op(opcode::DUP1)
+            .push(U256::from_big_endian(callee.as_bytes()))
+            .op(opcode::GAS)
+            .op(opcode::CALL)
+            .op(opcode::POP)
+            .push(1)
+            .push(0)
+            .op(opcode::DUP1)
+            .op(opcode::LOG1)
+            .op(opcode::STOP)
+            .into_bytes();
+        // LOG1 with topic 2, REVERT
+        let callee_code = BytecodeBuilder::new()
+            .push(2)
+            .push(0)
+            .op(opcode::DUP1)
+            .op(opcode::LOG1)
+            .push(0)
+            .op(opcode::DUP1)
+            .op(opcode::REVERT)
+            .build();
+        let mut evm = evm_with_code(contract, code);
+        evm.db()
+            .unwrap()
+            .insert_account_info(callee, AccountInfo::from_code(callee_code));
+
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(result.logs.len(), 1);
+        assert_eq!(result.logs[0].topics, vec![H256::from_low_u64_be(1)]);
+        assert!(result.reverted_logs.is_empty());
+
+        evm.env.cfg.keep_reverted_logs = true;
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(result.logs.len(), 1);
+        assert_eq!(result.logs[0].topics, vec![H256::from_low_u64_be(1)]);
+        assert_eq!(result.reverted_logs.len(), 1);
+        assert_eq!(result.reverted_logs[0].address, callee);
+        assert_eq!(
+            result.reverted_logs[0].topics,
+            vec![H256::from_low_u64_be(2)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "secp256r1")]
+    fn p256_verify_enabled_by_cfg() {
+        let p256 = H160::from_low_u64_be(0x100);
+        let mut evm = evm_with_code(H160::from_low_u64_be(0x42), vec![0x00]);
+        evm.env.tx.transact_to = TransactTo::Call(p256);
+        // 160 zero bytes, invalid signature.
+        evm.env.tx.data = vec![0u8; 160].into();
+
+        // without precompile the call hits an empty account.
+        let (result, _) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(result.gas_used, 21_000 + 160 * 4);
+
+        evm.env.cfg.p256_verify = true;
+        let (result, _) = evm.transact();
+        assert_eq!(result.gas_used, 21_000 + 160 * 4 + 3_450);
+        match result.out {
+            TransactOut::Call(out) => assert!(out.is_empty()),
+            _ => panic!("expected call output"),
+        }
     }
 }
@@ -1,23 +1,33 @@
 use crate::{
     db::Database,
     gas,
-    interpreter::{self, bytecode::Bytecode},
-    interpreter::{Contract, Interpreter},
+    interpreter::bytecode::Bytecode,
+    interpreter::{Contract, Interpreter, InterpreterResult},
     journaled_state::{Account, JournaledState, State},
     models::SelfDestructResult,
-    return_ok, CallContext, CallInputs, CallScheme, CreateInputs, CreateScheme, Env,
-    ExecutionResult, Gas, Inspector, Log, Return, Spec,
+    return_ok, CallContext, CallInputs, CallScheme, CfgEnv, CreateInputs, CreateScheme, Env,
+    ExecutionResult, Gas, HookStorage, Inspector, Log, Return, Spec,
     SpecId::*,
     TransactOut, TransactTo, Transfer, KECCAK_EMPTY,
 };
 use alloc::vec::Vec;
 use bytes::Bytes;
-use core::{cmp::min, marker::PhantomData};
+use core::marker::PhantomData;
 use hashbrown::HashMap as Map;
 use primitive_types::{H160, H256, U256};
-use revm_precompiles::{Precompile, PrecompileOutput, Precompiles};
+use revm_precompiles::{PrecompileOutput, Precompiles, Return as PrecompileReturn};
 use sha3::{Digest, Keccak256};
 
+/// Apply interpreter settings of `cfg` that are not passed to the constructor.
+fn configure_interpreter(interp: &mut Interpreter, cfg: &CfgEnv) {
+    interp.stack.set_limit(cfg.stack_limit);
+    if cfg.disabled_opcodes.contains(&true) {
+        interp.disabled_opcodes = Some(cfg.disabled_opcodes);
+    }
+    interp.gas_per_opcode = cfg.gas_per_opcode;
+    interp.limit_instructions = cfg.instruction_limit.is_some();
+}
+
 pub struct EVMData<'a, DB> {
     pub env: &'a mut Env,
     pub journaled_state: JournaledState,
@@ -28,6 +38,10 @@ pub struct EVMImpl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> {
     data: EVMData<'a, DB>,
     precompiles: Precompiles,
     inspector: &'a mut dyn Inspector<DB>,
+    /// Instructions that can still be executed, see [`CfgEnv::instruction_limit`].
+    instructions_left: u64,
+    /// Whether data returned by some call was truncated, see [`CfgEnv::max_returndata_size`].
+    returndata_truncated: bool,
     _phantomdata: PhantomData<GSPEC>,
 }
 
@@ -45,9 +59,11 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> Transact
         let value = self.data.env.tx.value;
         let data = self.data.env.tx.data.clone();
         let gas_limit = self.data.env.tx.gas_limit;
+        let is_deposit = self.data.env.tx.is_deposit();
         let exit = |reason: Return| (ExecutionResult::new_with_reason(reason), State::new());
 
-        if GSPEC::enabled(LONDON) {
+        // Optimism deposits are paid for on L1, fee checks do not apply to them.
+        if GSPEC::enabled(LONDON) && !is_deposit {
             if let Some(priority_fee) = self.data.env.tx.gas_priority_fee {
                 if priority_fee > self.data.env.tx.gas_price {
                     // or gas_max_fee for eip1559
@@ -70,9 +86,26 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> Transact
             return exit(Return::CallerGasLimitMoreThenBlock);
         }
 
+        // EIP-7702: authorizations are only valid in call transactions, since Prague.
+        if !self.data.env.tx.authorization_list.is_empty()
+            && (!GSPEC::enabled(PRAGUE)
+                || matches!(self.data.env.tx.transact_to, TransactTo::Create(_)))
+        {
+            return exit(Return::InvalidAuthorizationList);
+        }
+
+        // EIP-3860: Limit and meter initcode
+        if GSPEC::enabled(SHANGHAI)
+            && matches!(self.data.env.tx.transact_to, TransactTo::Create(_))
+            && data.len() > self.data.env.cfg.max_initcode_size()
+        {
+            return exit(Return::CreateInitcodeSizeLimit);
+        }
+
         let mut gas = Gas::new(gas_limit);
         // record initial gas cost. if not using gas metering init will return 0
-        if !gas.record_cost(self.initialization::<GSPEC>()) {
+        let intrinsic_gas = self.initialization::<GSPEC>();
+        if !gas.record_cost(intrinsic_gas) {
             return exit(Return::OutOfGas);
         }
 
@@ -81,15 +114,48 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> Transact
 
         // EIP-3607: Reject transactions from senders with deployed code
         // This EIP is introduced after london but there was no colision in past
-        // so we can leave it enabled always
-        if self.data.journaled_state.account(caller).info.code_hash != KECCAK_EMPTY {
-            return exit(Return::RejectCallerWithCode);
+        // so we can leave it enabled always. EIP-7702 allows senders that delegate their code.
+        if !is_deposit && self.data.journaled_state.account(caller).info.code_hash != KECCAK_EMPTY {
+            let (account, _) = self.data.journaled_state.load_code(caller, self.data.db);
+            if account
+                .info
+                .code
+                .as_ref()
+                .unwrap()
+                .delegation_address()
+                .is_none()
+            {
+                return exit(Return::RejectCallerWithCode);
+            }
         }
 
+        #[cfg(feature = "optimism")]
+        let l1_cost = if is_deposit {
+            if let Some(mint) = self.data.env.tx.optimism.mint {
+                let balance = &mut self
+                    .data
+                    .journaled_state
+                    .state
+                    .get_mut(&caller)
+                    .unwrap()
+                    .info
+                    .balance;
+                *balance = balance.saturating_add(mint);
+            }
+            U256::zero()
+        } else {
+            self.data.env.tx.optimism.l1_cost
+        };
+        #[cfg(not(feature = "optimism"))]
+        let l1_cost = U256::zero();
+
         // substract gas_limit*gas_price from current account.
-        if let Some(payment_value) =
+        let gas_payment = if is_deposit {
+            Some(U256::zero())
+        } else {
             U256::from(gas_limit).checked_mul(self.data.env.effective_gas_price())
-        {
+        };
+        if let Some(payment_value) = gas_payment.and_then(|payment| payment.checked_add(l1_cost)) {
             let balance = &mut self
                 .data
                 .journaled_state
@@ -106,6 +172,23 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> Transact
             return exit(Return::OverflowPayment);
         }
 
+        #[cfg(feature = "optimism")]
+        if !l1_cost.is_zero() {
+            self.data
+                .journaled_state
+                .load_account(crate::L1_FEE_RECIPIENT, self.data.db);
+            self.data.journaled_state.touch(&crate::L1_FEE_RECIPIENT);
+            let vault = &mut self
+                .data
+                .journaled_state
+                .state
+                .get_mut(&crate::L1_FEE_RECIPIENT)
+                .unwrap()
+                .info
+                .balance;
+            *vault = vault.saturating_add(l1_cost);
+        }
+
         // check if we have enought balance for value transfer.
         let difference = self.data.env.tx.gas_price - self.data.env.effective_gas_price();
         if difference + value > self.data.journaled_state.account(caller).info.balance {
@@ -125,6 +208,14 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> Transact
                     // overflow
                     return exit(Return::NonceOverflow);
                 }
+                self.apply_authorizations::<GSPEC>(&mut gas);
+                // EIP-7702: delegate of the called account is warm, without charge.
+                if GSPEC::enabled(PRAGUE) {
+                    let (code, _) = self.code(address);
+                    if let Some(delegate) = code.delegation_address() {
+                        self.load_account(delegate);
+                    }
+                }
                 let context = CallContext {
                     caller,
                     address,
@@ -162,15 +253,18 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> Transact
         if crate::USE_GAS {
             gas.reimburse_unspend(&exit_reason, ret_gas);
         }
-
         let (state, logs, gas_used, gas_refunded) = self.finalize::<GSPEC>(caller, &gas);
+        let reverted_logs = core::mem::take(&mut self.data.journaled_state.reverted_logs);
         (
             ExecutionResult {
                 exit_reason,
                 out,
                 gas_used,
                 gas_refunded,
+                intrinsic_gas,
                 logs,
+                reverted_logs,
+                returndata_truncated: self.returndata_truncated,
             },
             state,
         )
@@ -182,9 +276,22 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
         db: &'a mut DB,
         env: &'a mut Env,
         inspector: &'a mut dyn Inspector<DB>,
-        precompiles: Precompiles,
+        #[allow(unused_mut)] mut precompiles: Precompiles,
     ) -> Self {
-        let mut journaled_state = JournaledState::default();
+        #[cfg(feature = "secp256r1")]
+        if env.cfg.p256_verify {
+            let (address, precompile) = revm_precompiles::P256VERIFY;
+            precompiles.insert(
+                address,
+                precompile,
+                Some(revm_precompiles::P256VERIFY_GAS.1),
+            );
+        }
+        let instructions_left = env.cfg.instruction_limit.unwrap_or(u64::MAX);
+        let mut journaled_state = JournaledState {
+            keep_reverted_logs: env.cfg.keep_reverted_logs,
+            ..Default::default()
+        };
         if env.cfg.perf_all_precompiles_have_balance {
             // load precompiles without asking db.
             let mut precompile_acc = Vec::new();
@@ -205,6 +312,8 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
                 journaled_state,
                 db,
             },
+            instructions_left,
+            returndata_truncated: false,
             precompiles,
             inspector,
             _phantomdata: PhantomData {},
@@ -218,11 +327,14 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
     ) -> (Map<H160, Account>, Vec<Log>, u64, u64) {
         let coinbase = self.data.env.block.coinbase;
         let (gas_used, gas_refunded) = if crate::USE_GAS {
-            let effective_gas_price = self.data.env.effective_gas_price();
+            // deposits did not pay for gas, so nothing is refunded or rewarded.
+            let effective_gas_price = if self.data.env.tx.is_deposit() {
+                U256::zero()
+            } else {
+                self.data.env.effective_gas_price()
+            };
             let basefee = self.data.env.block.basefee;
-            let max_refund_quotient = if SPEC::enabled(LONDON) { 5 } else { 2 }; // EIP-3529: Reduction in refunds
-
-            let gas_refunded = min(gas.refunded() as u64, gas.spend() / max_refund_quotient);
+            let gas_refunded = gas.final_refund::<SPEC>();
             let acc_caller = self.data.journaled_state.state().get_mut(&caller).unwrap();
             acc_caller.info.balance = acc_caller
                 .info
@@ -246,10 +358,12 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
                 .state()
                 .get_mut(&coinbase)
                 .unwrap();
-            acc_coinbase.info.balance = acc_coinbase
-                .info
-                .balance
-                .saturating_add(coinbase_gas_price * (gas.spend() - gas_refunded));
+            if !self.data.env.cfg.defer_coinbase_reward {
+                acc_coinbase.info.balance = acc_coinbase
+                    .info
+                    .balance
+                    .saturating_add(coinbase_gas_price * (gas.spend() - gas_refunded));
+            }
             (gas.spend() - gas_refunded, gas_refunded)
         } else {
             // touch coinbase
@@ -279,58 +393,95 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
         self.data.journaled_state.load_account(caller, self.data.db)
     }
 
+    /// EIP-7702: Set EOA account code. Invalid authorizations are skipped. Applied changes are
+    /// kept even if execution reverts.
+    fn apply_authorizations<SPEC: Spec>(&mut self, gas: &mut Gas) {
+        if !SPEC::enabled(PRAGUE) {
+            return;
+        }
+        let chain_id = self.data.env.cfg.chain_id;
+        for authorization in self.data.env.tx.authorization_list.clone() {
+            if !authorization.chain_id.is_zero() && authorization.chain_id != chain_id {
+                continue;
+            }
+            if authorization.nonce == u64::MAX {
+                continue;
+            }
+            let authority = match authorization.authority() {
+                Some(authority) => authority,
+                None => continue,
+            };
+
+            let (_, exists) = self
+                .data
+                .journaled_state
+                .load_account_exist(authority, self.data.db);
+            let (account, _) = self.data.journaled_state.load_code(authority, self.data.db);
+            let code = account.info.code.as_ref().unwrap();
+            if !code.is_empty() && code.delegation_address().is_none() {
+                continue;
+            }
+            if account.info.nonce != authorization.nonce {
+                continue;
+            }
+            // intrinsic gas assumed a new account for every authorization.
+            if crate::USE_GAS && exists {
+                gas.record_refund((gas::PER_EMPTY_ACCOUNT_COST - gas::PER_AUTH_BASE_COST) as i64);
+            }
+
+            let code = if authorization.address.is_zero() {
+                Bytecode::new()
+            } else {
+                Bytecode::new_delegation(authorization.address)
+            };
+            self.data.journaled_state.set_code(authority, code);
+            self.data.journaled_state.inc_nonce(authority);
+        }
+    }
+
     fn initialization<SPEC: Spec>(&mut self) -> u64 {
         let is_create = matches!(self.data.env.tx.transact_to, TransactTo::Create(_));
         let input = &self.data.env.tx.data;
 
-        if crate::USE_GAS {
-            let zero_data_len = input.iter().filter(|v| **v == 0).count() as u64;
-            let non_zero_data_len = (input.len() as u64 - zero_data_len) as u64;
-            let (accessed_accounts, accessed_slots) = {
-                if SPEC::enabled(BERLIN) {
-                    let mut accessed_slots = 0_u64;
-
-                    for (address, slots) in self.data.env.tx.access_list.iter() {
-                        self.data
-                            .journaled_state
-                            .load_account(*address, self.data.db);
-                        accessed_slots += slots.len() as u64;
-                        for slot in slots {
-                            self.data
-                                .journaled_state
-                                .sload(*address, *slot, self.data.db);
-                        }
-                    }
-                    (self.data.env.tx.access_list.len() as u64, accessed_slots)
-                } else {
-                    (0, 0)
-                }
-            };
+        // EIP-3651: Warm COINBASE
+        if SPEC::enabled(SHANGHAI) {
+            self.data
+                .journaled_state
+                .load_account(self.data.env.block.coinbase, self.data.db);
+        }
 
-            let transact = if is_create {
-                if SPEC::enabled(HOMESTEAD) {
-                    // EIP-2: Homestead Hard-fork Changes
-                    53000
-                } else {
-                    21000
+        if SPEC::enabled(BERLIN) {
+            for (address, slots) in self.data.env.tx.access_list.iter() {
+                self.data
+                    .journaled_state
+                    .load_account(*address, self.data.db);
+                for slot in slots {
+                    self.data
+                        .journaled_state
+                        .sload(*address, *slot, self.data.db);
                 }
-            } else {
-                21000
-            };
-
-            // EIP-2028: Transaction data gas cost reduction
-            let gas_transaction_non_zero_data = if SPEC::enabled(ISTANBUL) { 16 } else { 68 };
+            }
+        }
 
-            transact
-                + zero_data_len * gas::TRANSACTION_ZERO_DATA
-                + non_zero_data_len * gas_transaction_non_zero_data
-                + accessed_accounts * gas::ACCESS_LIST_ADDRESS
-                + accessed_slots * gas::ACCESS_LIST_STORAGE_KEY
+        if crate::USE_GAS {
+            gas::initial_tx_gas::<SPEC>(
+                input,
+                is_create,
+                &self.data.env.tx.access_list,
+                self.data.env.tx.authorization_list.len(),
+            )
         } else {
             0
         }
     }
 
+    fn hook_storage(&mut self) -> HookStorage<'_> {
+        HookStorage {
+            journaled_state: &mut self.data.journaled_state,
+            db: self.data.db,
+        }
+    }
+
     fn create_inner<SPEC: Spec>(
         &mut self,
         inputs: &mut CreateInputs,
@@ -349,7 +500,7 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
         self.load_account(inputs.caller);
 
         // Check depth of calls
-        if self.data.journaled_state.depth() > interpreter::CALL_STACK_LIMIT {
+        if self.data.journaled_state.depth() > self.data.env.cfg.call_depth_limit {
             return (Return::CallTooDeep, None, gas, Bytes::new());
         }
         // Check balance of caller and value. Do this before increasing nonce
@@ -357,19 +508,23 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
             return (Return::OutOfFund, None, gas, Bytes::new());
         }
 
-        // Increase nonce of caller and check if it overflows
+        // Increase nonce of caller and check if it overflows (EIP-2681)
         let old_nonce;
         if let Some(nonce) = self.data.journaled_state.inc_nonce(inputs.caller) {
             old_nonce = nonce - 1;
         } else {
-            return (Return::Return, None, gas, Bytes::new());
+            return (Return::NonceOverflow, None, gas, Bytes::new());
         }
 
         // Create address
         let code_hash = H256::from_slice(Keccak256::digest(&inputs.init_code).as_slice());
         let created_address = match inputs.scheme {
             CreateScheme::Create => create_address(inputs.caller, old_nonce),
-            CreateScheme::Create2 { salt } => create2_address(inputs.caller, code_hash, salt),
+            CreateScheme::Create2 { salt } => {
+                let mut salt_bytes = H256::zero();
+                salt.to_big_endian(salt_bytes.as_bytes_mut());
+                create2_address(inputs.caller, salt_bytes, code_hash)
+            }
         };
         let ret = Some(created_address);
 
@@ -410,7 +565,7 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
         {
             // overflow
             self.data.journaled_state.checkpoint_revert(checkpoint);
-            return (Return::Return, None, gas, Bytes::new());
+            return (Return::NonceOverflow, None, gas, Bytes::new());
         }
 
         // Create new interpreter and execute initcode
@@ -431,40 +586,51 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
 
         #[cfg(not(feature = "memory_limit"))]
         let mut interp = Interpreter::new::<SPEC>(contract, gas.limit());
+        configure_interpreter(&mut interp, &self.data.env.cfg);
 
         if Self::INSPECT {
             self.inspector
                 .initialize_interp(&mut interp, &mut self.data, SPEC::IS_STATIC_CALL);
         }
-        let exit_reason = interp.run::<Self, SPEC>(self);
+        let InterpreterResult {
+            result: exit_reason,
+            mut gas,
+            output,
+        } = interp.run::<Self, SPEC>(self);
 
         // Host error if present on execution\
         let (ret, address, gas, out) = match exit_reason {
             return_ok!() => {
                 let b = Bytes::new();
                 // if ok, check contract creation limit and calculate gas deduction on output len.
-                let bytes = interp.return_value();
+                let bytes = output;
 
                 // EIP-3541: Reject new contract code starting with the 0xEF byte
                 if SPEC::enabled(LONDON) && !bytes.is_empty() && bytes.first() == Some(&0xEF) {
                     self.data.journaled_state.checkpoint_revert(checkpoint);
-                    return (Return::CreateContractWithEF, ret, interp.gas, b);
+                    return (Return::CreateContractWithEF, ret, gas, b);
                 }
 
                 // EIP-170: Contract code size limit
                 // By default limit is 0x6000 (~25kb)
                 if SPEC::enabled(SPURIOUS_DRAGON)
                     && bytes.len() > self.data.env.cfg.limit_contract_code_size
+                    && !self
+                        .data
+                        .env
+                        .cfg
+                        .contract_code_size_exempt
+                        .contains(&created_address)
                 {
                     self.data.journaled_state.checkpoint_revert(checkpoint);
-                    return (Return::CreateContractLimit, ret, interp.gas, b);
+                    return (Return::CreateContractLimit, ret, gas, b);
                 }
                 if crate::USE_GAS {
                     let gas_for_code = bytes.len() as u64 * crate::gas::CODEDEPOSIT;
                     // record code deposit gas cost and check if we are out of gas.
-                    if !interp.gas.record_cost(gas_for_code) {
+                    if !gas.record_cost(gas_for_code) {
                         self.data.journaled_state.checkpoint_revert(checkpoint);
-                        return (Return::OutOfGas, ret, interp.gas, b);
+                        return (Return::OutOfGas, ret, gas, b);
                     }
                 }
                 // if we have enought gas
@@ -474,11 +640,12 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
                 self.data
                     .journaled_state
                     .set_code(created_address, bytecode);
-                (Return::Continue, ret, interp.gas, b)
+                (Return::Continue, ret, gas, b)
             }
             _ => {
                 self.data.journaled_state.checkpoint_revert(checkpoint);
-                (exit_reason, ret, interp.gas, interp.return_value())
+                let (exit_reason, output) = self.limit_returndata(exit_reason, output);
+                (exit_reason, ret, gas, output)
             }
         };
 
@@ -490,6 +657,22 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
         }
     }
 
+    /// Apply [`CfgEnv::max_returndata_size`] to data returned by a call or create that ended
+    /// with `result`. Halting makes the call consume all of its gas.
+    fn limit_returndata(&mut self, result: Return, mut output: Bytes) -> (Return, Bytes) {
+        match self.data.env.cfg.max_returndata_size {
+            Some(max) if output.len() > max => {
+                if self.data.env.cfg.halt_on_returndata_limit {
+                    return (Return::ReturnDataLimitReached, Bytes::new());
+                }
+                output.truncate(max);
+                self.returndata_truncated = true;
+                (result, output)
+            }
+            _ => (result, output),
+        }
+    }
+
     fn call_inner<SPEC: Spec>(&mut self, inputs: &mut CallInputs) -> (Return, Gas, Bytes) {
         // Call the inspector
         if INSPECT {
@@ -511,9 +694,14 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
         let mut gas = Gas::new(inputs.gas_limit);
         // Load account and get code. Account is now hot.
         let (bytecode, _) = self.code(inputs.contract);
+        // EIP-7702: execute code of the account that execution is delegated to.
+        let bytecode = match bytecode.delegation_address() {
+            Some(delegate) if SPEC::enabled(PRAGUE) => self.code(delegate).0,
+            _ => bytecode,
+        };
 
         // Check depth
-        if self.data.journaled_state.depth() > interpreter::CALL_STACK_LIMIT {
+        if self.data.journaled_state.depth() > self.data.env.cfg.call_depth_limit {
             let (ret, gas, out) = (Return::CallTooDeep, gas, Bytes::new());
             if Self::INSPECT {
                 return self.inspector.call_end(
@@ -562,11 +750,10 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
         }
 
         // Call precompiles
-        let (ret, gas, out) = if let Some(precompile) = self.precompiles.get(&inputs.contract) {
-            let out = match precompile {
-                Precompile::Standard(fun) => fun(inputs.input.as_ref(), inputs.gas_limit),
-                Precompile::Custom(fun) => fun(inputs.input.as_ref(), inputs.gas_limit),
-            };
+        let (ret, gas, out) = if let Some(out) =
+            self.precompiles
+                .run(&inputs.contract, inputs.input.as_ref(), inputs.gas_limit)
+        {
             match out {
                 Ok(PrecompileOutput { output, cost, logs }) => {
                     if !crate::USE_GAS || gas.record_cost(cost) {
@@ -577,13 +764,23 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
                                 data: l.data,
                             })
                         });
-                        self.data.journaled_state.checkpoint_commit();
-                        (Return::Continue, gas, Bytes::from(output))
+                        let (ret, output) =
+                            self.limit_returndata(Return::Continue, Bytes::from(output));
+                        if ret == Return::Continue {
+                            self.data.journaled_state.checkpoint_commit();
+                        } else {
+                            self.data.journaled_state.checkpoint_revert(checkpoint);
+                        }
+                        (ret, gas, output)
                     } else {
                         self.data.journaled_state.checkpoint_revert(checkpoint);
                         (Return::OutOfGas, gas, Bytes::new())
                     }
                 }
+                Err(PrecompileReturn::OutOfGas) => {
+                    self.data.journaled_state.checkpoint_revert(checkpoint);
+                    (Return::OutOfGas, gas, Bytes::new())
+                }
                 Err(_e) => {
                     self.data.journaled_state.checkpoint_revert(checkpoint); //TODO check if we are discarding or reverting
                     (Return::PrecompileError, gas, Bytes::new())
@@ -603,20 +800,26 @@ impl<'a, GSPEC: Spec, DB: Database, const INSPECT: bool> EVMImpl<'a, GSPEC, DB,
 
             #[cfg(not(feature = "memory_limit"))]
             let mut interp = Interpreter::new::<SPEC>(contract, gas.limit());
+            configure_interpreter(&mut interp, &self.data.env.cfg);
 
             if Self::INSPECT {
                 // create is always no static call.
                 self.inspector
                     .initialize_interp(&mut interp, &mut self.data, false);
             }
-            let exit_reason = interp.run::<Self, SPEC>(self);
-            if matches!(exit_reason, return_ok!()) {
+            let InterpreterResult {
+                result,
+                gas,
+                output,
+            } = interp.run::<Self, SPEC>(self);
+            let (result, output) = self.limit_returndata(result, output);
+            if matches!(result, return_ok!()) {
                 self.data.journaled_state.checkpoint_commit();
             } else {
                 self.data.journaled_state.checkpoint_revert(checkpoint);
             }
 
-            (exit_reason, interp.gas, interp.return_value())
+            (result, gas, output)
         };
 
         if INSPECT {
@@ -643,6 +846,14 @@ impl<'a, GSPEC: Spec, DB: Database + 'a, const INSPECT: bool> Host
             .step_end(interp, &mut self.data, is_static, ret)
     }
 
+    fn record_instruction(&mut self) -> bool {
+        if self.instructions_left == 0 {
+            return false;
+        }
+        self.instructions_left -= 1;
+        true
+    }
+
     fn env(&mut self) -> &mut Env {
         self.data.env
     }
@@ -670,19 +881,42 @@ impl<'a, GSPEC: Spec, DB: Database + 'a, const INSPECT: bool> Host
         (acc.info.code.clone().unwrap(), is_cold)
     }
 
-    /// Get code hash of address.
+    fn code_size(&mut self, address: H160) -> (usize, bool) {
+        let is_cold = self
+            .data
+            .journaled_state
+            .load_account(address, self.data.db);
+        let info = &self.data.journaled_state.account(address).info;
+        let size = match &info.code {
+            Some(code) => code.len(),
+            None if !info.has_code() => 0,
+            None => self.data.db.code_size(info.code_hash),
+        };
+        (size, is_cold)
+    }
+
+    /// Get code hash of address as defined by EIP-1052: zero for nonexistent or empty
+    /// accounts, [`KECCAK_EMPTY`] for accounts without code. Code is not loaded.
     fn code_hash(&mut self, address: H160) -> (H256, bool) {
-        let (acc, is_cold) = self.data.journaled_state.load_code(address, self.data.db);
+        let is_cold = self
+            .data
+            .journaled_state
+            .load_account(address, self.data.db);
         //asume that all precompiles have some balance
         let is_precompile = self.precompiles.contains(&address);
         if is_precompile && self.data.env.cfg.perf_all_precompiles_have_balance {
             return (KECCAK_EMPTY, is_cold);
         }
-        if acc.is_empty() {
-            return (H256::zero(), is_cold);
-        }
-
-        (acc.info.code_hash, is_cold)
+        let info = &self.data.journaled_state.account(address).info;
+        let code_hash = if info.is_empty() {
+            H256::zero()
+        } else if !info.has_code() {
+            // zero hash of database that does not set it means no code.
+            KECCAK_EMPTY
+        } else {
+            info.code_hash
+        };
+        (code_hash, is_cold)
     }
 
     fn sload(&mut self, address: H160, index: U256) -> (U256, bool) {
@@ -698,6 +932,31 @@ impl<'a, GSPEC: Spec, DB: Database + 'a, const INSPECT: bool> Host
             .sstore(address, index, value, self.data.db)
     }
 
+    fn hook_sload(&mut self, address: H160, index: U256, value: U256, cost: u64) -> (U256, u64) {
+        match self.data.env.cfg.storage_hook.clone() {
+            Some(hook) => hook
+                .0
+                .sload(&mut self.hook_storage(), address, index, value, cost),
+            None => (value, cost),
+        }
+    }
+
+    fn hook_sstore(&mut self, address: H160, index: U256, value: U256) -> U256 {
+        match self.data.env.cfg.storage_hook.clone() {
+            Some(hook) => hook
+                .0
+                .sstore(&mut self.hook_storage(), address, index, value),
+            None => value,
+        }
+    }
+
+    fn hook_sstore_cost(&mut self, address: H160, index: U256, cost: u64) -> u64 {
+        match &self.data.env.cfg.storage_hook {
+            Some(hook) => hook.0.sstore_cost(address, index, cost),
+            None => cost,
+        }
+    }
+
     fn log(&mut self, address: H160, topics: Vec<H256>, data: Bytes) {
         if INSPECT {
             self.inspector.log(&mut self.data, &address, &topics, &data);
@@ -716,7 +975,7 @@ impl<'a, GSPEC: Spec, DB: Database + 'a, const INSPECT: bool> Host
         }
         self.data
             .journaled_state
-            .selfdestruct(address, target, self.data.db)
+            .selfdestruct::<GSPEC, DB>(address, target, self.data.db)
     }
 
     fn create<SPEC: Spec>(
@@ -731,26 +990,22 @@ impl<'a, GSPEC: Spec, DB: Database + 'a, const INSPECT: bool> Host
     }
 }
 
-pub fn create_address(caller: H160, nonce: u64) -> H160 {
+/// Address of contract created by `deployer` with `CREATE` at `nonce`.
+pub fn create_address(deployer: H160, nonce: u64) -> H160 {
     let mut stream = rlp::RlpStream::new_list(2);
-    stream.append(&caller);
+    stream.append(&deployer);
     stream.append(&nonce);
-    let out = H256::from_slice(Keccak256::digest(&stream.out()).as_slice());
-    let out = H160::from_slice(&out.as_bytes()[12..]);
-    out
+    H160::from_slice(&Keccak256::digest(&stream.out())[12..])
 }
 
-/// Get the create address from given scheme.
-pub fn create2_address(caller: H160, code_hash: H256, salt: U256) -> H160 {
-    let mut temp: [u8; 32] = [0; 32];
-    salt.to_big_endian(&mut temp);
-
+/// Address of contract created by `deployer` with `CREATE2`, see EIP-1014.
+pub fn create2_address(deployer: H160, salt: H256, init_code_hash: H256) -> H160 {
     let mut hasher = Keccak256::new();
-    hasher.update(&[0xff]);
-    hasher.update(&caller[..]);
-    hasher.update(&temp);
-    hasher.update(&code_hash[..]);
-    H160::from_slice(&hasher.finalize().as_slice()[12..])
+    hasher.update([0xff]);
+    hasher.update(deployer.as_bytes());
+    hasher.update(salt.as_bytes());
+    hasher.update(init_code_hash.as_bytes());
+    H160::from_slice(&hasher.finalize()[12..])
 }
 
 /// EVM context host.
@@ -761,6 +1016,11 @@ pub trait Host {
 
     fn step(&mut self, interp: &mut Interpreter, is_static: bool) -> Return;
     fn step_end(&mut self, interp: &mut Interpreter, is_static: bool, ret: Return) -> Return;
+    /// Count an instruction about to be executed, returns false if no more instructions are
+    /// allowed. Called only if [`Interpreter::limit_instructions`] is set.
+    fn record_instruction(&mut self) -> bool {
+        true
+    }
 
     fn env(&mut self) -> &mut Env;
 
@@ -772,12 +1032,30 @@ pub trait Host {
     fn balance(&mut self, address: H160) -> (U256, bool);
     /// Get code of address.
     fn code(&mut self, address: H160) -> (Bytecode, bool);
+    /// Get length of code of address, without loading the code if the database does not
+    /// need to.
+    fn code_size(&mut self, address: H160) -> (usize, bool) {
+        let (code, is_cold) = self.code(address);
+        (code.len(), is_cold)
+    }
     /// Get code hash of address.
     fn code_hash(&mut self, address: H160) -> (H256, bool);
     /// Get storage value of address at index.
     fn sload(&mut self, address: H160, index: U256) -> (U256, bool);
     /// Set storage value of address at index. Return if slot is cold/hot access.
     fn sstore(&mut self, address: H160, index: U256, value: U256) -> (U256, U256, U256, bool);
+    /// Value and gas of SLOAD as set by [`crate::StorageHook::sload`], standard by default.
+    fn hook_sload(&mut self, _address: H160, _index: U256, value: U256, cost: u64) -> (U256, u64) {
+        (value, cost)
+    }
+    /// Value written by SSTORE as set by [`crate::StorageHook::sstore`], standard by default.
+    fn hook_sstore(&mut self, _address: H160, _index: U256, value: U256) -> U256 {
+        value
+    }
+    /// Gas of SSTORE as set by [`crate::StorageHook::sstore_cost`], standard by default.
+    fn hook_sstore_cost(&mut self, _address: H160, _index: U256, cost: u64) -> u64 {
+        cost
+    }
     /// Create a log owned by address with given topics and data.
     fn log(&mut self, address: H160, topics: Vec<H256>, data: Bytes);
     /// Mark an address to be deleted, with funds transferred to target.
@@ -790,3 +1068,169 @@ pub trait Host {
     /// Invoke a call operation.
     fn call<SPEC: Spec>(&mut self, input: &mut CallInputs) -> (Return, Gas, Bytes);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h160(hex: &str) -> H160 {
+        hex.parse().unwrap()
+    }
+
+    fn h256(hex: &str) -> H256 {
+        hex.parse().unwrap()
+    }
+
+    #[test]
+    fn create_address_vectors() {
+        let deployer = h160("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+        assert_eq!(
+            create_address(deployer, 0),
+            h160("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d")
+        );
+        assert_eq!(
+            create_address(deployer, 1),
+            h160("343c43a37d37dff08ae8c4a11544c718abb4fcf8")
+        );
+        assert_eq!(
+            create_address(deployer, 2),
+            h160("f778b86fa74e846c4f0a1fbd1335fe81c00a0c91")
+        );
+    }
+
+    #[test]
+    fn create2_address_vectors() {
+        // examples from EIP-1014.
+        let deadbeef = [0xde, 0xad, 0xbe, 0xef];
+        let vectors: [(&str, &str, &[u8], &str); 7] = [
+            (
+                "0000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &[0x00],
+                "4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38",
+            ),
+            (
+                "deadbeef00000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &[0x00],
+                "b928f69bb1d91cd65274e3c79d8986362984fda3",
+            ),
+            (
+                "deadbeef00000000000000000000000000000000",
+                "000000000000000000000000feed000000000000000000000000000000000000",
+                &[0x00],
+                "d04116cdd17bebe565eb2422f2497e06cc1c9833",
+            ),
+            (
+                "0000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &deadbeef,
+                "70f2b2914a2a4b783faefb75f459a580616fcb5e",
+            ),
+            (
+                "00000000000000000000000000000000deadbeef",
+                "00000000000000000000000000000000000000000000000000000000cafebabe",
+                &deadbeef,
+                "60f3f640a8508fc6a86d45df051962668e1e8ac7",
+            ),
+            (
+                "00000000000000000000000000000000deadbeef",
+                "00000000000000000000000000000000000000000000000000000000cafebabe",
+                &deadbeef.repeat(11),
+                "1d8bfdc5d46dc4f61d6b6115972536ebe6a8854c",
+            ),
+            (
+                "0000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                &[],
+                "e33c0c7f7df4809055c3eba6c09cfe4baf1bd9e0",
+            ),
+        ];
+        for (deployer, salt, init_code, expected) in vectors {
+            let init_code_hash = H256::from_slice(&Keccak256::digest(init_code));
+            assert_eq!(
+                create2_address(h160(deployer), h256(salt), init_code_hash),
+                h160(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn run_yielding_resumes_call() {
+        use crate::{
+            opcode, AccountInfo, BytecodeBuilder, InMemoryDB, LatestSpec, NoOpInspector, SpecId,
+            Yield,
+        };
+
+        let contract = h160("0000000000000000000000000000000000000042");
+        let callee = h160("0000000000000000000000000000000000000043");
+        let mut db = InMemoryDB::default();
+        // returns 0x2a in a word
+        let callee_code = BytecodeBuilder::new()
+            .push(0x2a)
+            .push(0)
+            .op(opcode::MSTORE)
+            .push(0x20)
+            .push(0)
+            .op(opcode::RETURN)
+            .build();
+        db.insert_account_info(callee, AccountInfo::from_code(callee_code));
+        let mut env = Env::default();
+        let mut inspector = NoOpInspector();
+        let mut evm = EVMImpl::<LatestSpec, _, false>::new(
+            &mut db,
+            &mut env,
+            &mut inspector,
+            Precompiles::new::<{ SpecId::to_precompile_id(SpecId::LATEST) }>(),
+        );
+
+        // CALL callee with output to memory word 0, then return call result and output.
+        let code = BytecodeBuilder::new()
+            .push(0x20)
+            .push(0)
+            .push(0)
+            .push(0)
+            .push(0)
+            .push(U256::from_big_endian(callee.as_bytes()))
+            .push(100_000)
+            .op(opcode::CALL)
+            .push(0x20)
+            .op(opcode::MSTORE)
+            .push(0x40)
+            .push(0)
+            .op(opcode::RETURN)
+            .build();
+        let contract =
+            Contract::new::<LatestSpec>(Bytes::new(), code, contract, H160::zero(), U256::zero());
+        #[cfg(feature = "memory_limit")]
+        let mut interp =
+            Interpreter::new_with_memory_limit::<LatestSpec>(contract, 1_000_000, u64::MAX);
+        #[cfg(not(feature = "memory_limit"))]
+        let mut interp = Interpreter::new::<LatestSpec>(contract, 1_000_000);
+
+        let mut inputs = match interp.run_yielding::<_, LatestSpec>(&mut evm) {
+            Yield::CallRequest { inputs, is_static } => {
+                assert!(!is_static);
+                inputs
+            }
+            Yield::Done(result) => panic!("expected call request, got {:?}", result),
+        };
+        assert_eq!(inputs.contract, callee);
+        assert_eq!(inputs.gas_limit, 100_000);
+        let gas_before_call = interp.gas().remaining();
+
+        let (ret, gas, out) = evm.call::<LatestSpec>(&mut inputs);
+        assert_eq!(ret, Return::Return);
+        let result = match interp.resume_call::<_, LatestSpec>(&mut evm, ret, gas, out) {
+            Yield::Done(result) => result,
+            Yield::CallRequest { .. } => panic!("expected execution to finish"),
+        };
+        assert_eq!(result.result, Return::Return);
+        // unused gas of the call is returned.
+        assert!(result.gas.remaining() > gas_before_call);
+        let mut expected = [0u8; 64];
+        expected[31] = 0x2a;
+        expected[63] = 1;
+        assert_eq!(result.output.as_ref(), &expected);
+    }
+}
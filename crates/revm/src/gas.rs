@@ -4,14 +4,21 @@ mod constants;
 pub use calc::*;
 pub use constants::*;
 
-use crate::{instructions::Return, return_ok, return_revert};
+use crate::{instructions::Return, return_ok, return_revert, Spec, SpecId::LONDON};
 
+/// Gas accounting of a call frame. Memory expansion is charged separately from other costs
+/// as its cost is for the total memory size, not for every expansion.
 #[derive(Clone, Copy, Debug)]
 pub struct Gas {
+    /// Gas given to the frame.
     limit: u64,
+    /// Gas spent on everything but memory.
     used: u64,
+    /// Cost of the current memory size.
     memory: u64,
+    /// Refund counter, can go negative within a call as refunds are taken back.
     refunded: i64,
+    /// `used + memory`, kept so that `remaining` is a single subtraction.
     all_used_gas: u64,
 }
 impl Gas {
@@ -42,6 +49,11 @@ impl Gas {
         self.limit
     }
 
+    /// Gas spent on everything but memory expansion.
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
     pub fn memory(&self) -> u64 {
         self.memory
     }
@@ -50,10 +62,12 @@ impl Gas {
         self.refunded
     }
 
+    /// All gas spent, including memory expansion.
     pub fn spend(&self) -> u64 {
         self.all_used_gas
     }
 
+    /// Gas that can still be spent, `limit - spend`.
     pub fn remaining(&self) -> u64 {
         self.limit - self.all_used_gas
     }
@@ -67,7 +81,23 @@ impl Gas {
         self.refunded += refund;
     }
 
-    /// Record an explict cost.
+    pub fn subtract_refund(&mut self, refund: i64) {
+        self.refunded -= refund;
+    }
+
+    /// Refund that is given back at the end of transaction. Refund counter is capped to
+    /// `spend / 2`, or `spend / 5` since London (EIP-3529: Reduction in refunds).
+    /// A negative counter results in no refund.
+    pub fn final_refund<SPEC: Spec>(&self) -> u64 {
+        let max_refund_quotient = if SPEC::enabled(LONDON) { 5 } else { 2 };
+        core::cmp::min(
+            self.refunded.max(0) as u64,
+            self.spend() / max_refund_quotient,
+        )
+    }
+
+    /// Record an explict cost. Returns false without recording anything if it is over the
+    /// remaining gas, also when adding it overflows.
     #[inline(always)]
     pub fn record_cost(&mut self, cost: u64) -> bool {
         let (all_used_gas, overflow) = self.all_used_gas.overflowing_add(cost);
@@ -80,7 +110,9 @@ impl Gas {
         true
     }
 
-    /// used in memory_resize! macro
+    /// Set cost of memory to `gas_memory` if it is higher than the current one, memory never
+    /// shrinks. Returns false if total spent gas would be over the limit. Used in
+    /// memory_resize! macro.
     pub fn record_memory(&mut self, gas_memory: u64) -> bool {
         if gas_memory > self.memory {
             let (all_used_gas, overflow) = self.used.overflowing_add(gas_memory);
@@ -95,6 +127,87 @@ impl Gas {
 
     /// used in gas_refund! macro
     pub fn gas_refund(&mut self, refund: i64) {
-        self.refunded += refund;
+        self.record_refund(refund);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BerlinSpec, LondonSpec};
+
+    #[test]
+    fn final_refund_cap() {
+        let mut gas = Gas::new(100_000);
+        assert!(gas.record_cost(50_000));
+        gas.record_refund(30_000);
+        assert_eq!(gas.final_refund::<BerlinSpec>(), 25_000);
+        assert_eq!(gas.final_refund::<LondonSpec>(), 10_000);
+
+        gas.subtract_refund(24_000);
+        assert_eq!(gas.refunded(), 6_000);
+        assert_eq!(gas.final_refund::<BerlinSpec>(), 6_000);
+        assert_eq!(gas.final_refund::<LondonSpec>(), 6_000);
+
+        gas.subtract_refund(10_000);
+        assert_eq!(gas.final_refund::<LondonSpec>(), 0);
+    }
+
+    #[test]
+    fn record_cost_overflow() {
+        let mut gas = Gas::new(u64::MAX);
+        assert!(gas.record_cost(u64::MAX - 10));
+        assert!(!gas.record_cost(11));
+        assert!(!gas.record_cost(u64::MAX));
+        assert_eq!(gas.spend(), u64::MAX - 10);
+        assert!(gas.record_cost(10));
+        assert_eq!(gas.remaining(), 0);
+        assert!(gas.record_cost(0));
+        assert!(!gas.record_cost(1));
+
+        let mut gas = Gas::new(100);
+        assert!(!gas.record_cost(101));
+        assert_eq!(gas.spend(), 0);
+        assert!(gas.record_cost(100));
+        assert_eq!(gas.remaining(), 0);
+    }
+
+    #[test]
+    fn memory_gas() {
+        let mut gas = Gas::new(1_000);
+        assert!(gas.record_cost(100));
+        assert!(gas.record_memory(300));
+        assert_eq!(gas.spend(), 400);
+        // memory cost is for the total size, lower cost does not change anything.
+        assert!(gas.record_memory(200));
+        assert_eq!(gas.memory(), 300);
+        assert!(gas.record_memory(900));
+        assert_eq!((gas.used(), gas.memory(), gas.remaining()), (100, 900, 0));
+        assert!(!gas.record_memory(901));
+        assert!(!gas.record_memory(u64::MAX));
+        assert_eq!(gas.spend(), 1_000);
+
+        // returned gas of a subcall does not touch memory.
+        gas.erase_cost(50);
+        assert_eq!((gas.used(), gas.memory(), gas.remaining()), (50, 900, 50));
+    }
+
+    #[test]
+    fn fake_exponential_vectors() {
+        // Test vectors from EIP-4844 consensus spec tests.
+        for (factor, numerator, denominator, expected) in [
+            (1, 0, 1, 1),
+            (38493, 0, 1000, 38493),
+            (0, 1234, 2345, 0),
+            (1, 2, 1, 6),
+            (1, 4, 2, 6),
+            (1, 3, 1, 16),
+            (1, 6, 2, 18),
+            (1, 8, 2, 50),
+            (1, 50000000, 2225652, 5709098764),
+        ] {
+            assert_eq!(fake_exponential(factor, numerator, denominator), expected);
+        }
+        assert_eq!(calc_blob_gasprice(0), 1);
     }
 }
@@ -1,6 +1,7 @@
 use super::constants::*;
-use crate::{models::SelfDestructResult, Spec, SpecId::*};
-use primitive_types::U256;
+use crate::{models::SelfDestructResult, Spec, SpecId, SpecId::*};
+use alloc::vec::Vec;
+use primitive_types::{H160, U256};
 
 #[allow(clippy::collapsible_else_if)]
 pub fn sstore_refund<SPEC: Spec>(original: U256, current: U256, new: U256) -> i64 {
@@ -63,6 +64,11 @@ pub fn create2_cost(len: usize) -> Option<u64> {
     Some(gas)
 }
 
+/// EIP-3860: Limit and meter initcode
+pub fn initcode_cost(len: usize) -> u64 {
+    INITCODE_WORD_COST * (len as u64).div_ceil(32)
+}
+
 fn log2floor(value: U256) -> u64 {
     assert!(!value.is_zero());
     let mut l: u64 = 256;
@@ -354,9 +360,89 @@ fn new_cost<SPEC: Spec>(is_call_or_staticcall: bool, is_new: bool, transfers_val
     }
 }
 
+/// Gas charged before execution starts: base cost of the transaction, its calldata, access
+/// list (since Berlin) and EIP-7702 authorizations (since Prague).
+pub fn initial_tx_gas<SPEC: Spec>(
+    input: &[u8],
+    is_create: bool,
+    access_list: &[(H160, Vec<U256>)],
+    authorizations: usize,
+) -> u64 {
+    initial_tx_gas_for(SPEC::SPEC_ID, input, is_create, access_list, authorizations)
+}
+
+/// [`initial_tx_gas`] for spec selected at runtime.
+pub fn initial_tx_gas_for(
+    spec_id: SpecId,
+    input: &[u8],
+    is_create: bool,
+    access_list: &[(H160, Vec<U256>)],
+    authorizations: usize,
+) -> u64 {
+    let enabled = |other| SpecId::enabled(spec_id, other);
+    let zero_data_len = input.iter().filter(|v| **v == 0).count() as u64;
+    let non_zero_data_len = input.len() as u64 - zero_data_len;
+
+    // EIP-2: Homestead Hard-fork Changes
+    let mut gas = if is_create && enabled(HOMESTEAD) {
+        53000
+    } else {
+        21000
+    };
+
+    // EIP-2028: Transaction data gas cost reduction
+    let non_zero_data_cost = if enabled(ISTANBUL) { 16 } else { 68 };
+    gas += zero_data_len * TRANSACTION_ZERO_DATA + non_zero_data_len * non_zero_data_cost;
+
+    // EIP-2930: Optional access lists
+    if enabled(BERLIN) {
+        let accessed_slots: usize = access_list.iter().map(|(_, slots)| slots.len()).sum();
+        gas += access_list.len() as u64 * ACCESS_LIST_ADDRESS
+            + accessed_slots as u64 * ACCESS_LIST_STORAGE_KEY;
+    }
+
+    // EIP-3860: Limit and meter initcode
+    if is_create && enabled(SHANGHAI) {
+        gas += initcode_cost(input.len());
+    }
+
+    // EIP-7702: Set EOA account code
+    if enabled(PRAGUE) {
+        gas += authorizations as u64 * PER_EMPTY_ACCOUNT_COST;
+    }
+    gas
+}
+
 pub fn memory_gas(a: usize) -> u64 {
     let a = a as u64;
     MEMORY
         .saturating_mul(a)
         .saturating_add(a.saturating_mul(a) / 512)
 }
+
+/// Blob gas price for the given excess blob gas. EIP-4844: Shard Blob Transactions.
+pub fn calc_blob_gasprice(excess_blob_gas: u64) -> u128 {
+    fake_exponential(
+        MIN_BLOB_GASPRICE,
+        excess_blob_gas,
+        BLOB_GASPRICE_UPDATE_FRACTION,
+    )
+}
+
+/// Approximates `factor * e ** (numerator / denominator)` using Taylor expansion, as
+/// defined in EIP-4844.
+pub fn fake_exponential(factor: u64, numerator: u64, denominator: u64) -> u128 {
+    let factor = factor as u128;
+    let numerator = numerator as u128;
+    let denominator = denominator as u128;
+
+    let mut i = 1;
+    let mut output = 0;
+    let mut numerator_accum = factor * denominator;
+    while numerator_accum > 0 {
+        output += numerator_accum;
+        numerator_accum = (numerator_accum * numerator) / (denominator * i);
+        i += 1;
+    }
+    output / denominator
+}
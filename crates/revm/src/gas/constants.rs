@@ -36,3 +36,14 @@ pub const STORAGE_READ_WARM: u64 = 100;
 pub const SLOAD_COLD: u64 = 2100;
 
 pub const CALL_STIPEND: u64 = 2300;
+
+// shanghai eip3860 constants
+pub const INITCODE_WORD_COST: u64 = 2;
+
+// prague eip7702 constants
+pub const PER_EMPTY_ACCOUNT_COST: u64 = 25000;
+pub const PER_AUTH_BASE_COST: u64 = 12500;
+
+// cancun eip4844 constants
+pub const MIN_BLOB_GASPRICE: u64 = 1;
+pub const BLOB_GASPRICE_UPDATE_FRACTION: u64 = 3338477;
@@ -22,7 +22,8 @@ pub trait Inspector<DB: Database> {
     /// Called on each step of the interpreter.
     ///
     /// Information about the current execution, including the memory, stack and more is available
-    /// on `interp` (see [Interpreter]).
+    /// on `interp` (see [Interpreter::stack], [Interpreter::memory] and
+    /// [Interpreter::program_counter]).
     ///
     /// # Example
     ///
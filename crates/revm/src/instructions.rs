@@ -3,7 +3,7 @@ mod macros;
 mod arithmetic;
 mod bitwise;
 mod control;
-mod host;
+pub(crate) mod host;
 mod host_env;
 mod i256;
 mod memory;
@@ -27,7 +27,7 @@ macro_rules! return_ok {
 #[macro_export]
 macro_rules! return_revert {
     () => {
-        Return::Revert | Return::CallTooDeep | Return::OutOfFund
+        Return::Revert | Return::CallTooDeep | Return::OutOfFund | Return::NonceOverflow
     };
 }
 
@@ -46,6 +46,10 @@ pub enum Return {
     CallTooDeep = 0x21,
     OutOfFund = 0x22,
 
+    /// Interpreter stopped at a CALL that the host has to execute, see
+    /// [`crate::Interpreter::run_yielding`].
+    CallYield = 0x40,
+
     // error codes
     OutOfGas = 0x50,
     OpcodeNotFound,
@@ -72,6 +76,107 @@ pub enum Return {
     CreateContractLimit,
     /// Error on created contract that begins with EF
     CreateContractWithEF,
+    /// Init code is over [`crate::CfgEnv::max_initcode_size`] (EIP-3860).
+    CreateInitcodeSizeLimit,
+    /// Memory expansion past [`crate::CfgEnv::memory_limit`].
+    MemoryLimitOOG,
+    /// Gas budget of the session ran out, see [`crate::EVM::set_session_gas_limit`].
+    SessionGasExhausted,
+    /// Opcode is disabled by [`crate::CfgEnv::disabled_opcodes`].
+    OpcodeDisabled,
+    /// Transaction executed [`crate::CfgEnv::instruction_limit`] instructions.
+    InstructionLimitReached,
+    /// Call returned more than [`crate::CfgEnv::max_returndata_size`] bytes.
+    ReturnDataLimitReached,
+    /// EIP-7702 authorization list in a create transaction or before Prague.
+    InvalidAuthorizationList,
+}
+
+/// Exceptional halt of execution. Every halt maps to one [`Return`] code, see [`Return::halt`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Halt {
+    OutOfGas,
+    MemoryLimitOOG,
+    OpcodeNotFound,
+    InvalidOpcode,
+    InvalidJump,
+    InvalidMemoryRange,
+    NotActivated,
+    StackUnderflow,
+    StackOverflow,
+    OutOfOffset,
+    /// State modifying opcode was executed inside of static call.
+    StateChangeDuringStaticCall,
+    CreateCollision,
+    PrecompileError,
+    NonceOverflow,
+    CreateContractLimit,
+    CreateContractWithEF,
+    CreateInitcodeSizeLimit,
+    SessionGasExhausted,
+    OpcodeDisabled,
+    InstructionLimitReached,
+    ReturnDataLimitReached,
+}
+
+impl Return {
+    /// Returns the halt reason if execution stopped exceptionally. Success and revert codes,
+    /// as well as transaction validation errors, return `None`.
+    pub fn halt(self) -> Option<Halt> {
+        Some(match self {
+            Return::OutOfGas => Halt::OutOfGas,
+            Return::MemoryLimitOOG => Halt::MemoryLimitOOG,
+            Return::OpcodeNotFound => Halt::OpcodeNotFound,
+            Return::InvalidOpcode => Halt::InvalidOpcode,
+            Return::InvalidJump => Halt::InvalidJump,
+            Return::InvalidMemoryRange => Halt::InvalidMemoryRange,
+            Return::NotActivated => Halt::NotActivated,
+            Return::StackUnderflow => Halt::StackUnderflow,
+            Return::StackOverflow => Halt::StackOverflow,
+            Return::OutOfOffset => Halt::OutOfOffset,
+            Return::CallNotAllowedInsideStatic => Halt::StateChangeDuringStaticCall,
+            Return::CreateCollision => Halt::CreateCollision,
+            Return::PrecompileError => Halt::PrecompileError,
+            Return::NonceOverflow => Halt::NonceOverflow,
+            Return::CreateContractLimit => Halt::CreateContractLimit,
+            Return::CreateContractWithEF => Halt::CreateContractWithEF,
+            Return::CreateInitcodeSizeLimit => Halt::CreateInitcodeSizeLimit,
+            Return::SessionGasExhausted => Halt::SessionGasExhausted,
+            Return::OpcodeDisabled => Halt::OpcodeDisabled,
+            Return::InstructionLimitReached => Halt::InstructionLimitReached,
+            Return::ReturnDataLimitReached => Halt::ReturnDataLimitReached,
+            _ => return None,
+        })
+    }
+}
+
+impl From<Halt> for Return {
+    fn from(halt: Halt) -> Self {
+        match halt {
+            Halt::OutOfGas => Return::OutOfGas,
+            Halt::MemoryLimitOOG => Return::MemoryLimitOOG,
+            Halt::OpcodeNotFound => Return::OpcodeNotFound,
+            Halt::InvalidOpcode => Return::InvalidOpcode,
+            Halt::InvalidJump => Return::InvalidJump,
+            Halt::InvalidMemoryRange => Return::InvalidMemoryRange,
+            Halt::NotActivated => Return::NotActivated,
+            Halt::StackUnderflow => Return::StackUnderflow,
+            Halt::StackOverflow => Return::StackOverflow,
+            Halt::OutOfOffset => Return::OutOfOffset,
+            Halt::StateChangeDuringStaticCall => Return::CallNotAllowedInsideStatic,
+            Halt::CreateCollision => Return::CreateCollision,
+            Halt::PrecompileError => Return::PrecompileError,
+            Halt::NonceOverflow => Return::NonceOverflow,
+            Halt::CreateContractLimit => Return::CreateContractLimit,
+            Halt::CreateContractWithEF => Return::CreateContractWithEF,
+            Halt::CreateInitcodeSizeLimit => Return::CreateInitcodeSizeLimit,
+            Halt::SessionGasExhausted => Return::SessionGasExhausted,
+            Halt::OpcodeDisabled => Return::OpcodeDisabled,
+            Halt::InstructionLimitReached => Return::InstructionLimitReached,
+            Halt::ReturnDataLimitReached => Return::ReturnDataLimitReached,
+        }
+    }
 }
 
 #[inline(always)]
@@ -142,6 +247,7 @@ pub fn eval<H: Host, S: Spec>(opcode: u8, interp: &mut Interpreter, host: &mut H
         opcode::PC => control::pc(interp),
         opcode::MSIZE => memory::msize(interp),
         opcode::JUMPDEST => control::jumpdest(interp),
+        opcode::PUSH0 => stack::push0::<S>(interp),
         opcode::PUSH1 => stack::push::<1>(interp),
         opcode::PUSH2 => stack::push::<2>(interp),
         opcode::PUSH3 => stack::push::<3>(interp),
@@ -212,6 +318,8 @@ pub fn eval<H: Host, S: Spec>(opcode: u8, interp: &mut Interpreter, host: &mut H
         opcode::REVERT => control::revert::<S>(interp),
         opcode::INVALID => Return::InvalidOpcode,
         opcode::BASEFEE => host_env::basefee::<H, S>(interp, host),
+        opcode::BLOBHASH => host_env::blob_hash::<H, S>(interp, host),
+        opcode::BLOBBASEFEE => host_env::blob_basefee::<H, S>(interp, host),
         opcode::ORIGIN => host_env::origin(interp, host),
         opcode::CALLER => system::caller(interp),
         opcode::CALLVALUE => system::callvalue(interp),
@@ -225,7 +333,7 @@ pub fn eval<H: Host, S: Spec>(opcode: u8, interp: &mut Interpreter, host: &mut H
         opcode::COINBASE => host_env::coinbase(interp, host),
         opcode::TIMESTAMP => host_env::timestamp(interp, host),
         opcode::NUMBER => host_env::number(interp, host),
-        opcode::DIFFICULTY => host_env::difficulty(interp, host),
+        opcode::DIFFICULTY => host_env::difficulty::<H, S>(interp, host),
         opcode::GASLIMIT => host_env::gaslimit(interp, host),
         opcode::SLOAD => host::sload::<H, S>(interp, host),
         opcode::SSTORE => host::sstore::<H, S>(interp, host),
@@ -246,3 +354,42 @@ pub fn eval<H: Host, S: Spec>(opcode: u8, interp: &mut Interpreter, host: &mut H
         _ => Return::OpcodeNotFound,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halt_roundtrip() {
+        let halts = [
+            Halt::OutOfGas,
+            Halt::MemoryLimitOOG,
+            Halt::OpcodeNotFound,
+            Halt::InvalidOpcode,
+            Halt::InvalidJump,
+            Halt::InvalidMemoryRange,
+            Halt::NotActivated,
+            Halt::StackUnderflow,
+            Halt::StackOverflow,
+            Halt::OutOfOffset,
+            Halt::StateChangeDuringStaticCall,
+            Halt::CreateCollision,
+            Halt::PrecompileError,
+            Halt::NonceOverflow,
+            Halt::CreateContractLimit,
+            Halt::CreateContractWithEF,
+            Halt::CreateInitcodeSizeLimit,
+            Halt::SessionGasExhausted,
+            Halt::OpcodeDisabled,
+            Halt::InstructionLimitReached,
+            Halt::ReturnDataLimitReached,
+        ];
+        for halt in halts {
+            assert_eq!(Return::from(halt).halt(), Some(halt));
+        }
+
+        assert_eq!(Return::Stop.halt(), None);
+        assert_eq!(Return::Revert.halt(), None);
+        assert_eq!(Return::LackOfFundForGasLimit.halt(), None);
+    }
+}
@@ -1,6 +1,11 @@
 use crate::{
-    alloc::vec::Vec, gas, interpreter::Interpreter, return_ok, return_revert, CallContext,
-    CallInputs, CallScheme, CreateInputs, CreateScheme, Host, Return, Spec, SpecId::*, Transfer,
+    alloc::vec::Vec,
+    gas,
+    interpreter::{Interpreter, PendingCall},
+    return_ok, return_revert, CallContext, CallInputs, CallScheme, CreateInputs, CreateScheme, Gas,
+    Host, Return, Spec,
+    SpecId::*,
+    Transfer,
 };
 use bytes::Bytes;
 use core::cmp::min;
@@ -39,10 +44,10 @@ pub fn selfbalance<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H)
 pub fn extcodesize<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> Return {
     pop_address!(interp, address);
 
-    let (code, is_cold) = host.code(address);
+    let (size, is_cold) = host.code_size(address);
     gas!(interp, gas::account_access_gas::<SPEC>(is_cold));
 
-    push!(interp, U256::from(code.len()));
+    push!(interp, U256::from(size));
 
     Return::Continue
 }
@@ -104,27 +109,32 @@ pub fn blockhash<H: Host>(interp: &mut Interpreter, host: &mut H) -> Return {
 
 pub fn sload<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> Return {
     pop!(interp, index);
-    let (value, is_cold) = host.sload(interp.contract.address, index);
-    gas!(interp, gas::sload_cost::<SPEC>(is_cold));
+    let address = interp.contract.address;
+    let (value, is_cold) = host.sload(address, index);
+    let (value, cost) = host.hook_sload(address, index, value, gas::sload_cost::<SPEC>(is_cold));
+    gas!(interp, cost);
     push!(interp, value);
     Return::Continue
 }
 
 pub fn sstore<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> Return {
-    check!(!SPEC::IS_STATIC_CALL);
+    check_not_static!(SPEC);
 
     pop!(interp, index, value);
-    let (original, old, new, is_cold) = host.sstore(interp.contract.address, index, value);
+    let address = interp.contract.address;
+    let value = host.hook_sstore(address, index, value);
+    let (original, old, new, is_cold) = host.sstore(address, index, value);
     gas_or_fail!(interp, {
         let remaining_gas = interp.gas.remaining();
         gas::sstore_cost::<SPEC>(original, old, new, remaining_gas, is_cold)
+            .map(|cost| host.hook_sstore_cost(address, index, cost))
     });
     refund!(interp, gas::sstore_refund::<SPEC>(original, old, new));
     interp.add_next_gas_block(interp.program_counter() - 1)
 }
 
 pub fn log<H: Host, SPEC: Spec>(interp: &mut Interpreter, n: u8, host: &mut H) -> Return {
-    check!(!SPEC::IS_STATIC_CALL);
+    check_not_static!(SPEC);
 
     pop!(interp, offset, len);
     gas_or_fail!(interp, gas::log_cost(n, len));
@@ -154,7 +164,7 @@ pub fn log<H: Host, SPEC: Spec>(interp: &mut Interpreter, n: u8, host: &mut H) -
 }
 
 pub fn selfdestruct<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> Return {
-    check!(!SPEC::IS_STATIC_CALL);
+    check_not_static!(SPEC);
     pop_address!(interp, target);
 
     let res = host.selfdestruct(interp.contract.address, target);
@@ -183,7 +193,7 @@ pub fn create<H: Host, SPEC: Spec>(
     is_create2: bool,
     host: &mut H,
 ) -> Return {
-    check!(!SPEC::IS_STATIC_CALL);
+    check_not_static!(SPEC);
     if is_create2 {
         check!(SPEC::enabled(CONSTANTINOPLE)); // EIP-1014: Skinny CREATE2
     }
@@ -201,6 +211,14 @@ pub fn create<H: Host, SPEC: Spec>(
         Bytes::copy_from_slice(interp.memory.get_slice(code_offset, len))
     };
 
+    // EIP-3860: Limit and meter initcode
+    if SPEC::enabled(SHANGHAI) {
+        if len > host.env().cfg.max_initcode_size() {
+            return Return::CreateInitcodeSizeLimit;
+        }
+        gas!(interp, gas::initcode_cost(len));
+    }
+
     let scheme = if is_create2 {
         pop!(interp, salt);
         gas_or_fail!(interp, gas::create2_cost(len));
@@ -265,8 +283,8 @@ pub fn call<H: Host, SPEC: Spec>(
         }
         CallScheme::Call => {
             pop!(interp, value);
-            if SPEC::IS_STATIC_CALL && !value.is_zero() {
-                return Return::CallNotAllowedInsideStatic;
+            if !value.is_zero() {
+                check_not_static!(SPEC);
             }
             value
         }
@@ -342,6 +360,21 @@ pub fn call<H: Host, SPEC: Spec>(
     let (is_cold, exist) = host.load_account(to);
     let is_new = !exist;
 
+    // EIP-7702: access of the account that execution is delegated to is paid too.
+    if SPEC::enabled(PRAGUE) {
+        if let Some(delegate) = host.code(to).0.delegation_address() {
+            let (delegate_is_cold, _) = host.load_account(delegate);
+            gas!(
+                interp,
+                if delegate_is_cold {
+                    gas::ACCOUNT_ACCESS_COLD
+                } else {
+                    gas::STORAGE_READ_WARM
+                }
+            );
+        }
+    }
+
     gas!(
         interp,
         gas::call_cost::<SPEC>(
@@ -372,12 +405,33 @@ pub fn call<H: Host, SPEC: Spec>(
         gas_limit,
         context,
     };
+    if interp.yield_calls {
+        interp.pending_call = Some(PendingCall {
+            inputs: call_input,
+            is_static: is_static || SPEC::IS_STATIC_CALL,
+            out_offset,
+            out_len,
+        });
+        return Return::CallYield;
+    }
     // CALL CONTRACT, with static or ordinary spec.
     let (reason, gas, return_data) = if is_static {
         host.call::<SPEC::STATIC>(&mut call_input)
     } else {
         host.call::<SPEC>(&mut call_input)
     };
+    call_return(interp, out_offset, out_len, reason, gas, return_data)
+}
+
+/// Handle result of a call made by CALL-like opcode, output is copied to memory at `out_offset`.
+pub(crate) fn call_return(
+    interp: &mut Interpreter,
+    out_offset: usize,
+    out_len: usize,
+    reason: Return,
+    gas: Gas,
+    return_data: Bytes,
+) -> Return {
     interp.return_data_buffer = return_data;
 
     let target_len = min(out_len, interp.return_data_buffer.len());
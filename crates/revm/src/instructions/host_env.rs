@@ -1,5 +1,6 @@
 use crate::{interpreter::Interpreter, Host, Return, Spec, SpecId::*};
-use primitive_types::H256;
+use core::convert::TryFrom;
+use primitive_types::{H256, U256};
 
 pub fn chainid<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> Return {
     // gas!(interp, gas::BASE);
@@ -27,8 +28,15 @@ pub fn number<H: Host>(interp: &mut Interpreter, host: &mut H) -> Return {
     Return::Continue
 }
 
-pub fn difficulty<H: Host>(interp: &mut Interpreter, host: &mut H) -> Return {
+pub fn difficulty<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> Return {
     // gas!(interp, gas::BASE);
+    // EIP-4399: Supplant DIFFICULTY opcode with PREVRANDAO
+    if SPEC::enabled(MERGE) {
+        if let Some(prevrandao) = host.env().block.prevrandao {
+            push_h256!(interp, prevrandao);
+            return Return::Continue;
+        }
+    }
     push!(interp, host.env().block.difficulty);
     Return::Continue
 }
@@ -53,6 +61,30 @@ pub fn basefee<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> R
     Return::Continue
 }
 
+pub fn blob_hash<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> Return {
+    // gas!(interp, gas::VERYLOW);
+    // EIP-4844: Shard Blob Transactions
+    check!(SPEC::enabled(CANCUN));
+    pop_top!(interp, index);
+    let blob_hashes = &host.env().tx.blob_hashes;
+    *index = match usize::try_from(*index)
+        .ok()
+        .and_then(|i| blob_hashes.get(i))
+    {
+        Some(hash) => U256::from_big_endian(hash.as_bytes()),
+        None => U256::zero(),
+    };
+    Return::Continue
+}
+
+pub fn blob_basefee<H: Host, SPEC: Spec>(interp: &mut Interpreter, host: &mut H) -> Return {
+    // gas!(interp, gas::BASE);
+    // EIP-7516: BLOBBASEFEE opcode
+    check!(SPEC::enabled(CANCUN));
+    push!(interp, U256::from(host.env().block.get_blob_gasprice()));
+    Return::Continue
+}
+
 pub fn origin<H: Host>(interp: &mut Interpreter, host: &mut H) -> Return {
     // gas!(interp, gas::BASE);
     let ret = H256::from(host.env().tx.caller);
@@ -17,6 +17,16 @@ macro_rules! check {
     };
 }
 
+/// Halt with [`Return::CallNotAllowedInsideStatic`] if `$spec` is static. Staticness is
+/// inherited by nested calls through [`crate::Spec::STATIC`].
+macro_rules! check_not_static {
+    ($spec:ident) => {
+        if $spec::IS_STATIC_CALL {
+            return Return::CallNotAllowedInsideStatic;
+        }
+    };
+}
+
 macro_rules! gas {
     ($interp:expr, $gas:expr) => {
         if crate::USE_GAS {
@@ -55,7 +65,7 @@ macro_rules! memory_resize {
         {
             #[cfg(feature = "memory_limit")]
             if new_size > ($interp.memory_limit as usize) {
-                return Return::OutOfGas;
+                return Return::MemoryLimitOOG;
             }
 
             if new_size > $interp.memory.len() {
@@ -47,6 +47,11 @@ pub const JUMPI: u8 = 0x57;
 pub const PC: u8 = 0x58;
 pub const MSIZE: u8 = 0x59;
 pub const JUMPDEST: u8 = 0x5b;
+/// EIP-2315 subroutine opcodes, not enabled in any spec. See [`crate::Spec::EIP2315`].
+pub const BEGINSUB: u8 = 0x5c;
+pub const RETURNSUB: u8 = 0x5d;
+pub const JUMPSUB: u8 = 0x5e;
+pub const PUSH0: u8 = 0x5f;
 pub const PUSH1: u8 = 0x60;
 pub const PUSH2: u8 = 0x61;
 pub const PUSH3: u8 = 0x62;
@@ -117,6 +122,8 @@ pub const INVALID: u8 = 0xfe;
 pub const ADDRESS: u8 = 0x30;
 pub const BALANCE: u8 = 0x31;
 pub const BASEFEE: u8 = 0x48;
+pub const BLOBHASH: u8 = 0x49;
+pub const BLOBBASEFEE: u8 = 0x4a;
 pub const ORIGIN: u8 = 0x32;
 pub const CALLER: u8 = 0x33;
 pub const CALLVALUE: u8 = 0x34;
@@ -174,7 +181,12 @@ const GAS_BLOCK_END_MASK: u32 = 0x40000000;
 const IS_PUSH_MASK: u32 = 0x20000000;
 const GAS_MASK: u32 = 0x1FFFFFFF;
 
-#[derive(Debug)]
+/// Static information about an opcode for a given spec, as used by bytecode analysis.
+///
+/// The meaning of the accessors is stable: `is_jump`, `is_gas_block_end`, `is_push` and
+/// `get_gas` always describe the opcode as revm executes it. The packed representation is
+/// an implementation detail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct OpInfo {
     /// Data contains few information packed inside u32:
     /// IS_JUMP (1bit) | IS_GAS_BLOCK_END (1bit) | IS_PUSH (1bit) | gas (29bits)
@@ -182,19 +194,25 @@ pub struct OpInfo {
 }
 
 impl OpInfo {
+    /// True for JUMPDEST.
     #[inline(always)]
     pub fn is_jump(&self) -> bool {
         self.data & JUMP_MASK == JUMP_MASK
     }
+    /// True if opcode ends a block of statically priced opcodes: it halts, jumps, reads
+    /// remaining gas or needs exact gas accounting (calls, creates and SSTORE).
     #[inline(always)]
     pub fn is_gas_block_end(&self) -> bool {
         self.data & GAS_BLOCK_END_MASK == GAS_BLOCK_END_MASK
     }
+    /// True for PUSH1..PUSH32.
     #[inline(always)]
     pub fn is_push(&self) -> bool {
         self.data & IS_PUSH_MASK == IS_PUSH_MASK
     }
 
+    /// Static gas cost. Zero for opcodes with only dynamic gas and for opcodes that are
+    /// unknown or not yet enabled in the spec.
     #[inline(always)]
     pub fn get_gas(&self) -> u32 {
         self.data & GAS_MASK
@@ -204,6 +222,14 @@ impl OpInfo {
         Self { data: 0 }
     }
 
+    /// Same opcode info with static gas cost replaced by `gas`. Used to build custom gas
+    /// tables, see [`crate::Bytecode::to_analysed_with_table`].
+    pub const fn with_gas(self, gas: u64) -> Self {
+        Self {
+            data: (self.data & !GAS_MASK) | (gas as u32 & GAS_MASK),
+        }
+    }
+
     pub const fn gas_block_end(gas: u64) -> Self {
         Self {
             data: gas as u32 | GAS_BLOCK_END_MASK,
@@ -340,8 +366,18 @@ macro_rules! gas_opcodee {
             } else {
                 0
             }),
-            /* 0x49 */ OpInfo::none(),
-            /* 0x4a */ OpInfo::none(),
+            /* 0x49  BLOBHASH */
+            OpInfo::gas(if SpecId::enabled($spec_id, SpecId::CANCUN) {
+                gas::VERYLOW
+            } else {
+                0
+            }),
+            /* 0x4a  BLOBBASEFEE */
+            OpInfo::gas(if SpecId::enabled($spec_id, SpecId::CANCUN) {
+                gas::BASE
+            } else {
+                0
+            }),
             /* 0x4b */ OpInfo::none(),
             /* 0x4c */ OpInfo::none(),
             /* 0x4d */ OpInfo::none(),
@@ -364,7 +400,12 @@ macro_rules! gas_opcodee {
             /* 0x5c */ OpInfo::none(),
             /* 0x5d */ OpInfo::none(),
             /* 0x5e */ OpInfo::none(),
-            /* 0x5f */ OpInfo::none(),
+            /* 0x5f  PUSH0 */
+            OpInfo::gas(if SpecId::enabled($spec_id, SpecId::SHANGHAI) {
+                gas::BASE
+            } else {
+                0
+            }),
             /* 0x60  PUSH1 */ OpInfo::push_opcode(),
             /* 0x61  PUSH2 */ OpInfo::push_opcode(),
             /* 0x62  PUSH3 */ OpInfo::push_opcode(),
@@ -579,6 +620,18 @@ pub const fn spec_opcode_gas(spec_id: SpecId) -> &'static [OpInfo; 256] {
             gas_opcodee!(MERGE, SpecId::MERGE);
             MERGE
         }
+        SpecId::SHANGHAI => {
+            gas_opcodee!(SHANGHAI, SpecId::SHANGHAI);
+            SHANGHAI
+        }
+        SpecId::CANCUN => {
+            gas_opcodee!(CANCUN, SpecId::CANCUN);
+            CANCUN
+        }
+        SpecId::PRAGUE => {
+            gas_opcodee!(PRAGUE, SpecId::PRAGUE);
+            PRAGUE
+        }
         SpecId::LATEST => {
             gas_opcodee!(LATEST, SpecId::LATEST);
             LATEST
@@ -586,6 +639,11 @@ pub const fn spec_opcode_gas(spec_id: SpecId) -> &'static [OpInfo; 256] {
     }
 }
 
+/// Returns information about `opcode` for the given spec. See [`OpInfo`].
+pub const fn opcode_info(spec_id: SpecId, opcode: u8) -> OpInfo {
+    spec_opcode_gas(spec_id)[opcode as usize]
+}
+
 pub const OPCODE_JUMPMAP: [Option<&'static str>; 256] = [
     /* 0x00 */ Some("STOP"),
     /* 0x01 */ Some("ADD"),
@@ -660,8 +718,8 @@ pub const OPCODE_JUMPMAP: [Option<&'static str>; 256] = [
     /* 0x46 */ Some("CHAINID"),
     /* 0x47 */ Some("SELFBALANCE"),
     /* 0x48 */ Some("BASEFEE"),
-    /* 0x49 */ None,
-    /* 0x4a */ None,
+    /* 0x49 */ Some("BLOBHASH"),
+    /* 0x4a */ Some("BLOBBASEFEE"),
     /* 0x4b */ None,
     /* 0x4c */ None,
     /* 0x4d */ None,
@@ -682,7 +740,7 @@ pub const OPCODE_JUMPMAP: [Option<&'static str>; 256] = [
     /* 0x5c */ None,
     /* 0x5d */ None,
     /* 0x5e */ None,
-    /* 0x5f */ None,
+    /* 0x5f */ Some("PUSH0"),
     /* 0x60 */ Some("PUSH1"),
     /* 0x61 */ Some("PUSH2"),
     /* 0x62 */ Some("PUSH3"),
@@ -844,3 +902,26 @@ pub const OPCODE_JUMPMAP: [Option<&'static str>; 256] = [
     /* 0xfe */ Some("INVALID"),
     /* 0xff */ Some("SELFDESTRUCT"),
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opcode_info_per_spec() {
+        let push1 = opcode_info(SpecId::LATEST, PUSH1);
+        assert!(push1.is_push());
+        assert_eq!(push1.get_gas(), gas::VERYLOW as u32);
+
+        let jumpdest = opcode_info(SpecId::LATEST, JUMPDEST);
+        assert!(jumpdest.is_jump());
+        assert!(jumpdest.is_gas_block_end());
+
+        assert_eq!(opcode_info(SpecId::BERLIN, BASEFEE).get_gas(), 0);
+        assert_eq!(
+            opcode_info(SpecId::LONDON, BASEFEE).get_gas(),
+            gas::BASE as u32
+        );
+        assert_eq!(opcode_info(SpecId::LATEST, 0x0c), OpInfo::none());
+    }
+}
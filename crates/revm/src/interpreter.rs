@@ -1,23 +1,63 @@
 pub mod bytecode;
+mod bytecode_builder;
 mod contract;
 pub(crate) mod memory;
 mod stack;
 
-pub use bytecode::{Bytecode, BytecodeLocked, BytecodeState};
-pub use contract::Contract;
-pub use memory::Memory;
+pub use bytecode::{
+    AnalysisItem, AnalysisIter, Bytecode, BytecodeLocked, BytecodeState, SubroutineBoundaries,
+};
+pub use bytecode_builder::BytecodeBuilder;
+pub use contract::{Contract, ValidJumpAddress};
+pub use memory::{copy_code_padded, Memory};
 pub use stack::Stack;
 
 use crate::{
-    instructions::{eval, Return},
-    Gas, Host, Spec, USE_GAS,
+    instructions::{eval, host::call_return, Return},
+    spec_opcode_gas, CallInputs, Gas, Host, Spec, USE_GAS,
 };
+use alloc::boxed::Box;
 use bytes::Bytes;
 use core::ops::Range;
+use primitive_types::U256;
 
 pub const STACK_LIMIT: u64 = 1024;
 pub const CALL_STACK_LIMIT: u64 = 1024;
 
+/// Result of executing code with [`Interpreter::run`].
+#[derive(Clone, Debug)]
+pub struct InterpreterResult {
+    /// Why execution stopped, success, revert or halt.
+    pub result: Return,
+    /// Gas at the end of execution, remaining gas and refunds.
+    pub gas: Gas,
+    /// Return or revert data, empty if execution halted without any.
+    pub output: Bytes,
+}
+
+/// Where [`Interpreter::run_yielding`] stopped.
+#[derive(Debug)]
+pub enum Yield {
+    /// Execution finished.
+    Done(InterpreterResult),
+    /// CALL, CALLCODE, DELEGATECALL or STATICCALL is waiting for the host to execute it. Gas
+    /// for the call is already charged. Execution continues with [`Interpreter::resume_call`].
+    CallRequest {
+        inputs: Box<CallInputs>,
+        /// Call has to be executed as static, either because it is STATICCALL or because the
+        /// current call is static.
+        is_static: bool,
+    },
+}
+
+/// CALL that is waiting for the host, see [`Yield::CallRequest`].
+pub(crate) struct PendingCall {
+    pub(crate) inputs: CallInputs,
+    pub(crate) is_static: bool,
+    pub(crate) out_offset: usize,
+    pub(crate) out_len: usize,
+}
+
 pub struct Interpreter {
     /// Contract information and invoking data
     pub contract: Contract,
@@ -33,6 +73,19 @@ pub struct Interpreter {
     pub return_data_buffer: Bytes,
     /// Return value.
     pub return_range: Range<usize>,
+    /// Opcodes that halt with [`Return::OpcodeDisabled`], `None` if all are allowed.
+    pub disabled_opcodes: Option<[bool; 256]>,
+    /// Charge static gas before every opcode instead of once per gas block. See
+    /// [`crate::CfgEnv::gas_per_opcode`].
+    pub gas_per_opcode: bool,
+    /// Count every executed instruction with [`Host::record_instruction`]. Set if
+    /// [`crate::CfgEnv::instruction_limit`] is set.
+    pub limit_instructions: bool,
+    /// Stop at CALLs instead of executing them through the host. Set by
+    /// [`Interpreter::run_yielding`].
+    pub(crate) yield_calls: bool,
+    /// CALL that is waiting for the host if execution stopped with [`Return::CallYield`].
+    pub(crate) pending_call: Option<PendingCall>,
     /// Memory limit. See [`crate::CfgEnv`].
     #[cfg(feature = "memory_limit")]
     pub memory_limit: u64,
@@ -49,6 +102,11 @@ impl Interpreter {
             return_data_buffer: Bytes::new(),
             contract,
             gas: Gas::new(gas_limit),
+            disabled_opcodes: None,
+            gas_per_opcode: false,
+            limit_instructions: false,
+            yield_calls: false,
+            pending_call: None,
         }
     }
 
@@ -66,6 +124,11 @@ impl Interpreter {
             return_data_buffer: Bytes::new(),
             contract,
             gas: Gas::new(gas_limit),
+            disabled_opcodes: None,
+            gas_per_opcode: false,
+            limit_instructions: false,
+            yield_calls: false,
+            pending_call: None,
             memory_limit,
         }
     }
@@ -78,13 +141,19 @@ impl Interpreter {
         &self.gas
     }
 
-    /// Reference of interp stack.
-    pub fn stack(&self) -> &Stack {
-        &self.stack
+    /// Stack values, bottom first. Meant for inspecting interpreter from
+    /// [`crate::Inspector::step`].
+    pub fn stack(&self) -> &[U256] {
+        self.stack.data()
+    }
+
+    /// Memory of the current call.
+    pub fn memory(&self) -> &[u8] {
+        self.memory.data()
     }
 
     pub fn add_next_gas_block(&mut self, pc: usize) -> Return {
-        if USE_GAS {
+        if USE_GAS && !self.gas_per_opcode {
             let gas_block = self.contract.gas_block(pc);
             if !self.gas.record_cost(gas_block) {
                 return Return::OutOfGas;
@@ -103,14 +172,97 @@ impl Interpreter {
     }
 
     /// loop steps until we are finished with execution
-    pub fn run<H: Host, SPEC: Spec>(&mut self, host: &mut H) -> Return {
+    pub fn run<H: Host, SPEC: Spec>(&mut self, host: &mut H) -> InterpreterResult {
+        let result = self.run_from::<H, SPEC>(host, true);
+        self.result(result)
+    }
+
+    fn result(&self, result: Return) -> InterpreterResult {
+        InterpreterResult {
+            result,
+            gas: self.gas,
+            output: self.return_value(),
+        }
+    }
+
+    /// Run until execution finishes or reaches a CALL. Instead of executing the call through
+    /// `host`, it is returned as [`Yield::CallRequest`] for the host to execute it, for
+    /// example asynchronously or in another VM. Result of the call is passed to
+    /// [`Interpreter::resume_call`] which continues execution.
+    ///
+    /// CREATE and CREATE2 are still executed through `host`.
+    pub fn run_yielding<H: Host, SPEC: Spec>(&mut self, host: &mut H) -> Yield {
+        self.yield_calls = true;
+        let ret = self.run_from::<H, SPEC>(host, true);
+        self.yield_to_host(ret)
+    }
+
+    /// Continue execution stopped by [`Yield::CallRequest`] with result of the call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no call waiting for its result.
+    pub fn resume_call<H: Host, SPEC: Spec>(
+        &mut self,
+        host: &mut H,
+        ret: Return,
+        gas: Gas,
+        out: Bytes,
+    ) -> Yield {
+        let pending = self
+            .pending_call
+            .take()
+            .expect("there is no call to resume");
+        let ret = call_return(self, pending.out_offset, pending.out_len, ret, gas, out);
+        let ret = if ret == Return::Continue {
+            self.yield_calls = true;
+            self.run_from::<H, SPEC>(host, false)
+        } else {
+            ret
+        };
+        self.yield_to_host(ret)
+    }
+
+    fn yield_to_host(&mut self, ret: Return) -> Yield {
+        self.yield_calls = false;
+        if ret != Return::CallYield {
+            return Yield::Done(self.result(ret));
+        }
+        let pending = self.pending_call.as_mut().unwrap();
+        Yield::CallRequest {
+            inputs: Box::new(pending.inputs.clone()),
+            is_static: pending.is_static,
+        }
+    }
+
+    fn run_from<H: Host, SPEC: Spec>(&mut self, host: &mut H, first_gas_block: bool) -> Return {
+        if self.gas_per_opcode {
+            self.run_inner::<H, SPEC, true>(host, first_gas_block)
+        } else {
+            self.run_inner::<H, SPEC, false>(host, first_gas_block)
+        }
+    }
+
+    fn run_inner<H: Host, SPEC: Spec, const GAS_PER_OPCODE: bool>(
+        &mut self,
+        host: &mut H,
+        first_gas_block: bool,
+    ) -> Return {
         //let timer = std::time::Instant::now();
         let mut ret = Return::Continue;
+        let opcode_gas = spec_opcode_gas(SPEC::SPEC_ID);
         // add first gas_block
-        if USE_GAS && !self.gas.record_cost(self.contract.first_gas_block()) {
+        if USE_GAS
+            && !GAS_PER_OPCODE
+            && first_gas_block
+            && !self.gas.record_cost(self.contract.first_gas_block())
+        {
             return Return::OutOfGas;
         }
         while ret == Return::Continue {
+            if self.limit_instructions && !host.record_instruction() {
+                return Return::InstructionLimitReached;
+            }
             // step
             if H::INSPECT {
                 let ret = host.step(self, SPEC::IS_STATIC_CALL);
@@ -119,6 +271,19 @@ impl Interpreter {
                 }
             }
             let opcode = unsafe { *self.program_counter };
+            if let Some(disabled) = &self.disabled_opcodes {
+                if disabled[opcode as usize] {
+                    return Return::OpcodeDisabled;
+                }
+            }
+            if USE_GAS
+                && GAS_PER_OPCODE
+                && !self
+                    .gas
+                    .record_cost(opcode_gas[opcode as usize].get_gas() as u64)
+            {
+                return Return::OutOfGas;
+            }
             // Safety: In analysis we are doing padding of bytecode so that we are sure that last.
             // byte instruction is STOP so we are safe to just increment program_counter bcs on last instruction
             // it will do noop and just stop execution of this contract
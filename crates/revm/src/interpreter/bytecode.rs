@@ -1,10 +1,15 @@
 use super::contract::{AnalysisData, ValidJumpAddress};
-use crate::{opcode, spec_opcode_gas, Spec, KECCAK_EMPTY};
+use crate::{opcode, spec_opcode_gas, OpInfo, Spec, SpecId, KECCAK_EMPTY};
 use bytes::Bytes;
-use primitive_types::H256;
+use core::hash::{Hash, Hasher};
+use hashbrown::HashMap;
+use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 use std::sync::Arc;
 
+/// Prefix of EIP-7702 delegation designator, it is followed by the delegated address.
+pub const EIP7702_MAGIC: [u8; 3] = [0xef, 0x01, 0x00];
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BytecodeState {
@@ -15,10 +20,105 @@ pub enum BytecodeState {
     Analysed {
         len: usize,
         jumptable: ValidJumpAddress,
+        /// Spec whose gas table was used for analysis. `None` if it was analysed with a
+        /// custom gas table or created with [`Bytecode::new_analysed`], such bytecode is
+        /// executed as is under any spec.
+        spec_id: Option<SpecId>,
+    },
+    /// Padded as checked bytecode with only valid JUMPDEST positions known, gas blocks are not
+    /// computed so this bytecode is analysed again before execution.
+    JumpdestChecked {
+        len: usize,
+        jumptable: ValidJumpAddress,
     },
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// EIP-2315 subroutine boundaries found by [`Bytecode::subroutine_boundaries`], as positions
+/// in the original code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubroutineBoundaries {
+    /// BEGINSUB positions, entry points of subroutines.
+    pub entries: Vec<usize>,
+    /// Positions after JUMPSUB, where execution resumes on RETURNSUB.
+    pub return_points: Vec<usize>,
+    /// RETURNSUB positions.
+    pub returns: Vec<usize>,
+}
+
+/// Item yielded by [`AnalysisIter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnalysisItem {
+    /// Valid JUMPDEST at the position.
+    JumpDest(usize),
+    /// Instructions from `start` up to and including the one at `end`, which ends the block,
+    /// together with their static gas. The last block ends at the last instruction, even if
+    /// it does not end a gas block.
+    GasBlock { start: usize, end: usize, gas: u64 },
+}
+
+/// Streaming analysis of code, yields the same jumpdests and gas blocks as
+/// [`Bytecode::to_analysed`] without allocating the analysis. Created with
+/// [`Bytecode::analysis_iter`].
+#[derive(Clone, Debug)]
+pub struct AnalysisIter<'a> {
+    code: &'a [u8],
+    table: &'a [OpInfo; 256],
+    index: usize,
+    block_start: usize,
+    gas: u64,
+    jumpdest: Option<usize>,
+}
+
+impl<'a> Iterator for AnalysisIter<'a> {
+    type Item = AnalysisItem;
+
+    fn next(&mut self) -> Option<AnalysisItem> {
+        if let Some(position) = self.jumpdest.take() {
+            return Some(AnalysisItem::JumpDest(position));
+        }
+        while self.index < self.code.len() {
+            let position = self.index;
+            let opcode = self.code[position];
+            let info = self.table[opcode as usize];
+            self.gas += info.get_gas() as u64;
+            self.index += if info.is_push() {
+                (opcode - opcode::PUSH1 + 2) as usize
+            } else {
+                1
+            };
+            if info.is_gas_block_end() {
+                if info.is_jump() {
+                    self.jumpdest = Some(position);
+                }
+                return Some(self.end_block(position));
+            }
+        }
+        if self.block_start < self.code.len() {
+            let last = self.code.len() - 1;
+            return Some(self.end_block(last));
+        }
+        None
+    }
+}
+
+impl<'a> AnalysisIter<'a> {
+    fn end_block(&mut self, end: usize) -> AnalysisItem {
+        let item = AnalysisItem::GasBlock {
+            start: self.block_start,
+            end,
+            gas: self.gas,
+        };
+        self.block_start = self.index.max(end + 1);
+        self.gas = 0;
+        item
+    }
+}
+
+/// Contract code together with its hash and analysis.
+///
+/// Equality and hashing only look at the original code, padding and analysis state are
+/// ignored, so raw and analysed versions of the same code are equal.
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bytecode {
     #[cfg_attr(feature = "with-serde", serde(with = "crate::models::serde_hex_bytes"))]
@@ -27,6 +127,20 @@ pub struct Bytecode {
     state: BytecodeState,
 }
 
+impl PartialEq for Bytecode {
+    fn eq(&self, other: &Self) -> bool {
+        self.original_bytes() == other.original_bytes()
+    }
+}
+
+impl Eq for Bytecode {}
+
+impl Hash for Bytecode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.original_bytes().hash(state);
+    }
+}
+
 impl Default for Bytecode {
     fn default() -> Self {
         Bytecode::new()
@@ -42,6 +156,7 @@ impl Bytecode {
             state: BytecodeState::Analysed {
                 len: 0,
                 jumptable: ValidJumpAddress::new(Arc::new(vec![AnalysisData::none()]), 0),
+                spec_id: None,
             },
         }
     }
@@ -79,7 +194,7 @@ impl Bytecode {
     pub unsafe fn new_checked(bytecode: Bytes, len: usize, hash: Option<H256>) -> Self {
         let hash = match hash {
             None if len == 0 => KECCAK_EMPTY,
-            None => H256::from_slice(Keccak256::digest(&bytecode).as_slice()),
+            None => H256::from_slice(Keccak256::digest(&bytecode[..len]).as_slice()),
             Some(hash) => hash,
         };
         Self {
@@ -103,13 +218,36 @@ impl Bytecode {
     ) -> Self {
         let hash = match hash {
             None if len == 0 => KECCAK_EMPTY,
-            None => H256::from_slice(Keccak256::digest(&bytecode).as_slice()),
+            None => H256::from_slice(Keccak256::digest(&bytecode[..len]).as_slice()),
             Some(hash) => hash,
         };
         Self {
             bytecode,
             hash,
-            state: BytecodeState::Analysed { len, jumptable },
+            state: BytecodeState::Analysed {
+                len,
+                jumptable,
+                spec_id: None,
+            },
+        }
+    }
+
+    /// EIP-7702 delegation designator `0xef0100 || address`, set as code of an account that
+    /// delegates execution to `address`.
+    pub fn new_delegation(address: H160) -> Self {
+        let mut code = EIP7702_MAGIC.to_vec();
+        code.extend_from_slice(address.as_bytes());
+        Self::new_raw(code.into())
+    }
+
+    /// Returns the address execution is delegated to if code is EIP-7702 delegation
+    /// designator.
+    pub fn delegation_address(&self) -> Option<H160> {
+        let code = self.original_bytes();
+        if code.len() == EIP7702_MAGIC.len() + 20 && code.starts_with(&EIP7702_MAGIC) {
+            Some(H160::from_slice(&code[EIP7702_MAGIC.len()..]))
+        } else {
+            None
         }
     }
 
@@ -117,6 +255,13 @@ impl Bytecode {
         &self.bytecode
     }
 
+    /// Code without the padding added by checking or analysis.
+    pub fn original_bytes(&self) -> &[u8] {
+        &self.bytecode[..self.len()]
+    }
+
+    /// Keccak256 hash of the original code. It is computed once when bytecode is created,
+    /// so this is cheap to call.
     pub fn hash(&self) -> H256 {
         self.hash
     }
@@ -130,6 +275,7 @@ impl Bytecode {
             BytecodeState::Raw => self.bytecode.is_empty(),
             BytecodeState::Checked { len } => len == 0,
             BytecodeState::Analysed { len, .. } => len == 0,
+            BytecodeState::JumpdestChecked { len, .. } => len == 0,
         }
     }
 
@@ -138,9 +284,47 @@ impl Bytecode {
             BytecodeState::Raw => self.bytecode.len(),
             BytecodeState::Checked { len, .. } => len,
             BytecodeState::Analysed { len, .. } => len,
+            BytecodeState::JumpdestChecked { len, .. } => len,
         }
     }
 
+    /// Returns raw bytecode that is guaranteed to end with a STOP opcode.
+    ///
+    /// STOP is appended only if the last instruction is not already STOP. If the code ends with
+    /// truncated PUSH data, the data is first padded with zeros (that is how it is executed
+    /// anyway) so that the appended STOP is an opcode and not an immediate. Appended bytes become
+    /// part of the code and the hash is recomputed over them.
+    ///
+    /// Checked and analysed bytecode already have the zero padding after the original code,
+    /// so they are returned unchanged and `hash()` keeps covering only the original code.
+    pub fn ensure_stop_terminated(self) -> Self {
+        if !matches!(self.state, BytecodeState::Raw) {
+            return self;
+        }
+
+        let code = self.bytecode.as_ref();
+        let mut index = 0;
+        let mut last_opcode = None;
+        while index < code.len() {
+            let opcode = code[index];
+            last_opcode = Some(opcode);
+            index += if (opcode::PUSH1..=opcode::PUSH32).contains(&opcode) {
+                (opcode - opcode::PUSH1 + 2) as usize
+            } else {
+                1
+            };
+        }
+        // index points past the push data of the last instruction.
+        if last_opcode == Some(opcode::STOP) {
+            return self;
+        }
+
+        let mut bytecode: Vec<u8> = Vec::from(code);
+        bytecode.resize(index, 0);
+        bytecode.push(opcode::STOP);
+        Self::new_raw(bytecode.into())
+    }
+
     pub fn to_checked(self) -> Self {
         match self.state {
             BytecodeState::Raw => {
@@ -157,7 +341,101 @@ impl Bytecode {
         }
     }
 
+    /// Analyse bytecode for `SPEC`. Bytecode analysed for a different spec is analysed again,
+    /// as gas blocks depend on the gas table of the spec.
     pub fn to_analysed<SPEC: Spec>(self) -> Self {
+        if self.is_analysed_for(SPEC::SPEC_ID) {
+            return self;
+        }
+        self.analyse(spec_opcode_gas(SPEC::SPEC_ID), Some(SPEC::SPEC_ID))
+    }
+
+    /// Returns true if bytecode is analysed and can be executed under `spec_id` as is, that
+    /// is it was analysed for `spec_id` or with a custom gas table.
+    pub fn is_analysed_for(&self, spec_id: SpecId) -> bool {
+        match self.state {
+            BytecodeState::Analysed {
+                spec_id: analysed, ..
+            } => analysed.is_none_or(|analysed| analysed == spec_id),
+            _ => false,
+        }
+    }
+
+    /// Analyse bytecode with a custom opcode gas table, usually a copy of
+    /// [`spec_opcode_gas`] output with some costs changed, e.g. for L2s. Interpreter charges
+    /// static gas from the analysis, so already analysed code keeps its gas table when
+    /// executed. Analysed bytecode is returned as is.
+    pub fn to_analysed_with_table(self, table: &[OpInfo; 256]) -> Self {
+        if matches!(self.state, BytecodeState::Analysed { .. }) {
+            return self;
+        }
+        self.analyse(table, None)
+    }
+
+    fn analyse(self, table: &[OpInfo; 256], spec_id: Option<SpecId>) -> Self {
+        let hash = self.hash;
+        let (bytecode, len) = match self.state {
+            BytecodeState::Raw => {
+                let len = self.bytecode.len();
+                let checked = self.to_checked();
+                (checked.bytecode, len)
+            }
+            // already padded.
+            BytecodeState::Checked { len }
+            | BytecodeState::JumpdestChecked { len, .. }
+            | BytecodeState::Analysed { len, .. } => (self.bytecode, len),
+        };
+        let jumptable = Self::analyze(bytecode.as_ref(), table);
+
+        Self {
+            bytecode,
+            hash,
+            state: BytecodeState::Analysed {
+                len,
+                jumptable,
+                spec_id,
+            },
+        }
+    }
+
+    /// Analyse bytecode reusing jumptable from `cache` if code with the same hash was
+    /// already analysed for the same spec, otherwise analyse it and store the jumptable in
+    /// `cache`.
+    pub fn to_analysed_cached<SPEC: Spec>(
+        self,
+        cache: &mut HashMap<(H256, SpecId), ValidJumpAddress>,
+    ) -> Self {
+        if self.is_analysed_for(SPEC::SPEC_ID) {
+            return self;
+        }
+        match cache.get(&(self.hash, SPEC::SPEC_ID)) {
+            Some(jumptable) => {
+                let len = self.len();
+                let checked = self.to_checked();
+                Self {
+                    bytecode: checked.bytecode,
+                    hash: checked.hash,
+                    state: BytecodeState::Analysed {
+                        len,
+                        jumptable: jumptable.clone(),
+                        spec_id: Some(SPEC::SPEC_ID),
+                    },
+                }
+            }
+            None => {
+                let analysed = self.to_analysed::<SPEC>();
+                if let BytecodeState::Analysed { jumptable, .. } = &analysed.state {
+                    cache.insert((analysed.hash, SPEC::SPEC_ID), jumptable.clone());
+                }
+                analysed
+            }
+        }
+    }
+
+    /// Finds valid JUMPDEST positions without computing gas blocks. Cheaper than
+    /// [`Bytecode::to_analysed`] for tooling that only needs to validate jumps.
+    /// Analysed bytecode is returned as is as it already contains jump information.
+    pub fn to_jumpdest_checked(self) -> Self {
         let hash = self.hash;
         let (bytecode, len) = match self.state {
             BytecodeState::Raw => {
@@ -168,37 +446,178 @@ impl Bytecode {
             BytecodeState::Checked { len } => (self.bytecode, len),
             _ => return self,
         };
-        let jumptable = Self::analyze::<SPEC>(bytecode.as_ref());
+        let jumptable = Self::analyze_jumpdests(bytecode.as_ref());
 
         Self {
             bytecode,
             hash,
-            state: BytecodeState::Analysed { len, jumptable },
+            state: BytecodeState::JumpdestChecked { len, jumptable },
         }
     }
 
+    /// Drops padding and analysis, returning raw bytecode of only the original code. Used to
+    /// reduce memory of code that is kept around but rarely executed, it is analysed again on
+    /// the next execution. The original code is copied so the padded buffer can be freed.
+    pub fn trim_analysis(self) -> Self {
+        if matches!(self.state, BytecodeState::Raw) {
+            return self;
+        }
+        Self {
+            bytecode: Bytes::copy_from_slice(self.original_bytes()),
+            hash: self.hash,
+            state: BytecodeState::Raw,
+        }
+    }
+
+    /// Analyse original code as a stream of jumpdests and gas blocks, for tooling that only
+    /// enumerates them. Unlike [`Bytecode::to_analysed`] memory use does not grow with the
+    /// size of the code.
+    pub fn analysis_iter<SPEC: Spec>(&self) -> AnalysisIter<'_> {
+        self.analysis_iter_with_table(spec_opcode_gas(SPEC::SPEC_ID))
+    }
+
+    /// [`Bytecode::analysis_iter`] with a custom opcode gas table, see
+    /// [`Bytecode::to_analysed_with_table`].
+    pub fn analysis_iter_with_table<'a>(&'a self, table: &'a [OpInfo; 256]) -> AnalysisIter<'a> {
+        AnalysisIter {
+            code: self.original_bytes(),
+            table,
+            index: 0,
+            block_start: 0,
+            gas: 0,
+            jumpdest: None,
+        }
+    }
+
+    /// Returns true if `position` is a valid jump destination. Returns `None` if bytecode is
+    /// not analysed or jumpdest checked.
+    pub fn is_valid_jump(&self, position: usize) -> Option<bool> {
+        match &self.state {
+            BytecodeState::Analysed { jumptable, .. }
+            | BytecodeState::JumpdestChecked { jumptable, .. } => {
+                Some(jumptable.is_valid(position))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the opcode at `pc`. Returns `None` if `pc` is past the end of the original
+    /// code or points inside PUSH data.
+    pub fn opcode_at(&self, pc: usize) -> Option<u8> {
+        if pc >= self.len() || self.is_push_data(pc) {
+            return None;
+        }
+        Some(self.bytecode[pc])
+    }
+
+    /// Returns the immediate of the PUSH instruction at `pc`. Returns `None` if there is no
+    /// PUSH at `pc`, see [`Bytecode::opcode_at`]. Push data truncated by the end of the code
+    /// is padded with zeros, as it is when executed.
+    pub fn push_value_at(&self, pc: usize) -> Option<U256> {
+        let opcode = self.opcode_at(pc)?;
+        if !(opcode::PUSH1..=opcode::PUSH32).contains(&opcode) {
+            return None;
+        }
+        let len = (opcode - opcode::PUSH1 + 1) as usize;
+        let code = self.original_bytes();
+        let data = &code[pc + 1..code.len().min(pc + 1 + len)];
+        let mut value = [0u8; 32];
+        value[32 - len..32 - len + data.len()].copy_from_slice(data);
+        Some(U256::from_big_endian(&value))
+    }
+
+    /// Returns true if `pc` points to an immediate of a PUSH instruction. Positions past the
+    /// end of the code are never push data.
+    pub fn is_push_data(&self, pc: usize) -> bool {
+        let code = self.original_bytes();
+        let mut index = 0;
+        while index < code.len() && index <= pc {
+            let opcode = code[index];
+            if (opcode::PUSH1..=opcode::PUSH32).contains(&opcode) {
+                let data_end = index + (opcode - opcode::PUSH1 + 2) as usize;
+                if pc < data_end {
+                    return pc > index && pc < code.len();
+                }
+                index = data_end;
+            } else {
+                index += 1;
+            }
+        }
+        false
+    }
+
+    /// Find EIP-2315 subroutine boundaries, PUSH data is skipped. Returns `None` if `SPEC` does
+    /// not enable subroutines, see [`Spec::EIP2315`]. This is separate from
+    /// [`Bytecode::to_analysed`], so jumptables are the same with or without subroutines.
+    pub fn subroutine_boundaries<SPEC: Spec>(&self) -> Option<SubroutineBoundaries> {
+        if !SPEC::EIP2315 {
+            return None;
+        }
+        let code = self.original_bytes();
+        let mut boundaries = SubroutineBoundaries::default();
+        let mut index = 0;
+        while index < code.len() {
+            let opcode = code[index];
+            match opcode {
+                opcode::BEGINSUB => boundaries.entries.push(index),
+                opcode::JUMPSUB => boundaries.return_points.push(index + 1),
+                opcode::RETURNSUB => boundaries.returns.push(index),
+                _ => (),
+            }
+            index += if (opcode::PUSH1..=opcode::PUSH32).contains(&opcode) {
+                (opcode - opcode::PUSH1 + 2) as usize
+            } else {
+                1
+            };
+        }
+        Some(boundaries)
+    }
+
     pub fn lock<SPEC: Spec>(self) -> BytecodeLocked {
         let Bytecode {
             bytecode,
             hash,
             state,
         } = self.to_analysed::<SPEC>();
-        if let BytecodeState::Analysed { len, jumptable } = state {
+        if let BytecodeState::Analysed {
+            len,
+            jumptable,
+            spec_id,
+        } = state
+        {
             BytecodeLocked {
                 bytecode,
                 len,
                 hash,
                 jumptable,
+                spec_id,
             }
         } else {
             unreachable!("to_analysed transforms state to analysed");
         }
     }
 
-    /// Analyze bytecode to get jumptable and gas blocks.
-    fn analyze<SPEC: Spec>(code: &[u8]) -> ValidJumpAddress {
-        let opcode_gas = spec_opcode_gas(SPEC::SPEC_ID);
+    /// Mark JUMPDEST opcodes that are not part of PUSH data.
+    fn analyze_jumpdests(code: &[u8]) -> ValidJumpAddress {
+        let mut jumps = vec![AnalysisData::none(); code.len()];
+
+        let mut index = 0;
+        while index < code.len() {
+            let opcode = code[index];
+            if opcode == opcode::JUMPDEST {
+                jumps[index].set_is_jump();
+                index += 1;
+            } else if (opcode::PUSH1..=opcode::PUSH32).contains(&opcode) {
+                index += (opcode - opcode::PUSH1 + 2) as usize;
+            } else {
+                index += 1;
+            }
+        }
+        ValidJumpAddress::new(Arc::new(jumps), 0)
+    }
 
+    /// Analyze bytecode to get jumptable and gas blocks.
+    fn analyze(code: &[u8], opcode_gas: &[OpInfo; 256]) -> ValidJumpAddress {
         let mut analysis = ValidJumpAddress {
             first_gas_block: 0,
             analysis: Arc::new(vec![AnalysisData::none(); code.len()]),
@@ -269,6 +688,7 @@ pub struct BytecodeLocked {
     len: usize,
     hash: H256,
     jumptable: ValidJumpAddress,
+    spec_id: Option<SpecId>,
 }
 
 impl BytecodeLocked {
@@ -290,6 +710,7 @@ impl BytecodeLocked {
             state: BytecodeState::Analysed {
                 len: self.len,
                 jumptable: self.jumptable,
+                spec_id: self.spec_id,
             },
         }
     }
@@ -305,3 +726,349 @@ impl BytecodeLocked {
         &self.jumptable
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BerlinSpec, LatestSpec};
+
+    #[test]
+    fn to_analysed_cached_reuses_jumptable() {
+        // PUSH1 0x04, JUMP, INVALID, JUMPDEST, STOP
+        let code = Bytes::from(vec![0x60, 0x04, 0x56, 0xfe, 0x5b, 0x00]);
+        let mut cache = HashMap::new();
+
+        let first = Bytecode::new_raw(code.clone()).to_analysed_cached::<LatestSpec>(&mut cache);
+        assert_eq!(cache.len(), 1);
+        let second = Bytecode::new_raw(code.clone()).to_analysed_cached::<LatestSpec>(&mut cache);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(first.state(), second.state());
+        assert_eq!(first.bytes(), second.bytes());
+        assert_eq!(
+            second.state(),
+            Bytecode::new_raw(code.clone())
+                .to_analysed::<LatestSpec>()
+                .state()
+        );
+        match (first.state(), second.state()) {
+            (
+                BytecodeState::Analysed { jumptable: a, .. },
+                BytecodeState::Analysed { jumptable: b, .. },
+            ) => assert!(Arc::ptr_eq(&a.analysis, &b.analysis)),
+            _ => panic!("bytecode should be analysed"),
+        }
+
+        // jumptable of another spec is not reused.
+        let berlin = Bytecode::new_raw(code.clone()).to_analysed_cached::<BerlinSpec>(&mut cache);
+        assert_eq!(cache.len(), 2);
+        assert!(berlin.is_analysed_for(SpecId::BERLIN));
+        assert_eq!(
+            berlin.state(),
+            Bytecode::new_raw(code).to_analysed::<BerlinSpec>().state()
+        );
+    }
+
+    #[test]
+    fn analysed_again_for_other_spec() {
+        use crate::LondonSpec;
+        let first_gas_block = |code: &Bytecode| match code.state() {
+            BytecodeState::Analysed { jumptable, .. } => jumptable.first_gas_block,
+            _ => panic!("bytecode should be analysed"),
+        };
+        // BASEFEE, STOP
+        let code = Bytecode::new_raw(vec![0x48, 0x00].into());
+        assert!(!code.is_analysed_for(SpecId::LONDON));
+
+        let london = code.clone().to_analysed::<LondonSpec>();
+        assert!(london.is_analysed_for(SpecId::LONDON));
+        assert!(!london.is_analysed_for(SpecId::BERLIN));
+
+        // BASEFEE costs 2 since London, it does not exist in Berlin.
+        let berlin = london.clone().to_analysed::<BerlinSpec>();
+        assert!(berlin.is_analysed_for(SpecId::BERLIN));
+        assert_eq!(first_gas_block(&london), first_gas_block(&berlin) + 2);
+        assert_eq!(berlin, code.clone().to_analysed::<BerlinSpec>());
+        assert_eq!(
+            first_gas_block(&berlin),
+            first_gas_block(&code.clone().to_analysed::<BerlinSpec>())
+        );
+        let locked = london.clone().lock::<BerlinSpec>();
+        assert_eq!(locked.jumptable().first_gas_block, first_gas_block(&berlin));
+        assert!(locked.unlock().is_analysed_for(SpecId::BERLIN));
+
+        // custom gas tables are kept under any spec.
+        let custom = code.to_analysed_with_table(crate::spec_opcode_gas(SpecId::LONDON));
+        assert!(custom.is_analysed_for(SpecId::BERLIN));
+        assert_eq!(
+            first_gas_block(&custom.to_analysed::<BerlinSpec>()),
+            first_gas_block(&london)
+        );
+    }
+
+    #[test]
+    fn subroutine_boundaries() {
+        struct SubroutineSpec;
+        impl Spec for SubroutineSpec {
+            type STATIC = Self;
+            const SPEC_ID: crate::SpecId = crate::SpecId::LATEST;
+            const IS_STATIC_CALL: bool = false;
+            const ASSUME_PRECOMPILE_HAS_BALANCE: bool = true;
+            const EIP2315: bool = true;
+        }
+
+        // PUSH1 0x04, JUMPSUB, STOP, BEGINSUB, PUSH1 0x5c, RETURNSUB
+        let code = Bytecode::new_raw(vec![0x60, 0x04, 0x5e, 0x00, 0x5c, 0x60, 0x5c, 0x5d].into());
+        assert_eq!(code.subroutine_boundaries::<LatestSpec>(), None);
+        assert_eq!(
+            code.subroutine_boundaries::<SubroutineSpec>(),
+            Some(SubroutineBoundaries {
+                entries: vec![4],
+                return_points: vec![3],
+                returns: vec![7],
+            })
+        );
+        // default analysis is not affected.
+        assert_eq!(
+            code.clone().to_analysed::<SubroutineSpec>().state(),
+            code.to_analysed::<LatestSpec>().state()
+        );
+    }
+
+    #[test]
+    fn hash_ignores_padding() {
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        let checked = code.clone().to_checked();
+        let analysed = code.clone().to_analysed::<LatestSpec>();
+        assert_eq!(checked.hash(), code.hash());
+        assert_eq!(analysed.hash(), code.hash());
+
+        // hash is computed from the original code when not given.
+        let checked = unsafe { Bytecode::new_checked(checked.bytes().clone(), 3, None) };
+        assert_eq!(checked.hash(), code.hash());
+        let jumptable = match analysed.state() {
+            BytecodeState::Analysed { jumptable, .. } => jumptable.clone(),
+            _ => unreachable!(),
+        };
+        let analysed =
+            unsafe { Bytecode::new_analysed(analysed.bytes().clone(), 3, jumptable, None) };
+        assert_eq!(analysed.hash(), code.hash());
+    }
+
+    #[test]
+    fn jumpdest_checked() {
+        // PUSH1 0x5b, JUMPDEST, PUSH2 0x5b5b, JUMPDEST
+        let code = Bytecode::new_raw(vec![0x60, 0x5b, 0x5b, 0x61, 0x5b, 0x5b, 0x5b].into());
+        let analysed = code.clone().to_analysed::<LatestSpec>();
+        let checked = code.clone().to_jumpdest_checked();
+
+        assert_eq!(code.is_valid_jump(2), None);
+        assert_eq!(checked.len(), 7);
+        assert_eq!(checked.hash(), code.hash());
+        for position in 0..10 {
+            assert_eq!(
+                checked.is_valid_jump(position),
+                analysed.is_valid_jump(position)
+            );
+        }
+        assert_eq!(checked.is_valid_jump(2), Some(true));
+        assert_eq!(checked.is_valid_jump(1), Some(false));
+        assert_eq!(checked.is_valid_jump(6), Some(true));
+
+        // jumpdest checked bytecode can still be analysed for execution.
+        assert_eq!(checked.to_analysed::<LatestSpec>(), analysed);
+    }
+
+    #[test]
+    fn ensure_stop_terminated() {
+        // PUSH1 0x01, PUSH1 0x02, ADD
+        let code = Bytecode::new_raw(vec![0x60, 0x01, 0x60, 0x02, 0x01].into());
+        let terminated = code.clone().ensure_stop_terminated();
+        assert_eq!(
+            terminated.bytes().as_ref(),
+            &[0x60, 0x01, 0x60, 0x02, 0x01, 0x00]
+        );
+        assert_ne!(terminated.hash(), code.hash());
+        assert_eq!(terminated, Bytecode::new_raw(terminated.bytes().clone()));
+
+        // already terminated
+        let terminated_again = terminated.clone().ensure_stop_terminated();
+        assert_eq!(terminated_again, terminated);
+
+        // zero as push data is not a STOP, and truncated push data is padded.
+        let code = Bytecode::new_raw(vec![0x61, 0x00].into()).ensure_stop_terminated();
+        assert_eq!(code.bytes().as_ref(), &[0x61, 0x00, 0x00, 0x00]);
+
+        // empty code gets a single STOP.
+        let code = Bytecode::new_raw(Bytes::new()).ensure_stop_terminated();
+        assert_eq!(code.bytes().as_ref(), &[0x00]);
+
+        // analysed code is already padded and keeps its hash.
+        let analysed = Bytecode::new_raw(vec![0x60, 0x01].into()).to_analysed::<LatestSpec>();
+        let hash = analysed.hash();
+        let analysed = analysed.ensure_stop_terminated();
+        assert_eq!(analysed.hash(), hash);
+        assert_eq!(analysed.len(), 2);
+    }
+
+    #[test]
+    fn opcode_at() {
+        // PUSH2 0x5b00, JUMPDEST, PUSH32 (truncated to one byte)
+        let code = Bytecode::new_raw(vec![0x61, 0x5b, 0x00, 0x5b, 0x7f, 0x01].into());
+        for code in [code.clone(), code.to_analysed::<LatestSpec>()] {
+            assert_eq!(code.opcode_at(0), Some(0x61));
+            assert!(!code.is_push_data(0));
+            assert_eq!(code.opcode_at(1), None);
+            assert!(code.is_push_data(1));
+            assert_eq!(code.opcode_at(2), None);
+            assert!(code.is_push_data(2));
+            assert_eq!(code.opcode_at(3), Some(0x5b));
+            assert!(!code.is_push_data(3));
+            assert_eq!(code.opcode_at(4), Some(0x7f));
+            assert!(code.is_push_data(5));
+            // out of range, including the padding of analysed code.
+            assert_eq!(code.opcode_at(6), None);
+            assert!(!code.is_push_data(6));
+            assert_eq!(code.opcode_at(100), None);
+        }
+    }
+
+    #[test]
+    fn push_value_at() {
+        // PUSH1 0x2a, PUSH32 0x01..0x20, ADD, PUSH3 (truncated to 0xffff)
+        let mut code = vec![0x60, 0x2a, 0x7f];
+        code.extend(1..=32u8);
+        code.extend([0x01, 0x62, 0xff, 0xff]);
+        let code = Bytecode::new_raw(code.into());
+        let push32: Vec<u8> = (1..=32u8).collect();
+        for code in [code.clone(), code.to_analysed::<LatestSpec>()] {
+            assert_eq!(code.push_value_at(0), Some(U256::from(0x2a)));
+            assert_eq!(code.push_value_at(1), None);
+            assert_eq!(code.push_value_at(2), Some(U256::from_big_endian(&push32)));
+            assert_eq!(code.push_value_at(3), None);
+            assert_eq!(code.push_value_at(35), None);
+            assert_eq!(code.push_value_at(36), Some(U256::from(0xffff00)));
+            assert_eq!(code.push_value_at(39), None);
+        }
+    }
+
+    #[test]
+    fn eq_and_hash_ignore_state() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |code: &Bytecode| {
+            let mut hasher = DefaultHasher::new();
+            Hash::hash(code, &mut hasher);
+            hasher.finish()
+        };
+
+        let raw = Bytecode::new_raw(vec![0x60, 0x01, 0x00].into());
+        for other in [
+            raw.clone().to_checked(),
+            raw.clone().to_jumpdest_checked(),
+            raw.clone().to_analysed::<LatestSpec>(),
+        ] {
+            assert_eq!(raw, other);
+            assert_eq!(hash(&raw), hash(&other));
+            assert_eq!(other.original_bytes(), &[0x60, 0x01, 0x00]);
+        }
+
+        let different = Bytecode::new_raw(vec![0x60, 0x02, 0x00].into());
+        assert_ne!(raw, different);
+        assert_ne!(raw.to_analysed::<LatestSpec>(), different);
+
+        // empty code in all forms is the same.
+        assert_eq!(Bytecode::new(), Bytecode::new_raw(Bytes::new()));
+    }
+
+    #[test]
+    fn trim_analysis() {
+        // PUSH1 0x03, JUMP, JUMPDEST, STOP
+        let raw = Bytecode::new_raw(vec![0x60, 0x03, 0x56, 0x5b, 0x00].into());
+        for code in [
+            raw.clone().to_checked(),
+            raw.clone().to_jumpdest_checked(),
+            raw.clone().to_analysed::<LatestSpec>(),
+        ] {
+            let trimmed = code.trim_analysis();
+            assert_eq!(trimmed.state(), &BytecodeState::Raw);
+            assert_eq!(trimmed.bytes(), raw.bytes());
+            assert_eq!(trimmed.hash(), raw.hash());
+            assert_eq!(
+                trimmed.hash(),
+                Bytecode::new_raw(trimmed.bytes().clone()).hash()
+            );
+            // analysed again on the next execution.
+            assert_eq!(
+                trimmed.to_analysed::<LatestSpec>().state(),
+                raw.clone().to_analysed::<LatestSpec>().state()
+            );
+        }
+
+        let empty = Bytecode::new().trim_analysis();
+        assert!(empty.bytes().is_empty());
+        assert_eq!(empty.hash(), KECCAK_EMPTY);
+    }
+
+    #[test]
+    fn analysis_iter() {
+        // PUSH1 0x05, JUMP, PUSH2 0x5b5b, JUMPDEST, PUSH1 0x01, ADD, JUMPDEST, CALLVALUE
+        let code = Bytecode::new_raw(
+            vec![
+                0x60, 0x05, 0x56, 0x61, 0x5b, 0x5b, 0x5b, 0x60, 0x01, 0x01, 0x5b, 0x34,
+            ]
+            .into(),
+        );
+        let items: Vec<_> = code.analysis_iter::<LatestSpec>().collect();
+        // JUMPDEST has no static gas in the table, it is charged when executed.
+        assert_eq!(
+            items,
+            vec![
+                AnalysisItem::GasBlock {
+                    start: 0,
+                    end: 2,
+                    gas: 3 + 8
+                },
+                AnalysisItem::GasBlock {
+                    start: 3,
+                    end: 6,
+                    gas: 3
+                },
+                AnalysisItem::JumpDest(6),
+                AnalysisItem::GasBlock {
+                    start: 7,
+                    end: 10,
+                    gas: 3 + 3
+                },
+                AnalysisItem::JumpDest(10),
+                AnalysisItem::GasBlock {
+                    start: 11,
+                    end: 11,
+                    gas: 2
+                },
+            ]
+        );
+
+        // same jumpdests and gas as the full analysis.
+        let analysed = code.clone().to_analysed::<LatestSpec>();
+        let jumptable = match analysed.state() {
+            BytecodeState::Analysed { jumptable, .. } => jumptable,
+            _ => unreachable!(),
+        };
+        let mut gas = Vec::new();
+        for item in items {
+            match item {
+                AnalysisItem::JumpDest(position) => assert!(jumptable.is_valid(position)),
+                AnalysisItem::GasBlock { gas: block_gas, .. } => gas.push(block_gas),
+            }
+        }
+        assert_eq!(gas[0], jumptable.first_gas_block as u64);
+        assert_eq!(gas[1], jumptable.gas_block(2));
+        assert_eq!(gas[2], jumptable.gas_block(6));
+        assert_eq!(gas[3], jumptable.gas_block(10));
+        assert_eq!(
+            (0..code.len()).filter(|p| jumptable.is_valid(*p)).count(),
+            2
+        );
+        assert_eq!(Bytecode::new().analysis_iter::<LatestSpec>().count(), 0);
+    }
+}
@@ -0,0 +1,157 @@
+use super::bytecode::Bytecode;
+use crate::{
+    alloc::{string::String, vec::Vec},
+    opcode, LatestSpec,
+};
+use hashbrown::HashMap as Map;
+use primitive_types::U256;
+
+/// Assembles [`Bytecode`] from opcodes, mostly for tests.
+///
+/// Values are pushed with the smallest `PUSHn` that fits them. Jump targets are named labels
+/// that can be used before they are placed, they are resolved by [`BytecodeBuilder::build`].
+#[derive(Clone, Debug, Default)]
+pub struct BytecodeBuilder {
+    code: Vec<u8>,
+    labels: Map<String, usize>,
+    /// Positions of two byte label references and the label they refer to.
+    references: Vec<(usize, String)>,
+}
+
+impl BytecodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a single opcode.
+    pub fn op(mut self, opcode: u8) -> Self {
+        self.code.push(opcode);
+        self
+    }
+
+    /// Push `value` with the smallest `PUSHn` that fits it, zero is pushed with `PUSH1`.
+    pub fn push(mut self, value: impl Into<U256>) -> Self {
+        let mut bytes = [0u8; 32];
+        value.into().to_big_endian(&mut bytes);
+        let skip = bytes.iter().take(31).take_while(|byte| **byte == 0).count();
+        let bytes = &bytes[skip..];
+        self.code.push(opcode::PUSH1 + bytes.len() as u8 - 1);
+        self.code.extend_from_slice(bytes);
+        self
+    }
+
+    /// Push position of `label` with `PUSH2`.
+    pub fn push_label(mut self, label: &str) -> Self {
+        self.code.push(opcode::PUSH2);
+        self.references.push((self.code.len(), label.into()));
+        self.code.extend_from_slice(&[0, 0]);
+        self
+    }
+
+    /// Place `label` here and mark it with `JUMPDEST`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if label is already placed.
+    pub fn jumpdest(mut self, label: &str) -> Self {
+        let previous = self.labels.insert(label.into(), self.code.len());
+        assert!(previous.is_none(), "label {} is placed twice", label);
+        self.op(opcode::JUMPDEST)
+    }
+
+    /// Jump to `label`.
+    pub fn jump(self, label: &str) -> Self {
+        self.push_label(label).op(opcode::JUMP)
+    }
+
+    /// Jump to `label` if the value on top of the stack is not zero.
+    pub fn jumpi(self, label: &str) -> Self {
+        self.push_label(label).op(opcode::JUMPI)
+    }
+
+    /// Assembled code with labels resolved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a referenced label is not placed.
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        for (position, label) in &self.references {
+            let target = match self.labels.get(label) {
+                Some(target) => *target as u16,
+                None => panic!("label {} is not placed", label),
+            };
+            self.code[*position..*position + 2].copy_from_slice(&target.to_be_bytes());
+        }
+        self.code
+    }
+
+    /// Assembled code, analysed with gas costs of the latest spec. See
+    /// [`BytecodeBuilder::into_bytes`].
+    pub fn build(self) -> Bytecode {
+        Bytecode::new_raw(self.into_bytes().into()).to_analysed::<LatestSpec>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccountInfo, InMemoryDB, Return, TransactTo};
+    use primitive_types::H160;
+
+    #[test]
+    fn push_width() {
+        let code = BytecodeBuilder::new()
+            .push(0)
+            .push(0xff)
+            .push(0x100)
+            .push(U256::MAX)
+            .into_bytes();
+        let mut expected = vec![0x60, 0x00, 0x60, 0xff, 0x61, 0x01, 0x00, 0x7f];
+        expected.extend_from_slice(&[0xff; 32]);
+        assert_eq!(code, expected);
+    }
+
+    #[test]
+    fn counting_loop() {
+        // count iterations of `for (i = 5; i != 0; i--)` and store them in slot 0.
+        let code = BytecodeBuilder::new()
+            .push(0)
+            .push(5)
+            .jumpdest("loop")
+            .op(opcode::SWAP1)
+            .push(1)
+            .op(opcode::ADD)
+            .op(opcode::SWAP1)
+            .push(1)
+            .op(opcode::SWAP1)
+            .op(opcode::SUB)
+            .op(opcode::DUP1)
+            .jumpi("loop")
+            .op(opcode::POP)
+            .push(0)
+            .op(opcode::SSTORE)
+            .op(opcode::STOP)
+            .build();
+        assert!(code.is_valid_jump(4).unwrap());
+
+        let contract = H160::from_low_u64_be(0x42);
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(contract, AccountInfo::from_code(code));
+        let mut evm = crate::new();
+        evm.database(db);
+        evm.env.tx.transact_to = TransactTo::Call(contract);
+        evm.env.tx.gas_limit = 100_000;
+        let (result, state) = evm.transact();
+        assert_eq!(result.exit_reason, Return::Stop);
+        assert_eq!(
+            state[&contract].storage[&U256::zero()].present_value(),
+            U256::from(5)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "label end is not placed")]
+    fn missing_label() {
+        BytecodeBuilder::new().jump("end").into_bytes();
+    }
+}
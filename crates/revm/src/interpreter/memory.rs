@@ -49,7 +49,12 @@ impl Memory {
     /// Resize the memory. asume that we already checked if
     /// we have enought gas to resize this vector and that we made new_size as multiply of 32
     pub fn resize(&mut self, new_size: usize) {
+        let old_size = self.data.len();
         self.data.resize(new_size, 0);
+        // expanded memory has to read as zeros, only checked in debug builds.
+        debug_assert!(self.data[min(old_size, new_size)..]
+            .iter()
+            .all(|byte| *byte == 0));
     }
 
     /// Get memory region at given offset. Dont check offset and size
@@ -84,25 +89,32 @@ impl Memory {
     /// are doing bound checks on data/data_offeset/len and zeroing parts that is not copied.
     #[inline(always)]
     pub fn set_data(&mut self, memory_offset: usize, data_offset: usize, len: usize, data: &[u8]) {
-        if data_offset >= data.len() {
-            // nulify all memory slots
-            for i in &mut self.data[memory_offset..memory_offset + len] {
-                *i = 0;
-            }
-            return;
-        }
-        let data_end = min(data_offset + len, data.len());
-        let memory_data_end = memory_offset + (data_end - data_offset);
-        self.data[memory_offset..memory_data_end].copy_from_slice(&data[data_offset..data_end]);
-
-        // nulify rest of memory slots
-        // Safety: Memory is assumed to be valid. And it is commented where that assumption is made
-        for i in &mut self.data[memory_data_end..memory_offset + len] {
-            *i = 0;
-        }
+        copy_padded(
+            &mut self.data[memory_offset..memory_offset + len],
+            data,
+            data_offset,
+        );
     }
 }
 
+/// Returns `len` bytes of `code` starting at `code_offset`, bytes past the end of code are
+/// zero. This is how CODECOPY, EXTCODECOPY and CALLDATACOPY read their source.
+pub fn copy_code_padded(code: &[u8], code_offset: usize, len: usize) -> Vec<u8> {
+    let mut out = vec![0; len];
+    copy_padded(&mut out, code, code_offset);
+    out
+}
+
+/// Fill `dest` from `data` starting at `data_offset`, zeroing the part that is past the end
+/// of `data`. Any `data_offset` is valid.
+#[inline(always)]
+fn copy_padded(dest: &mut [u8], data: &[u8], data_offset: usize) {
+    let available = data.get(data_offset..).unwrap_or_default();
+    let copied = min(available.len(), dest.len());
+    dest[..copied].copy_from_slice(&available[..copied]);
+    dest[copied..].fill(0);
+}
+
 /// Rounds up `x` to the closest multiple of 32. If `x % 32 == 0` then `x` is returned.
 #[inline]
 pub(crate) fn next_multiple_of_32(x: usize) -> Option<usize> {
@@ -112,7 +124,38 @@ pub(crate) fn next_multiple_of_32(x: usize) -> Option<usize> {
 
 #[cfg(test)]
 mod tests {
-    use super::next_multiple_of_32;
+    use super::{copy_code_padded, next_multiple_of_32, Memory};
+
+    #[test]
+    fn test_copy_code_padded() {
+        let code = [1, 2, 3];
+        assert_eq!(copy_code_padded(&code, 0, 2), vec![1, 2]);
+        assert_eq!(copy_code_padded(&code, 1, 4), vec![2, 3, 0, 0]);
+        // offset past the end gives only zeros.
+        assert_eq!(copy_code_padded(&code, 3, 2), vec![0, 0]);
+        assert_eq!(copy_code_padded(&code, 100, 3), vec![0, 0, 0]);
+        assert_eq!(copy_code_padded(&code, usize::MAX, 3), vec![0, 0, 0]);
+        assert_eq!(copy_code_padded(&code, usize::MAX - 1, 0), Vec::<u8>::new());
+
+        let mut memory = Memory::new();
+        memory.resize(32);
+        memory.set(0, &[0xff; 32]);
+        memory.set_data(1, usize::MAX, 2, &code);
+        memory.set_data(4, 2, 3, &code);
+        assert_eq!(&memory.data()[..8], &[0xff, 0, 0, 0xff, 3, 0, 0, 0xff]);
+    }
+
+    #[test]
+    fn test_resize_zeroes_memory() {
+        let mut memory = Memory::new();
+        memory.resize(64);
+        memory.set(0, &[0xff; 64]);
+        // shrinking keeps the capacity, growing again must not expose old bytes.
+        memory.resize(32);
+        memory.resize(96);
+        assert_eq!(memory.get_slice(0, 32), &[0xff; 32]);
+        assert_eq!(memory.get_slice(32, 64), &[0; 64]);
+    }
 
     #[test]
     fn test_next_multiple_of_32() {
@@ -8,6 +8,8 @@ pub const STACK_LIMIT: usize = 1024;
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stack {
     data: Vec<U256>,
+    /// Maximum number of items, pushing past it fails with `StackOverflow`.
+    limit: usize,
 }
 
 #[cfg(feature = "std")]
@@ -38,11 +40,27 @@ impl Stack {
     /// Create a new stack with given limit.
     pub fn new() -> Self {
         Self {
-            // Safety: A lot of functions assumes that capacity is STACK_LIMIT
+            // Safety: A lot of functions assumes that capacity is at least limit
             data: Vec::with_capacity(STACK_LIMIT),
+            limit: STACK_LIMIT,
         }
     }
 
+    #[inline]
+    /// Maximum number of items on the stack, [`STACK_LIMIT`] by default.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Change the maximum number of items. Allocates only if `limit` is above any limit used
+    /// before. Items already on the stack are kept even if there are more than `limit`.
+    pub fn set_limit(&mut self, limit: usize) {
+        if limit > self.data.capacity() {
+            self.data.reserve_exact(limit - self.data.len());
+        }
+        self.limit = limit;
+    }
+
     #[inline]
     /// Stack length.
     pub fn len(&self) -> usize {
@@ -85,10 +103,11 @@ impl Stack {
     /// # Safety
     /// The caller is responsible to check length of array
     pub unsafe fn pop_unsafe(&mut self) -> U256 {
-        let mut len = self.data.len();
-        len -= 1;
+        let len = self.data.len() - 1;
+        // read before shrinking, indexing past the length with `get_unchecked` is UB.
+        let pop = *self.data.get_unchecked(len);
         self.data.set_len(len);
-        *self.data.get_unchecked(len)
+        pop
     }
 
     #[inline(always)]
@@ -136,13 +155,13 @@ impl Stack {
     /// # Safety
     /// The caller is responsible to check length of array
     pub unsafe fn pop2_unsafe(&mut self) -> (U256, U256) {
-        let mut len = self.data.len();
-        len -= 2;
-        self.data.set_len(len);
-        (
+        let len = self.data.len() - 2;
+        let pop = (
             *self.data.get_unchecked(len + 1),
             *self.data.get_unchecked(len),
-        )
+        );
+        self.data.set_len(len);
+        pop
     }
 
     #[inline(always)]
@@ -151,14 +170,14 @@ impl Stack {
     /// # Safety
     /// The caller is responsible to check length of array
     pub unsafe fn pop3_unsafe(&mut self) -> (U256, U256, U256) {
-        let mut len = self.data.len();
-        len -= 3;
-        self.data.set_len(len);
-        (
+        let len = self.data.len() - 3;
+        let pop = (
             *self.data.get_unchecked(len + 2),
             *self.data.get_unchecked(len + 1),
             *self.data.get_unchecked(len),
-        )
+        );
+        self.data.set_len(len);
+        pop
     }
 
     #[inline(always)]
@@ -167,22 +186,22 @@ impl Stack {
     /// # Safety
     /// The caller is responsible to check length of array
     pub unsafe fn pop4_unsafe(&mut self) -> (U256, U256, U256, U256) {
-        let mut len = self.data.len();
-        len -= 4;
-        self.data.set_len(len);
-        (
+        let len = self.data.len() - 4;
+        let pop = (
             *self.data.get_unchecked(len + 3),
             *self.data.get_unchecked(len + 2),
             *self.data.get_unchecked(len + 1),
             *self.data.get_unchecked(len),
-        )
+        );
+        self.data.set_len(len);
+        pop
     }
 
     #[inline]
     /// Push a new value into the stack. If it will exceed the stack limit,
     /// returns `StackOverflow` error and leaves the stack unchanged.
     pub fn push_h256(&mut self, value: H256) -> Result<(), Return> {
-        if self.data.len() + 1 > STACK_LIMIT {
+        if self.data.len() + 1 > self.limit {
             return Err(Return::StackOverflow);
         }
         self.data.push(U256::from_big_endian(value.as_ref()));
@@ -193,7 +212,7 @@ impl Stack {
     /// Push a new value into the stack. If it will exceed the stack limit,
     /// returns `StackOverflow` error and leaves the stack unchanged.
     pub fn push(&mut self, value: U256) -> Result<(), Return> {
-        if self.data.len() + 1 > STACK_LIMIT {
+        if self.data.len() + 1 > self.limit {
             return Err(Return::StackOverflow);
         }
         self.data.push(value);
@@ -217,12 +236,13 @@ impl Stack {
         let len = self.data.len();
         if len < N {
             Return::StackUnderflow
-        } else if len + 1 > STACK_LIMIT {
+        } else if len + 1 > self.limit {
             Return::StackOverflow
         } else {
             // Safety: check for out of bounds is done above and it makes this safe to do.
             unsafe {
-                *self.data.get_unchecked_mut(len) = *self.data.get_unchecked(len - N);
+                // slot at `len` is past the length, write it through the pointer.
+                *self.data.as_mut_ptr().add(len) = *self.data.get_unchecked(len - N);
                 self.data.set_len(len + 1);
             }
             Return::Continue
@@ -248,7 +268,7 @@ impl Stack {
     #[inline(always)]
     pub fn push_slice<const N: usize>(&mut self, slice: &[u8]) -> Return {
         let new_len = self.data.len() + 1;
-        if new_len > STACK_LIMIT {
+        if new_len > self.limit {
             return Return::StackOverflow;
         }
 
@@ -306,3 +326,51 @@ impl Stack {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Stack;
+    use crate::Return;
+    use primitive_types::U256;
+
+    fn stack(values: &[u64]) -> Stack {
+        let mut stack = Stack::new();
+        for value in values {
+            stack.push(U256::from(*value)).unwrap();
+        }
+        stack
+    }
+
+    #[test]
+    fn test_pop_unsafe() {
+        let mut stack = stack(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        unsafe {
+            assert_eq!(stack.pop_unsafe(), U256::from(10));
+            assert_eq!(stack.pop2_unsafe(), (U256::from(9), U256::from(8)));
+            assert_eq!(
+                stack.pop3_unsafe(),
+                (U256::from(7), U256::from(6), U256::from(5))
+            );
+            assert_eq!(
+                stack.pop4_unsafe(),
+                (U256::from(4), U256::from(3), U256::from(2), U256::from(1))
+            );
+        }
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_dup() {
+        let mut stack = stack(&[1, 2]);
+        assert_eq!(stack.dup::<2>(), Return::Continue);
+        assert_eq!(
+            stack.data(),
+            &vec![U256::from(1), U256::from(2), U256::from(1)]
+        );
+        assert_eq!(stack.dup::<4>(), Return::StackUnderflow);
+
+        stack.set_limit(3);
+        assert_eq!(stack.dup::<1>(), Return::StackOverflow);
+        assert_eq!(stack.len(), 3);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{interpreter::bytecode::Bytecode, models::SelfDestructResult, Return, KECCAK_EMPTY};
+use crate::{
+    interpreter::bytecode::Bytecode, models::SelfDestructResult, Return, Spec, SpecId::CANCUN,
+    KECCAK_EMPTY,
+};
 use alloc::{vec, vec::Vec};
 use core::mem::{self};
 use hashbrown::{hash_map::Entry, HashMap as Map};
@@ -6,6 +9,10 @@ use primitive_types::{H160, U256};
 
 use crate::{db::Database, AccountInfo, Log};
 
+/// State of the running transaction between the interpreter and the [`Database`]. Every change
+/// is recorded in a journal so that calls that fail can be reverted to their checkpoint without
+/// touching the database. Changes reach the database only when state returned by
+/// [`JournaledState::finalize`] is committed.
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JournaledState {
@@ -13,12 +20,18 @@ pub struct JournaledState {
     pub state: State,
     /// logs
     pub logs: Vec<Log>,
+    /// Logs of reverted calls, only kept if `keep_reverted_logs` is set.
+    pub reverted_logs: Vec<Log>,
+    /// Move logs of reverted calls to `reverted_logs` instead of dropping them.
+    pub keep_reverted_logs: bool,
     /// how deep are we in call stack.
     pub depth: usize,
     /// journal with changes that happened between calls.
     pub journal: Vec<Vec<JournalEntry>>,
 }
 
+/// Accounts touched by a transaction and their new values. It is returned by
+/// [`crate::EVM::transact`] and can be applied with [`crate::DatabaseCommit::commit`].
 pub type State = Map<H160, Account>;
 pub type Storage = Map<U256, StorageSlot>;
 
@@ -33,6 +46,12 @@ pub struct Account {
     pub storage_cleared: bool,
     /// if account is destroyed it will be scheduled for removal.
     pub is_destroyed: bool,
+    /// Account was created by the current transaction. Since Cancun only such accounts are
+    /// removed by SELFDESTRUCT (EIP-6780).
+    pub is_created: bool,
+    /// Beneficiary and balance sent to it by the last selfdestruct of this account. Balance is
+    /// already added to the beneficiary, if beneficiary is the account itself balance is burned.
+    pub selfdestruct_transfer: Option<(H160, U256)>,
     /// if account is touched
     pub is_touched: bool,
     /// is precompile
@@ -52,6 +71,8 @@ impl From<AccountInfo> for Account {
             storage: Map::new(),
             storage_cleared: false,
             is_destroyed: false,
+            is_created: false,
+            selfdestruct_transfer: None,
             is_touched: false,
             is_existing_precompile: false,
         }
@@ -61,6 +82,7 @@ impl From<AccountInfo> for Account {
 #[derive(Debug, Clone, Default, Eq, PartialEq)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StorageSlot {
+    /// Value at the start of the transaction, used for EIP-2200/EIP-3529 SSTORE gas and refunds.
     original_value: U256,
     /// When loaded with sload present value is set to original value
     present_value: U256,
@@ -73,9 +95,24 @@ impl StorageSlot {
             present_value: original,
         }
     }
+    /// Slot that was changed from `original` to `present`.
+    pub fn new_changed(original: U256, present: U256) -> Self {
+        Self {
+            original_value: original,
+            present_value: present,
+        }
+    }
+    /// Value of the slot when it was first loaded in this transaction.
+    pub fn original_value(&self) -> U256 {
+        self.original_value
+    }
     pub fn present_value(&self) -> U256 {
         self.present_value
     }
+    /// Returns true if present value differs from the original one.
+    pub fn is_changed(&self) -> bool {
+        self.original_value != self.present_value
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -93,12 +130,17 @@ pub enum JournalEntry {
         target: H160,
         was_destroyed: bool, // if account had already been destroyed before this journal entry
         had_balance: U256,
+        had_transfer: Option<(H160, U256)>,
     },
     /// Loading account does not mean that account will need to be added to MerkleTree (touched).
     /// Only when account is called (to execute contract or transfer balance) only then account is made touched.
     /// Action: Mark account touched
     /// Revert: Unmark account touched
     AccountTouched { address: H160 },
+    /// Account is created by CREATE or CREATE2.
+    /// Action: Mark account created
+    /// Revert: Unmark account created
+    AccountCreated { address: H160 },
     /// Transfer balance between two accounts
     /// Action: Transfer balance
     /// Revert: Transfer balance back
@@ -141,6 +183,8 @@ impl JournaledState {
         Self {
             state: Map::new(),
             logs: Vec::new(),
+            reverted_logs: Vec::new(),
+            keep_reverted_logs: false,
             journal: vec![vec![]],
             depth: 0,
         }
@@ -186,7 +230,7 @@ impl JournaledState {
         self.state.extend(state);
     }
 
-    /// do cleanup and return modified state
+    /// do cleanup and return modified state. Reverted logs are left in `reverted_logs`.
     pub fn finalize(&mut self) -> (State, Vec<Log>) {
         let state = mem::take(&mut self.state);
 
@@ -221,7 +265,7 @@ impl JournaledState {
             .unwrap()
             .push(JournalEntry::CodeChange {
                 address,
-                had_code: code.clone(),
+                had_code: account.info.code.clone().unwrap_or_default(),
             });
 
         account.info.code_hash = code.hash();
@@ -231,18 +275,14 @@ impl JournaledState {
     pub fn inc_nonce(&mut self, address: H160) -> Option<u64> {
         let account = self.state.get_mut(&address).unwrap();
         // Check if nonce is going to overflow.
-        if account.info.nonce == u64::MAX {
-            return None;
-        }
+        let nonce = account.info.inc_nonce()?;
         Self::touch_account(self.journal.last_mut().unwrap(), &address, account);
         self.journal
             .last_mut()
             .unwrap()
             .push(JournalEntry::NonceChange { address });
 
-        account.info.nonce += 1;
-
-        Some(account.info.nonce)
+        Some(nonce)
     }
 
     pub fn transfer<DB: Database>(
@@ -319,11 +359,11 @@ impl JournaledState {
 
         acc.info.code_hash = KECCAK_EMPTY;
         acc.info.code = None;
+        acc.is_created = true;
 
-        self.journal
-            .last_mut()
-            .unwrap()
-            .push(JournalEntry::AccountTouched { address });
+        let journal = self.journal.last_mut().unwrap();
+        journal.push(JournalEntry::AccountTouched { address });
+        journal.push(JournalEntry::AccountCreated { address });
         true
     }
 
@@ -341,18 +381,26 @@ impl JournaledState {
                         state.get_mut(&address).unwrap().is_touched = false;
                     }
                 }
+                JournalEntry::AccountCreated { address } => {
+                    state.get_mut(&address).unwrap().is_created = false;
+                }
                 JournalEntry::AccountDestroyed {
                     address,
                     target,
                     was_destroyed,
                     had_balance,
+                    had_transfer,
                 } => {
                     let account = state.get_mut(&address).unwrap();
                     account.is_destroyed = was_destroyed;
+                    account.selfdestruct_transfer = had_transfer;
                     account.info.balance += had_balance;
 
-                    let target = state.get_mut(&target).unwrap();
-                    target.info.balance -= had_balance;
+                    // balance sent to itself was burned, not transferred.
+                    if address != target {
+                        let target = state.get_mut(&target).unwrap();
+                        target.info.balance -= had_balance;
+                    }
                 }
                 JournalEntry::BalanceTransfer { from, to, balance } => {
                     // we dont need to check overflow and underflow when adding sub subtracting the balance.
@@ -410,23 +458,55 @@ impl JournaledState {
             .take(leng - checkpoint.journal_i)
             .for_each(|cs| Self::journal_revert(state, mem::take(cs)));
 
-        self.logs.truncate(checkpoint.log_i);
+        if self.keep_reverted_logs {
+            self.reverted_logs
+                .extend(self.logs.drain(checkpoint.log_i..));
+        } else {
+            self.logs.truncate(checkpoint.log_i);
+        }
         self.journal.truncate(checkpoint.journal_i);
     }
 
     /// transfer balance from address to target. Check if target exist/is_cold
-    pub fn selfdestruct<DB: Database>(
+    ///
+    /// Since Cancun (EIP-6780) account is destroyed only if it was created in the same
+    /// transaction, otherwise only its balance is transferred.
+    pub fn selfdestruct<SPEC: Spec, DB: Database>(
         &mut self,
         address: H160,
         target: H160,
         db: &mut DB,
     ) -> SelfDestructResult {
         let (is_cold, exists) = self.load_account_exist(target, db);
-        // transfer all the balance
         let acc = self.state.get_mut(&address).unwrap();
+        if SPEC::enabled(CANCUN) && !acc.is_created {
+            let balance = acc.info.balance;
+            // balance sent to itself stays with the account.
+            if address != target && !balance.is_zero() {
+                acc.info.balance = U256::zero();
+                let journal = self.journal.last_mut().unwrap();
+                let target_account = self.state.get_mut(&target).unwrap();
+                Self::touch_account(journal, &target, target_account);
+                target_account.info.balance += balance;
+                journal.push(JournalEntry::BalanceTransfer {
+                    from: address,
+                    to: target,
+                    balance,
+                });
+            }
+            return SelfDestructResult {
+                had_value: !balance.is_zero(),
+                is_cold,
+                exists,
+                previously_destroyed: false,
+            };
+        }
+
+        // transfer all the balance
         let balance = mem::take(&mut acc.info.balance);
         let previously_destroyed = acc.is_destroyed;
         acc.is_destroyed = true;
+        let had_transfer = acc.selfdestruct_transfer.replace((target, balance));
         // In case that target and destroyed addresses are same, balance will be lost.
         // ref: https://github.com/ethereum/go-ethereum/blob/141cd425310b503c5678e674a8c3872cf46b7086/core/vm/instructions.go#L832-L833
         // https://github.com/ethereum/go-ethereum/blob/141cd425310b503c5678e674a8c3872cf46b7086/core/state/statedb.go#L449
@@ -444,6 +524,7 @@ impl JournaledState {
                 target,
                 was_destroyed: previously_destroyed,
                 had_balance: balance,
+                had_transfer,
             });
 
         SelfDestructResult {
@@ -455,6 +536,31 @@ impl JournaledState {
     }
 
     /// load account into memory. return if it is cold or hot accessed
+    /// Returns true if account was already accessed in this transaction (EIP-2929).
+    /// Warm accounts are the ones present in state, they are cleared on `finalize`.
+    pub fn is_warm_account(&self, address: H160) -> bool {
+        self.state.contains_key(&address)
+    }
+
+    /// Marks account as warm by loading it. Returns true if it was cold.
+    pub fn warm_account<DB: Database>(&mut self, address: H160, db: &mut DB) -> bool {
+        self.load_account(address, db)
+    }
+
+    /// Returns true if storage slot was already accessed in this transaction (EIP-2929).
+    pub fn is_warm_storage(&self, address: H160, key: U256) -> bool {
+        self.state
+            .get(&address)
+            .map(|account| account.storage.contains_key(&key))
+            .unwrap_or_default()
+    }
+
+    /// Marks account and its storage slot as warm by loading them. Returns true if slot was cold.
+    pub fn warm_storage<DB: Database>(&mut self, address: H160, key: U256, db: &mut DB) -> bool {
+        self.load_account(address, db);
+        self.sload(address, key, db).1
+    }
+
     pub fn load_account<DB: Database>(&mut self, address: H160, db: &mut DB) -> bool {
         match self.state.entry(address) {
             Entry::Occupied(ref mut _entry) => false,
@@ -487,7 +593,8 @@ impl JournaledState {
         let is_cold = self.load_account(address, db);
         let acc = self.state.get_mut(&address).unwrap();
         if acc.info.code.is_none() {
-            if acc.info.code_hash == KECCAK_EMPTY {
+            // accounts without code, most of all EOAs, do not need a database lookup.
+            if !acc.info.has_code() {
                 let empty = Bytecode::new();
                 acc.info.code = Some(empty);
             } else {
@@ -567,3 +674,101 @@ impl JournaledState {
         self.logs.push(log);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDB, ShanghaiSpec};
+
+    #[test]
+    fn warm_and_cold_access() {
+        let mut db = InMemoryDB::default();
+        let mut journal = JournaledState::new();
+        let address = H160::from_low_u64_be(1);
+        let key = U256::from(7);
+
+        assert!(!journal.is_warm_account(address));
+        assert!(journal.warm_account(address, &mut db));
+        assert!(journal.is_warm_account(address));
+        assert!(!journal.warm_account(address, &mut db));
+
+        assert!(!journal.is_warm_storage(address, key));
+        assert!(journal.warm_storage(address, key, &mut db));
+        assert!(journal.is_warm_storage(address, key));
+        assert!(!journal.warm_storage(address, key, &mut db));
+        assert!(!journal.sload(address, key, &mut db).1);
+
+        // everything is cold again in the next transaction.
+        journal.finalize();
+        assert!(!journal.is_warm_account(address));
+        assert!(!journal.is_warm_storage(address, key));
+    }
+
+    #[test]
+    fn nested_reverts_restore_storage() {
+        let address = H160::from_low_u64_be(0x42);
+        let key = U256::one();
+        let mut db = InMemoryDB::default();
+        db.insert_account_storage(address, key, U256::from(5));
+        let mut journal = JournaledState::new();
+        journal.load_account(address, &mut db);
+        journal.touch(&address);
+        let present =
+            |journal: &JournaledState| journal.account(address).storage[&key].present_value;
+
+        journal.sstore(address, key, U256::from(1), &mut db);
+        let outer = journal.checkpoint();
+        journal.sstore(address, key, U256::from(2), &mut db);
+        journal.inc_nonce(address);
+
+        let inner = journal.checkpoint();
+        journal.sstore(address, key, U256::from(3), &mut db);
+        journal.sstore(address, U256::from(2), U256::from(9), &mut db);
+        journal.checkpoint_revert(inner);
+        assert_eq!(present(&journal), U256::from(2));
+        assert!(!journal.is_warm_storage(address, U256::from(2)));
+        assert_eq!(journal.account(address).info.nonce, 1);
+
+        journal.checkpoint();
+        journal.sstore(address, key, U256::from(4), &mut db);
+        journal.checkpoint_commit();
+        assert_eq!(present(&journal), U256::from(4));
+
+        // reverting outer call reverts committed inner calls too.
+        journal.checkpoint_revert(outer);
+        assert_eq!(present(&journal), U256::from(1));
+        assert_eq!(journal.account(address).info.nonce, 0);
+        assert_eq!(journal.depth(), 0);
+
+        // database is not written to, original value is kept for gas calculation.
+        assert_eq!(Database::storage(&mut db, address, key), U256::from(5));
+        let (state, _) = journal.finalize();
+        let slot = &state[&address].storage[&key];
+        assert_eq!(slot.original_value(), U256::from(5));
+        assert_eq!(slot.present_value(), U256::from(1));
+    }
+
+    #[test]
+    fn revert_selfdestruct_to_self() {
+        let address = H160::from_low_u64_be(0x42);
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(address, AccountInfo::from_balance(U256::from(1000)));
+        let mut journal = JournaledState::new();
+        journal.load_account(address, &mut db);
+
+        let checkpoint = journal.checkpoint();
+        journal.selfdestruct::<ShanghaiSpec, _>(address, address, &mut db);
+        let account = journal.account(address);
+        assert_eq!(account.info.balance, U256::zero());
+        assert_eq!(
+            account.selfdestruct_transfer,
+            Some((address, U256::from(1000)))
+        );
+
+        journal.checkpoint_revert(checkpoint);
+        let account = journal.account(address);
+        assert!(!account.is_destroyed);
+        assert_eq!(account.selfdestruct_transfer, None);
+        assert_eq!(account.info.balance, U256::from(1000));
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 //#![no_std]
 
+mod access_witness;
+mod block;
 pub mod db;
 mod evm;
 mod evm_impl;
@@ -10,26 +12,38 @@ mod instructions;
 mod interpreter;
 mod journaled_state;
 mod models;
+mod parallel;
+mod prestate_tracer;
 mod specification;
+mod storage_hook;
+mod validation;
 
-pub use evm_impl::{EVMData, Host};
+pub use evm_impl::{create2_address, create_address, EVMData, Host};
 
 pub type DummyStateDB = InMemoryDB;
 
+pub use access_witness::{AccessWitness, WitnessEntry, CODE_CHUNK_SIZE};
+pub use block::{finalize_block, BlockRewards, TxFee};
 pub use db::{Database, DatabaseCommit, InMemoryDB};
-pub use evm::{evm_inner, new, EVM};
+pub use evm::{evm_inner, new, EstimateError, EVM};
 pub use gas::Gas;
 pub use inspector::{Inspector, NoOpInspector};
 pub use instructions::{
-    opcode::{self, spec_opcode_gas, OpCode, OPCODE_JUMPMAP},
-    Return,
+    opcode::{self, opcode_info, spec_opcode_gas, OpCode, OpInfo, OPCODE_JUMPMAP},
+    Halt, Return,
 };
 pub use interpreter::{
-    Bytecode, BytecodeLocked, BytecodeState, Contract, Interpreter, Memory, Stack,
+    copy_code_padded, AnalysisItem, AnalysisIter, Bytecode, BytecodeBuilder, BytecodeLocked,
+    BytecodeState, Contract, Interpreter, InterpreterResult, Memory, Stack, SubroutineBoundaries,
+    ValidJumpAddress, Yield,
 };
-pub use journaled_state::{Account, JournaledState};
+pub use journaled_state::{Account, JournaledState, State, StorageSlot};
 pub use models::*;
+pub use parallel::{transact_parallel, BatchResult};
+pub use prestate_tracer::{PrestateAccount, PrestateTrace, PrestateTracer};
 pub use specification::*;
+pub use storage_hook::{HookStorage, StorageHook, StorageHookRef};
+pub use validation::{validate_env, InvalidTransaction};
 
 extern crate alloc;
 
@@ -1,8 +1,13 @@
 use core::cmp::min;
 
-use crate::{alloc::vec::Vec, interpreter::bytecode::Bytecode, Return, SpecId};
+use crate::{
+    alloc::{collections::BTreeSet, string::String, vec::Vec},
+    interpreter::{bytecode::Bytecode, CALL_STACK_LIMIT, STACK_LIMIT},
+    Halt, Return, SpecId,
+};
 use bytes::Bytes;
 use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
 
 pub const KECCAK_EMPTY: H256 = H256([
     0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
@@ -55,8 +60,13 @@ impl AccountInfo {
     }
 
     pub fn is_empty(&self) -> bool {
-        let code_empty = self.code_hash == KECCAK_EMPTY || self.code_hash.is_zero();
-        self.balance.is_zero() && self.nonce == 0 && code_empty
+        self.balance.is_zero() && self.nonce == 0 && !self.has_code()
+    }
+
+    /// Whether account has code, decided by `code_hash` alone so code does not need to be
+    /// loaded. Zero hash is treated as [`KECCAK_EMPTY`].
+    pub fn has_code(&self) -> bool {
+        self.code_hash != KECCAK_EMPTY && !self.code_hash.is_zero()
     }
 
     pub fn exists(&self) -> bool {
@@ -69,9 +79,25 @@ impl AccountInfo {
             ..Default::default()
         }
     }
+
+    /// Increment nonce, returns `None` and leaves it unchanged if it would overflow.
+    pub fn inc_nonce(&mut self) -> Option<u64> {
+        self.nonce = self.nonce.checked_add(1)?;
+        Some(self.nonce)
+    }
+
+    /// Account with given code and `code_hash` set to its hash.
+    pub fn from_code(code: Bytecode) -> Self {
+        AccountInfo {
+            code_hash: code.hash(),
+            code: Some(code),
+            ..Default::default()
+        }
+    }
 }
 
 /// Inputs for a call.
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CallInputs {
     /// The target of the call.
@@ -137,6 +163,55 @@ pub enum CreateScheme {
     },
 }
 
+/// Outcome of a `CREATE`, `CREATE2` or create transaction.
+///
+/// Inside the interpreter a failed creation only pushes zero on the stack, this tells why it
+/// failed.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CreateResult {
+    /// Contract was deployed at the address.
+    Created(H160),
+    /// Init code executed `REVERT`.
+    Reverted,
+    /// Init code or code deposit ran out of gas.
+    OutOfGas,
+    /// Account already exists at the created address.
+    Collision,
+    /// Deployed code is over [`crate::CfgEnv::limit_contract_code_size`] (EIP-170).
+    CodeTooLarge,
+    /// Deployed code starts with `0xEF` (EIP-3541).
+    InvalidCode,
+    /// Creation failed for another reason.
+    Failed(Return),
+}
+
+impl CreateResult {
+    /// Classify `ret` and created `address` as returned by [`crate::Host::create`].
+    pub fn new(ret: Return, address: Option<H160>) -> Self {
+        match (ret, address) {
+            (
+                Return::Continue | Return::Stop | Return::Return | Return::SelfDestruct,
+                Some(address),
+            ) => CreateResult::Created(address),
+            (Return::Revert, _) => CreateResult::Reverted,
+            (Return::OutOfGas, _) => CreateResult::OutOfGas,
+            (Return::CreateCollision, _) => CreateResult::Collision,
+            (Return::CreateContractLimit, _) => CreateResult::CodeTooLarge,
+            (Return::CreateContractWithEF, _) => CreateResult::InvalidCode,
+            (ret, _) => CreateResult::Failed(ret),
+        }
+    }
+
+    /// Returns the created address on success.
+    pub fn address(&self) -> Option<H160> {
+        match self {
+            CreateResult::Created(address) => Some(*address),
+            _ => None,
+        }
+    }
+}
+
 /// Call schemes.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
@@ -195,9 +270,93 @@ pub struct BlockEnv {
     pub coinbase: H160,
     pub timestamp: U256,
     pub difficulty: U256,
+    /// Output of the randomness beacon, returned by DIFFICULTY (now PREVRANDAO) since the
+    /// Merge. If it is not set `difficulty` is returned. EIP-4399.
+    pub prevrandao: Option<H256>,
     /// basefee is added in EIP1559 London upgrade
     pub basefee: U256,
     pub gas_limit: U256,
+    /// Excess blob gas of the block, used to calculate the blob gas price. Added in EIP-4844
+    /// Cancun upgrade.
+    pub blob_excess_gas: Option<u64>,
+}
+
+/// Signed EIP-7702 authorization, lets the signer (authority) delegate execution of its
+/// account to code of `address`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Authorization {
+    /// Chain the authorization is valid on, zero for any chain.
+    pub chain_id: U256,
+    /// Address whose code is executed for the authority. Zero address clears delegation.
+    pub address: H160,
+    /// Nonce the authority account has to have.
+    pub nonce: u64,
+    pub y_parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
+/// Half of the secp256k1 curve order, signatures with higher `s` are malleable (EIP-2).
+const SECP256K1N_HALF: U256 = U256([
+    0xdfe92f46681b20a0,
+    0x5d576e7357a4501d,
+    0xffffffffffffffff,
+    0x7fffffffffffffff,
+]);
+
+/// Prefix of the message signed by EIP-7702 authorization.
+const EIP7702_AUTH_MAGIC: u8 = 0x05;
+
+impl Authorization {
+    /// Hash signed by the authority, `keccak256(0x05 || rlp([chain_id, address, nonce]))`.
+    pub fn signature_hash(&self) -> H256 {
+        let mut stream = rlp::RlpStream::new_list(3);
+        stream.append(&self.chain_id);
+        stream.append(&self.address);
+        stream.append(&self.nonce);
+        let mut hasher = Keccak256::new();
+        hasher.update([EIP7702_AUTH_MAGIC]);
+        hasher.update(stream.out());
+        H256::from_slice(&hasher.finalize())
+    }
+
+    /// Recover the signer of the authorization, `None` if signature is not valid.
+    pub fn authority(&self) -> Option<H160> {
+        if self.y_parity > 1 || self.s > SECP256K1N_HALF {
+            return None;
+        }
+        let mut sig = [0u8; 65];
+        self.r.to_big_endian(&mut sig[..32]);
+        self.s.to_big_endian(&mut sig[32..64]);
+        sig[64] = self.y_parity;
+        revm_precompiles::recover_address(&sig, self.signature_hash().as_fixed_bytes())
+    }
+}
+
+/// Address of the Optimism L1 fee vault, credited with the L1 cost of regular transactions.
+#[cfg(feature = "optimism")]
+pub const L1_FEE_RECIPIENT: H160 = H160([
+    0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x1a,
+]);
+
+/// Optimism specific transaction fields.
+#[cfg(feature = "optimism")]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptimismFields {
+    /// Source hash of a deposit transaction, `None` for regular transactions.
+    ///
+    /// Deposits are already included on L1 so they skip fee checks, do not pay for gas and
+    /// can be sent by accounts with code.
+    pub source_hash: Option<H256>,
+    /// Value minted to the caller of a deposit before execution. Minted value is kept even if
+    /// the execution fails.
+    pub mint: Option<U256>,
+    /// L1 data fee of a regular transaction. Deducted from the caller together with the gas
+    /// payment and credited to [`L1_FEE_RECIPIENT`].
+    pub l1_cost: U256,
 }
 
 #[derive(Clone, Debug)]
@@ -206,7 +365,9 @@ pub struct TxEnv {
     /// Caller or Author or tx signer
     pub caller: H160,
     pub gas_limit: u64,
+    /// Gas price of legacy transactions, or `max_fee_per_gas` of EIP-1559 transactions.
     pub gas_price: U256,
+    /// `max_priority_fee_per_gas` of EIP-1559 transactions, `None` for legacy transactions.
     pub gas_priority_fee: Option<U256>,
     pub transact_to: TransactTo,
     pub value: U256,
@@ -215,10 +376,31 @@ pub struct TxEnv {
     pub chain_id: Option<u64>,
     pub nonce: Option<u64>,
     pub access_list: Vec<(H160, Vec<U256>)>,
+    /// Versioned hashes of the blobs carried by the transaction, read by BLOBHASH. EIP-4844.
+    pub blob_hashes: Vec<H256>,
+    /// Authorizations applied before execution, EIP-7702.
+    pub authorization_list: Vec<Authorization>,
+    #[cfg(feature = "optimism")]
+    pub optimism: OptimismFields,
+}
+
+impl TxEnv {
+    /// Optimism deposit transaction, always `false` without the `optimism` feature.
+    #[cfg(feature = "optimism")]
+    pub fn is_deposit(&self) -> bool {
+        self.optimism.source_hash.is_some()
+    }
+
+    /// Optimism deposit transaction, always `false` without the `optimism` feature.
+    #[cfg(not(feature = "optimism"))]
+    pub fn is_deposit(&self) -> bool {
+        false
+    }
 }
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CfgEnv {
+    /// Chain id returned by `CHAINID`, EIP-1344. Defaults to 1, mainnet.
     pub chain_id: U256,
     pub spec_id: SpecId,
     /// If all precompiles have some balance we can skip initially fetching them from the database.
@@ -229,15 +411,63 @@ pub struct CfgEnv {
     /// This is very benefitial for testing and speeds up execution of that bytecode when
     pub perf_analyse_created_bytecodes: bool,
     /// Effects EIP-170: Contract code size limit. Usefull to increase this because of tests.
-    /// By default it is 0x6000 (~25kb).
+    /// By default it is 0x6000 (~25kb). Init code is limited to twice this size since
+    /// Shanghai, see [`CfgEnv::max_initcode_size`].
     pub limit_contract_code_size: usize,
+    /// Addresses that may be created with code over `limit_contract_code_size`, for injecting
+    /// system contracts and predeploys. Empty by default.
+    pub contract_code_size_exempt: BTreeSet<H160>,
+    /// Maximum depth of nested calls and creates. Going deeper fails the CALL/CREATE the same
+    /// way as exceeding the call stack does on mainnet. By default it is 1024.
+    pub call_depth_limit: u64,
+    /// Maximum number of items on the stack of every call. Pushing past it halts with
+    /// [`Return::StackOverflow`]. By default it is 1024.
+    pub stack_limit: usize,
+    /// Opcodes, indexed by value, that halt execution with [`Return::OpcodeDisabled`]. Useful
+    /// for sandboxes that forbid e.g. `SELFDESTRUCT` or `CREATE`. None are disabled by default.
+    #[cfg_attr(feature = "with-serde", serde(with = "serde_opcode_set"))]
+    pub disabled_opcodes: [bool; 256],
+    /// Charge static gas of every opcode right before it executes, as Geth does, instead of
+    /// charging a whole block of statically priced opcodes at its start. Total gas used is the
+    /// same, but running out of gas is detected at the exact opcode, which traces comparing
+    /// against Geth need. Costs an extra check per opcode and ignores custom gas tables of
+    /// [`crate::Bytecode::to_analysed_with_table`]. Defaults to false.
+    pub gas_per_opcode: bool,
+    /// Skip crediting the coinbase with fees at the end of every transaction, so that fees of
+    /// the whole block can be credited at once with [`crate::finalize_block`]. Defaults to false.
+    pub defer_coinbase_reward: bool,
+    /// Keep logs of reverted calls in [`ExecutionResult::reverted_logs`], for debugging.
+    /// Consensus requires them to be discarded, which is the default.
+    pub keep_reverted_logs: bool,
+    /// Maximum number of instructions a transaction executes across all of its calls, halting
+    /// with [`Return::InstructionLimitReached`] when exceeded. Independent of gas, useful when
+    /// running untrusted code with cheap or free opcodes. No limit by default.
+    pub instruction_limit: Option<u64>,
+    /// Maximum length of data returned by a call or create, for sandboxes that want to bound
+    /// returndata buffers. Longer data is truncated and [`ExecutionResult::returndata_truncated`]
+    /// is set, or the returning call halts with [`Return::ReturnDataLimitReached`] if
+    /// `halt_on_returndata_limit` is set. No limit by default, as consensus requires.
+    pub max_returndata_size: Option<usize>,
+    /// Halt calls that return more than `max_returndata_size` instead of truncating their
+    /// data. Defaults to false.
+    pub halt_on_returndata_limit: bool,
     /// A hard memory limit in bytes beyond which [Memory] cannot be resized.
     ///
     /// In cases where the gas limit may be extraordinarily high, it is recommended to set this to
-    /// a sane value to prevent memory allocation panics. Defaults to `2^32 - 1` bytes per
-    /// EIP-1985.
+    /// a sane value to prevent memory allocation panics, e.g. `2^32 - 1` bytes per EIP-1985.
+    /// Expanding memory past the limit halts with [`Return::MemoryLimitOOG`] before anything is
+    /// allocated. Defaults to `u64::MAX`, no limit. Without the `memory_limit` feature memory is
+    /// only bounded by gas.
     #[cfg(feature = "memory_limit")]
     pub memory_limit: u64,
+    /// Enables the RIP-7212 secp256r1 signature verification precompile at address `0x100`,
+    /// adopted by L2s. Disabled by default.
+    #[cfg(feature = "secp256r1")]
+    pub p256_verify: bool,
+    /// Hook invoked on SLOAD and SSTORE that can change values and gas, see
+    /// [`crate::StorageHook`]. None by default.
+    #[cfg_attr(feature = "with-serde", serde(skip))]
+    pub storage_hook: Option<crate::StorageHookRef>,
 }
 
 impl Default for CfgEnv {
@@ -248,12 +478,41 @@ impl Default for CfgEnv {
             perf_all_precompiles_have_balance: false,
             perf_analyse_created_bytecodes: true,
             limit_contract_code_size: 0x6000,
+            contract_code_size_exempt: BTreeSet::new(),
+            call_depth_limit: CALL_STACK_LIMIT,
+            stack_limit: STACK_LIMIT as usize,
+            disabled_opcodes: [false; 256],
+            gas_per_opcode: false,
+            defer_coinbase_reward: false,
+            keep_reverted_logs: false,
+            instruction_limit: None,
+            max_returndata_size: None,
+            halt_on_returndata_limit: false,
             #[cfg(feature = "memory_limit")]
-            memory_limit: 2u64.pow(32) - 1,
+            memory_limit: u64::MAX,
+            #[cfg(feature = "secp256r1")]
+            p256_verify: false,
+            storage_hook: None,
         }
     }
 }
 
+impl CfgEnv {
+    /// EIP-3860: Limit and meter initcode. Init code of create transactions and CREATE/CREATE2
+    /// may be at most twice the [`CfgEnv::limit_contract_code_size`].
+    pub fn max_initcode_size(&self) -> usize {
+        self.limit_contract_code_size.saturating_mul(2)
+    }
+}
+
+impl BlockEnv {
+    /// Blob gas price derived from `blob_excess_gas`, missing excess blob gas is treated as
+    /// zero. EIP-4844.
+    pub fn get_blob_gasprice(&self) -> u128 {
+        crate::gas::calc_blob_gasprice(self.blob_excess_gas.unwrap_or_default())
+    }
+}
+
 impl Default for BlockEnv {
     fn default() -> BlockEnv {
         BlockEnv {
@@ -262,7 +521,9 @@ impl Default for BlockEnv {
             coinbase: H160::zero(),
             timestamp: U256::one(),
             difficulty: U256::zero(),
+            prevrandao: None,
             basefee: U256::zero(),
+            blob_excess_gas: None,
         }
     }
 }
@@ -280,19 +541,25 @@ impl Default for TxEnv {
             chain_id: None,
             nonce: None,
             access_list: Vec::new(),
+            blob_hashes: Vec::new(),
+            authorization_list: Vec::new(),
+            #[cfg(feature = "optimism")]
+            optimism: OptimismFields::default(),
         }
     }
 }
 
 impl Env {
+    /// Price paid for every unit of gas. Legacy transactions pay `gas_price`, EIP-1559
+    /// transactions pay `min(max_fee_per_gas, basefee + max_priority_fee_per_gas)`. Sender is
+    /// charged and refunded unused gas at this price.
     pub fn effective_gas_price(&self) -> U256 {
-        if self.tx.gas_priority_fee.is_none() {
-            self.tx.gas_price
-        } else {
-            min(
+        match self.tx.gas_priority_fee {
+            None => self.tx.gas_price,
+            Some(priority_fee) => min(
                 self.tx.gas_price,
-                self.block.basefee + self.tx.gas_priority_fee.unwrap(),
-            )
+                self.block.basefee.saturating_add(priority_fee),
+            ),
         }
     }
 }
@@ -353,6 +620,32 @@ pub(crate) mod serde_hex_bytes {
         .map_err(|e| serde::de::Error::custom(e.to_string()))
     }
 }
+/// Serde functions to serde a set of opcodes as list of opcode values
+#[cfg(feature = "with-serde")]
+pub(crate) mod serde_opcode_set {
+    use crate::alloc::vec::Vec;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(set: &[bool; 256], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let opcodes: Vec<u8> = (0..=255u8).filter(|op| set[*op as usize]).collect();
+        opcodes.serialize(s)
+    }
+
+    pub fn deserialize<'de, D>(d: D) -> Result<[bool; 256], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut set = [false; 256];
+        for opcode in Vec::<u8>::deserialize(d)? {
+            set[opcode as usize] = true;
+        }
+        Ok(set)
+    }
+}
+
 /// Serde functions to serde an Option [bytes::Bytes] hex string
 #[cfg(feature = "with-serde")]
 pub(crate) mod serde_hex_bytes_opt {
@@ -401,9 +694,18 @@ pub(crate) mod serde_hex_bytes_opt {
 pub struct ExecutionResult {
     pub exit_reason: Return,
     pub out: TransactOut,
+    /// Gas used by the transaction, after refunds.
     pub gas_used: u64,
     pub gas_refunded: u64,
+    /// Part of `gas_used` charged before execution: base cost, calldata, access list and
+    /// authorizations. Execution gas is `gas_used + gas_refunded - intrinsic_gas`.
+    pub intrinsic_gas: u64,
     pub logs: Vec<Log>,
+    /// Logs of reverted calls, in order they were reverted. Only kept with
+    /// [`CfgEnv::keep_reverted_logs`], they are not part of consensus.
+    pub reverted_logs: Vec<Log>,
+    /// Data returned by some call was cut to [`CfgEnv::max_returndata_size`].
+    pub returndata_truncated: bool,
 }
 
 impl ExecutionResult {
@@ -413,7 +715,126 @@ impl ExecutionResult {
             out: TransactOut::None,
             gas_used: 0,
             gas_refunded: 0,
+            intrinsic_gas: 0,
             logs: Vec::new(),
+            reverted_logs: Vec::new(),
+            returndata_truncated: false,
         }
     }
+
+    /// Returns the reason execution halted exceptionally, if it did.
+    pub fn halt_reason(&self) -> Option<Halt> {
+        self.exit_reason.halt()
+    }
+
+    /// Returns the outcome of contract creation, `None` if transaction was a call or did not
+    /// execute.
+    pub fn create_result(&self) -> Option<CreateResult> {
+        match &self.out {
+            TransactOut::Create(_, address) => Some(CreateResult::new(self.exit_reason, *address)),
+            _ => None,
+        }
+    }
+
+    /// Returns the decoded revert reason if execution reverted, see [`decode_revert_reason`].
+    pub fn revert_reason(&self) -> Option<String> {
+        if self.exit_reason != Return::Revert {
+            return None;
+        }
+        match &self.out {
+            TransactOut::Call(output) | TransactOut::Create(output, _) => {
+                decode_revert_reason(output)
+            }
+            TransactOut::None => None,
+        }
+    }
+}
+
+/// Selector of `Error(string)`.
+pub const REVERT_ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`.
+pub const REVERT_PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Decodes revert output of a call.
+///
+/// `Error(string)` is decoded to its message and `Panic(uint256)` to `panic code 0x..`. Any
+/// other output of at least four bytes is a custom error and is returned as
+/// `custom error 0x<selector>`. Returns `None` for empty output or malformed ABI encoding.
+pub fn decode_revert_reason(output: &Bytes) -> Option<String> {
+    if output.len() < 4 {
+        return None;
+    }
+    let (selector, data) = output.split_at(4);
+    if selector == REVERT_ERROR_SELECTOR {
+        let offset = abi_word_to_usize(data.get(..32)?)?;
+        let len_end = offset.checked_add(32)?;
+        let len = abi_word_to_usize(data.get(offset..len_end)?)?;
+        let message = data.get(len_end..len_end.checked_add(len)?)?;
+        return String::from_utf8(message.to_vec()).ok();
+    }
+    if selector == REVERT_PANIC_SELECTOR {
+        let code = U256::from_big_endian(data.get(..32)?);
+        return Some(format!("panic code {:#x}", code));
+    }
+    let selector = selector
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    Some(format!("custom error 0x{}", selector))
+}
+
+/// Reads a 32 byte big endian ABI word, returning `None` if it does not fit into `usize`.
+fn abi_word_to_usize(word: &[u8]) -> Option<usize> {
+    let word = U256::from_big_endian(word);
+    if word > U256::from(usize::MAX) {
+        return None;
+    }
+    Some(word.as_usize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_error_string() {
+        // Error("Not enough Ether provided.")
+        let message = b"Not enough Ether provided.";
+        let mut output = REVERT_ERROR_SELECTOR.to_vec();
+        let mut word = [0u8; 32];
+        U256::from(32).to_big_endian(&mut word);
+        output.extend_from_slice(&word);
+        U256::from(message.len()).to_big_endian(&mut word);
+        output.extend_from_slice(&word);
+        output.extend_from_slice(message);
+        output.resize(output.len() + 32 - message.len(), 0);
+        let output = Bytes::from(output);
+        assert_eq!(
+            decode_revert_reason(&output).as_deref(),
+            Some("Not enough Ether provided.")
+        );
+
+        // truncated message
+        let truncated = output.slice(..output.len() - 32);
+        assert_eq!(decode_revert_reason(&truncated), None);
+    }
+
+    #[test]
+    fn decode_panic_and_custom_error() {
+        let mut panic = REVERT_PANIC_SELECTOR.to_vec();
+        panic.extend_from_slice(&[0u8; 31]);
+        panic.push(0x11);
+        assert_eq!(
+            decode_revert_reason(&Bytes::from(panic)).as_deref(),
+            Some("panic code 0x11")
+        );
+
+        let custom = Bytes::from(vec![0xde, 0xad, 0xbe, 0xef, 0x01]);
+        assert_eq!(
+            decode_revert_reason(&custom).as_deref(),
+            Some("custom error 0xdeadbeef")
+        );
+
+        assert_eq!(decode_revert_reason(&Bytes::new()), None);
+    }
 }
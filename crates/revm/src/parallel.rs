@@ -0,0 +1,305 @@
+use crate::{
+    db::{CacheDB, Database, DatabaseCommit, DatabaseRef, DbQuery, RecorderDB, RefDBWrapper},
+    evm_impl::EVMData,
+    journaled_state::State,
+    opcode, Env, ExecutionResult, Inspector, Interpreter, Return, TransactTo, TxEnv,
+};
+use alloc::vec::Vec;
+use hashbrown::HashSet;
+use primitive_types::{H160, U256};
+
+/// Outcome of [`transact_parallel`].
+pub struct BatchResult<'a, DB: DatabaseRef> {
+    /// Execution results in transaction order.
+    pub results: Vec<ExecutionResult>,
+    /// Indices of transactions that conflicted with an earlier transaction of the batch and
+    /// were executed again on top of its changes.
+    pub reexecuted: Vec<usize>,
+    /// Base state with changes of all transactions applied.
+    pub state: CacheDB<&'a DB>,
+}
+
+/// Transaction executed against a database, with all database reads it made.
+struct Executed {
+    result: ExecutionResult,
+    state: State,
+    reads: Vec<DbQuery>,
+    /// Whether execution could depend on the coinbase account, not counting the fee payment.
+    coinbase_observed: bool,
+}
+
+/// Keys written by already applied transactions of the batch.
+#[derive(Default)]
+struct WriteSet {
+    accounts: HashSet<H160>,
+    wiped_storage: HashSet<H160>,
+    slots: HashSet<(H160, U256)>,
+}
+
+/// Execute `txs` in `env` on top of `db`, giving the same results as executing them one by one
+/// in order.
+///
+/// All transactions are first executed concurrently, each against `db` alone, while recording
+/// the accounts and storage slots they read. Results are then applied in order. A transaction
+/// that read something an earlier transaction of the batch wrote is executed again on top of
+/// the applied changes.
+///
+/// Every transaction pays fees to the coinbase. When the coinbase is only read for the fee
+/// payment, or to warm it up (EIP-3651), the fee is added to the coinbase balance instead of
+/// counting as a conflict.
+pub fn transact_parallel<'a, DB: DatabaseRef + Sync>(
+    db: &'a DB,
+    env: &Env,
+    txs: Vec<TxEnv>,
+) -> BatchResult<'a, DB> {
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let chunk_size = txs.len().div_ceil(threads).max(1);
+
+    let speculative: Vec<Executed> = std::thread::scope(|scope| {
+        let handles: Vec<_> = txs
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|tx| execute(RefDBWrapper::new(db), env, tx))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("transaction execution panicked"))
+            .collect()
+    });
+
+    let coinbase = env.block.coinbase;
+    let mut state = CacheDB::new(db);
+    let mut writes = WriteSet::default();
+    let mut results = Vec::with_capacity(txs.len());
+    let mut reexecuted = Vec::new();
+    for (index, (tx, mut executed)) in txs.iter().zip(speculative).enumerate() {
+        let fee_only_coinbase = !executed.coinbase_observed;
+        let skip_basic = fee_only_coinbase.then_some(coinbase);
+        let coinbase_read = executed.reads.iter().find_map(|query| match query {
+            DbQuery::Basic { address, info } if *address == coinbase => Some(info.clone()),
+            _ => None,
+        });
+
+        if writes.conflicts(&executed, skip_basic) {
+            reexecuted.push(index);
+            executed = execute(&mut state, env, tx);
+        } else if let (true, Some(base)) = (fee_only_coinbase, coinbase_read) {
+            if let Some(account) = executed.state.get_mut(&coinbase) {
+                let fee = account.info.balance.saturating_sub(base.balance);
+                account.info = DatabaseRef::basic(&state, coinbase);
+                account.info.balance = account.info.balance.saturating_add(fee);
+            }
+        }
+
+        writes.record(&executed);
+        state.commit(executed.state);
+        results.push(executed.result);
+    }
+
+    BatchResult {
+        results,
+        reexecuted,
+        state,
+    }
+}
+
+fn execute<DB: Database>(db: DB, env: &Env, tx: &TxEnv) -> Executed {
+    let mut evm = crate::new();
+    evm.env = env.clone();
+    evm.env.tx = tx.clone();
+    evm.database(RecorderDB::new(db));
+    let coinbase = env.block.coinbase;
+    let mut observer = CoinbaseObserver {
+        coinbase,
+        observed: tx.caller == coinbase
+            || matches!(tx.transact_to, TransactTo::Call(to) if to == coinbase),
+    };
+    let (result, state) = evm.inspect(&mut observer);
+    Executed {
+        result,
+        state,
+        reads: evm.db.take().unwrap().into_journal(),
+        coinbase_observed: observer.observed,
+    }
+}
+
+/// Notices instructions that access the coinbase account.
+struct CoinbaseObserver {
+    coinbase: H160,
+    observed: bool,
+}
+
+impl<DB: Database> Inspector<DB> for CoinbaseObserver {
+    fn step(
+        &mut self,
+        interp: &mut Interpreter,
+        _data: &mut EVMData<'_, DB>,
+        _is_static: bool,
+    ) -> Return {
+        let opcode = interp.contract.bytecode.bytecode()[interp.program_counter()];
+        // position of the accessed address from the top of the stack.
+        let address_at = match opcode {
+            opcode::BALANCE
+            | opcode::EXTCODESIZE
+            | opcode::EXTCODECOPY
+            | opcode::EXTCODEHASH
+            | opcode::SELFDESTRUCT => Some(0),
+            opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL => Some(1),
+            opcode::SELFBALANCE => {
+                self.observed |= interp.contract.address == self.coinbase;
+                None
+            }
+            _ => None,
+        };
+        let stack = interp.stack();
+        if let Some(value) = address_at
+            .and_then(|at| stack.len().checked_sub(at + 1))
+            .map(|index| stack[index])
+        {
+            let mut bytes = [0u8; 32];
+            value.to_big_endian(&mut bytes);
+            self.observed |= H160::from_slice(&bytes[12..]) == self.coinbase;
+        }
+        Return::Continue
+    }
+}
+
+impl WriteSet {
+    /// Returns true if transaction read or changed anything that was written. Account reads of
+    /// `skip_basic` are ignored.
+    fn conflicts(&self, executed: &Executed, skip_basic: Option<H160>) -> bool {
+        let read_conflict = executed.reads.iter().any(|query| match query {
+            DbQuery::Basic { address, .. } => {
+                skip_basic != Some(*address) && self.accounts.contains(address)
+            }
+            DbQuery::Storage { address, index, .. } => {
+                self.wiped_storage.contains(address) || self.slots.contains(&(*address, *index))
+            }
+            DbQuery::CodeByHash { .. } | DbQuery::BlockHash { .. } => false,
+        });
+        // precompiles can be loaded without asking the database.
+        let state_conflict = executed.state.keys().any(|address| {
+            self.accounts.contains(address)
+                && !executed.reads.iter().any(
+                    |query| matches!(query, DbQuery::Basic { address: read, .. } if read == address),
+                )
+        });
+        read_conflict || state_conflict
+    }
+
+    fn record(&mut self, executed: &Executed) {
+        for (address, account) in &executed.state {
+            let original = executed.reads.iter().find_map(|query| match query {
+                DbQuery::Basic {
+                    address: read,
+                    info,
+                } if read == address => Some(info),
+                _ => None,
+            });
+            if account.is_destroyed || account.storage_cleared {
+                self.accounts.insert(*address);
+                self.wiped_storage.insert(*address);
+            } else {
+                let changed = match original {
+                    Some(original) => {
+                        original.balance != account.info.balance
+                            || original.nonce != account.info.nonce
+                            || original.code_hash != account.info.code_hash
+                    }
+                    None => true,
+                };
+                if changed {
+                    self.accounts.insert(*address);
+                }
+            }
+            for (index, slot) in &account.storage {
+                if slot.is_changed() {
+                    self.slots.insert((*address, *index));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccountInfo, Bytecode, InMemoryDB, Return, TransactTo};
+    use bytes::Bytes;
+
+    #[test]
+    fn conflicting_transactions_are_reexecuted() {
+        let coinbase = H160::from_low_u64_be(0xc0);
+        let counter = H160::from_low_u64_be(0x42);
+        let callers: Vec<H160> = (0x1001..=0x1004).map(H160::from_low_u64_be).collect();
+
+        let mut db = InMemoryDB::default();
+        for caller in &callers {
+            db.insert_account_info(*caller, AccountInfo::from_balance(U256::from(10_000_000)));
+        }
+        // SSTORE(0, SLOAD(0) + 1)
+        let code = vec![0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00];
+        db.insert_account_info(
+            counter,
+            AccountInfo::from_code(Bytecode::new_raw(Bytes::from(code))),
+        );
+
+        let mut env = Env::default();
+        env.block.coinbase = coinbase;
+        let tx = |caller: H160, to: H160| TxEnv {
+            caller,
+            transact_to: TransactTo::Call(to),
+            gas_limit: 100_000,
+            gas_price: U256::one(),
+            value: U256::one(),
+            ..Default::default()
+        };
+        let txs = vec![
+            // two independent transfers.
+            tx(callers[0], H160::from_low_u64_be(0x100)),
+            tx(callers[1], H160::from_low_u64_be(0x101)),
+            // both increment the same counter.
+            tx(callers[2], counter),
+            tx(callers[3], counter),
+        ];
+
+        let batch = transact_parallel(&db, &env, txs.clone());
+        assert_eq!(batch.reexecuted, vec![3]);
+
+        // same as executing one by one.
+        let mut serial = crate::new();
+        serial.database(db.clone());
+        serial.env = env;
+        for (tx, result) in txs.into_iter().zip(&batch.results) {
+            serial.env.tx = tx;
+            let serial_result = serial.transact_commit();
+            assert_eq!(result.exit_reason, Return::Stop);
+            assert_eq!(result.exit_reason, serial_result.exit_reason);
+            assert_eq!(result.gas_used, serial_result.gas_used);
+        }
+        let serial = serial.db.unwrap();
+        assert_eq!(
+            DatabaseRef::storage(&batch.state, counter, U256::zero()),
+            U256::from(2)
+        );
+        for address in callers
+            .iter()
+            .chain([&coinbase, &counter])
+            .chain(&[H160::from_low_u64_be(0x100), H160::from_low_u64_be(0x101)])
+        {
+            assert_eq!(
+                DatabaseRef::basic(&batch.state, *address),
+                DatabaseRef::basic(&serial, *address)
+            );
+        }
+        assert!(!DatabaseRef::basic(&batch.state, coinbase).balance.is_zero());
+    }
+}
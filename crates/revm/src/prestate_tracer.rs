@@ -0,0 +1,228 @@
+use crate::{db::DbQuery, interpreter::bytecode::Bytecode, AccountInfo, State};
+use alloc::collections::BTreeMap;
+use bytes::Bytes;
+use primitive_types::{H160, H256, U256};
+
+/// Account as reported by [`PrestateTracer`], in the shape of Geth's `prestateTracer`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrestateAccount {
+    pub balance: U256,
+    pub nonce: u64,
+    #[cfg_attr(feature = "with-serde", serde(with = "crate::models::serde_hex_bytes"))]
+    pub code: Bytes,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// Result of [`PrestateTracer::trace`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrestateTrace {
+    /// Accounts and storage slots read by the transaction, with their values before it.
+    /// Accounts created by the transaction are not listed.
+    pub pre: BTreeMap<H160, PrestateAccount>,
+    /// Accounts changed by the transaction with their values after it. Only changed storage
+    /// slots are listed. Destroyed accounts are not listed, as in Geth's diff mode.
+    pub diff: BTreeMap<H160, PrestateAccount>,
+}
+
+/// Collects state read by a transaction, for Geth's `prestateTracer`.
+///
+/// Values before execution are taken from queries recorded by [`crate::db::RecorderDB`], see
+/// [`PrestateTracer::record_queries`], so only the first answer for every account and slot
+/// is kept. Changes come from the [`State`] returned by the transaction.
+#[derive(Clone, Debug, Default)]
+pub struct PrestateTracer {
+    accounts: BTreeMap<H160, AccountInfo>,
+    contracts: BTreeMap<H256, Bytecode>,
+    storage: BTreeMap<H160, BTreeMap<U256, U256>>,
+}
+
+impl PrestateTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record accounts, code and storage slots read from the database. Block hashes are not
+    /// state, so they are skipped.
+    pub fn record_queries<'a>(&mut self, queries: impl IntoIterator<Item = &'a DbQuery>) {
+        for query in queries {
+            match query {
+                DbQuery::Basic { address, info } => {
+                    self.accounts
+                        .entry(*address)
+                        .or_insert_with(|| info.clone());
+                }
+                DbQuery::CodeByHash { code_hash, code } => {
+                    self.contracts
+                        .entry(*code_hash)
+                        .or_insert_with(|| code.clone());
+                }
+                DbQuery::Storage {
+                    address,
+                    index,
+                    value,
+                } => {
+                    self.storage
+                        .entry(*address)
+                        .or_default()
+                        .entry(*index)
+                        .or_insert(*value);
+                }
+                DbQuery::BlockHash { .. } => (),
+            }
+        }
+    }
+
+    /// Pre state and diff of the transaction that changed `state`.
+    pub fn trace(&self, state: &State) -> PrestateTrace {
+        let mut trace = PrestateTrace::default();
+        for (address, info) in &self.accounts {
+            if state.get(address).is_some_and(|account| account.is_created) {
+                continue;
+            }
+            let code = match &info.code {
+                Some(code) => Bytes::copy_from_slice(code.original_bytes()),
+                None => self
+                    .contracts
+                    .get(&info.code_hash)
+                    .map(|code| Bytes::copy_from_slice(code.original_bytes()))
+                    .unwrap_or_default(),
+            };
+            trace.pre.insert(
+                *address,
+                PrestateAccount {
+                    balance: info.balance,
+                    nonce: info.nonce,
+                    code,
+                    storage: self.storage.get(address).cloned().unwrap_or_default(),
+                },
+            );
+        }
+
+        for (address, account) in state {
+            if account.is_destroyed {
+                continue;
+            }
+            let storage: BTreeMap<U256, U256> = account
+                .storage
+                .iter()
+                .filter(|(_, slot)| slot.present_value() != slot.original_value())
+                .map(|(index, slot)| (*index, slot.present_value()))
+                .collect();
+            let changed = match trace.pre.get(address) {
+                Some(pre) => {
+                    pre.balance != account.info.balance
+                        || pre.nonce != account.info.nonce
+                        || !storage.is_empty()
+                }
+                None => account.is_created,
+            };
+            if !changed {
+                continue;
+            }
+            let code = account
+                .info
+                .code
+                .as_ref()
+                .map(|code| Bytes::copy_from_slice(code.original_bytes()))
+                .or_else(|| trace.pre.get(address).map(|pre| pre.code.clone()))
+                .unwrap_or_default();
+            trace.diff.insert(
+                *address,
+                PrestateAccount {
+                    balance: account.info.balance,
+                    nonce: account.info.nonce,
+                    code,
+                    storage,
+                },
+            );
+        }
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::RecorderDB, opcode, BytecodeBuilder, ExecutionResult, InMemoryDB, Return, TransactTo,
+    };
+
+    const CALLER: H160 = H160([0x10; 20]);
+    const CONTRACT: H160 = H160([0x42; 20]);
+
+    fn traced(transact_to: TransactTo, data: Bytes) -> (ExecutionResult, PrestateTrace) {
+        // SLOAD slot 1, SSTORE 7 to slot 0
+        let code = BytecodeBuilder::new()
+            .push(1)
+            .op(opcode::SLOAD)
+            .op(opcode::POP)
+            .push(7)
+            .push(0)
+            .op(opcode::SSTORE)
+            .op(opcode::STOP)
+            .build();
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(CALLER, AccountInfo::from_balance(U256::from(1000)));
+        db.insert_account_info(CONTRACT, AccountInfo::from_code(code));
+        db.insert_account_storage(CONTRACT, U256::zero(), U256::from(5));
+        db.insert_account_storage(CONTRACT, U256::one(), U256::from(6));
+
+        let mut evm = crate::new();
+        evm.database(RecorderDB::new(db));
+        evm.env.tx.caller = CALLER;
+        evm.env.tx.transact_to = transact_to;
+        evm.env.tx.data = data;
+        evm.env.tx.gas_limit = 100_000;
+        let (result, state) = evm.transact();
+
+        let mut tracer = PrestateTracer::new();
+        tracer.record_queries(&evm.db().unwrap().journal);
+        (result, tracer.trace(&state))
+    }
+
+    #[test]
+    fn storage_write() {
+        let (result, trace) = traced(TransactTo::Call(CONTRACT), Bytes::new());
+        assert_eq!(result.exit_reason, Return::Stop);
+
+        let pre = &trace.pre[&CONTRACT];
+        assert_eq!(pre.nonce, 0);
+        assert!(!pre.code.is_empty());
+        assert_eq!(
+            pre.storage,
+            [(U256::zero(), U256::from(5)), (U256::one(), U256::from(6))].into()
+        );
+        assert_eq!(trace.pre[&CALLER].balance, U256::from(1000));
+        assert_eq!(trace.pre[&CALLER].nonce, 0);
+
+        // only the written slot is in the diff.
+        let diff = &trace.diff[&CONTRACT];
+        assert_eq!(diff.storage, [(U256::zero(), U256::from(7))].into());
+        assert_eq!(diff.code, pre.code);
+        assert_eq!(trace.diff[&CALLER].nonce, 1);
+    }
+
+    #[test]
+    fn created_account_only_in_diff() {
+        // init code: SSTORE 1 to slot 0, return empty code
+        let init_code = BytecodeBuilder::new()
+            .push(1)
+            .push(0)
+            .op(opcode::SSTORE)
+            .op(opcode::STOP)
+            .into_bytes();
+        let (result, trace) = traced(TransactTo::create(), init_code.into());
+        let created = crate::create_address(CALLER, 0);
+        assert_eq!(
+            result.create_result(),
+            Some(crate::CreateResult::Created(created))
+        );
+        assert!(!trace.pre.contains_key(&created));
+        let diff = &trace.diff[&created];
+        assert_eq!(diff.nonce, 1);
+        assert_eq!(diff.storage, [(U256::zero(), U256::one())].into());
+        assert!(trace.pre.contains_key(&CALLER));
+    }
+}
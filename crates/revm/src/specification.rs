@@ -19,7 +19,10 @@ pub enum SpecId {
     BERLIN = 10,
     LONDON = 11,
     MERGE = 12,
-    LATEST = 13,
+    SHANGHAI = 13,
+    CANCUN = 14,
+    PRAGUE = 15,
+    LATEST = 16,
 }
 
 impl SpecId {
@@ -28,7 +31,9 @@ impl SpecId {
             FRONTIER | HOMESTEAD | TANGERINE | SPURIOUS_DRAGON => PrecompileId::HOMESTEAD as u8,
             BYZANTIUM | CONSTANTINOPLE | PETERSBURG => PrecompileId::BYZANTIUM as u8,
             ISTANBUL | MUIRGLACIER => PrecompileId::ISTANBUL as u8,
-            BERLIN | LONDON | MERGE | LATEST => PrecompileId::BERLIN as u8,
+            BERLIN | LONDON | MERGE | SHANGHAI => PrecompileId::BERLIN as u8,
+            CANCUN => PrecompileId::CANCUN as u8,
+            PRAGUE | LATEST => PrecompileId::PRAGUE as u8,
         }
     }
 
@@ -54,6 +59,9 @@ impl From<&str> for SpecId {
             "Berlin" => SpecId::BERLIN,
             "London" => SpecId::LONDON,
             "Merge" => SpecId::MERGE,
+            "Shanghai" => SpecId::SHANGHAI,
+            "Cancun" => SpecId::CANCUN,
+            "Prague" => SpecId::PRAGUE,
             _ => SpecId::LATEST,
         }
     }
@@ -64,8 +72,64 @@ impl SpecId {
     pub const fn enabled(our: SpecId, other: SpecId) -> bool {
         our as u8 >= other as u8
     }
+
+    /// Hardfork that activated `eip`, `None` if it is not in [`EIP_ACTIVATIONS`].
+    pub fn eip_activation(eip: u16) -> Option<SpecId> {
+        EIP_ACTIVATIONS
+            .iter()
+            .find(|(number, _)| *number == eip)
+            .map(|(_, spec_id)| *spec_id)
+    }
+
+    /// Whether `eip` is enabled in this spec. Meant for code that picks the fork at runtime,
+    /// e.g. from chain config, execution itself checks forks with [`Spec::enabled`].
+    pub fn is_eip_enabled(self, eip: u16) -> bool {
+        Self::eip_activation(eip).is_some_and(|spec_id| SpecId::enabled(self, spec_id))
+    }
 }
 
+/// EIPs implemented by this crate and hardforks that activated them. EIPs outside of the EVM,
+/// like transaction signing or withdrawals, are not listed.
+pub const EIP_ACTIVATIONS: &[(u16, SpecId)] = &[
+    (2, HOMESTEAD),
+    (7, HOMESTEAD),
+    (150, TANGERINE),
+    (160, SPURIOUS_DRAGON),
+    (161, SPURIOUS_DRAGON),
+    (170, SPURIOUS_DRAGON),
+    (140, BYZANTIUM),
+    (196, BYZANTIUM),
+    (197, BYZANTIUM),
+    (198, BYZANTIUM),
+    (211, BYZANTIUM),
+    (214, BYZANTIUM),
+    (145, CONSTANTINOPLE),
+    (1014, CONSTANTINOPLE),
+    (1052, CONSTANTINOPLE),
+    (152, ISTANBUL),
+    (1108, ISTANBUL),
+    (1344, ISTANBUL),
+    (1884, ISTANBUL),
+    (2028, ISTANBUL),
+    (2200, ISTANBUL),
+    (2565, BERLIN),
+    (2929, BERLIN),
+    (2930, BERLIN),
+    (1559, LONDON),
+    (3198, LONDON),
+    (3529, LONDON),
+    (3541, LONDON),
+    (4399, MERGE),
+    (3651, SHANGHAI),
+    (3855, SHANGHAI),
+    (3860, SHANGHAI),
+    (4844, CANCUN),
+    (6780, CANCUN),
+    (7516, CANCUN),
+    (2537, PRAGUE),
+    (7702, PRAGUE),
+];
+
 pub(crate) trait NotStaticSpec {}
 
 pub trait Spec: Sized {
@@ -81,6 +145,10 @@ pub trait Spec: Sized {
     const IS_STATIC_CALL: bool;
 
     const ASSUME_PRECOMPILE_HAS_BALANCE: bool;
+
+    /// Whether analysis marks EIP-2315 subroutines, see [`crate::Bytecode::subroutine_boundaries`].
+    /// No hardfork enables it, it is meant for custom specs used in experiments.
+    const EIP2315: bool = false;
 }
 
 pub(crate) mod spec_impl {
@@ -120,16 +188,30 @@ pub(crate) mod spec_impl {
     }
 
     spec!(LATEST);
+    spec!(PRAGUE);
+    spec!(CANCUN);
+    spec!(SHANGHAI);
     spec!(MERGE);
     spec!(LONDON);
     spec!(BERLIN);
+    spec!(MUIRGLACIER);
     spec!(ISTANBUL);
+    spec!(PETERSBURG);
+    spec!(CONSTANTINOPLE);
     spec!(BYZANTIUM);
+    spec!(SPURIOUS_DRAGON);
+    spec!(TANGERINE);
+    spec!(HOMESTEAD);
     spec!(FRONTIER);
 }
 
 pub use spec_impl::{
     BERLIN::SpecImpl as BerlinSpec, BYZANTIUM::SpecImpl as ByzantiumSpec,
-    FRONTIER::SpecImpl as FrontierSpec, ISTANBUL::SpecImpl as IstanbulSpec,
-    LATEST::SpecImpl as LatestSpec, LONDON::SpecImpl as LondonSpec, MERGE::SpecImpl as MergeSpec,
+    CANCUN::SpecImpl as CancunSpec, CONSTANTINOPLE::SpecImpl as ConstantinopleSpec,
+    FRONTIER::SpecImpl as FrontierSpec, HOMESTEAD::SpecImpl as HomesteadSpec,
+    ISTANBUL::SpecImpl as IstanbulSpec, LATEST::SpecImpl as LatestSpec,
+    LONDON::SpecImpl as LondonSpec, MERGE::SpecImpl as MergeSpec,
+    MUIRGLACIER::SpecImpl as MuirGlacierSpec, PETERSBURG::SpecImpl as PetersburgSpec,
+    PRAGUE::SpecImpl as PragueSpec, SHANGHAI::SpecImpl as ShanghaiSpec,
+    SPURIOUS_DRAGON::SpecImpl as SpuriousDragonSpec, TANGERINE::SpecImpl as TangerineSpec,
 };
@@ -0,0 +1,89 @@
+use crate::{Database, JournaledState};
+use alloc::sync::Arc;
+use core::fmt;
+use primitive_types::{H160, U256};
+
+/// Hook invoked on SLOAD and SSTORE, for custom storage pricing or layouts, e.g. of L2s. Set
+/// with [`crate::CfgEnv::storage_hook`]. Default methods keep standard behavior.
+///
+/// Ordering relative to EIP-2929 warm/cold accounting:
+/// * [`StorageHook::sload`] runs after the slot is loaded and marked warm, `cost` already
+///   includes the cold surcharge.
+/// * [`StorageHook::sstore`] runs before the slot is loaded, marked warm and written, the
+///   returned value is what gets written and what gas is computed for.
+/// * [`StorageHook::sstore_cost`] runs after the write, with standard gas including the cold
+///   surcharge. The EIP-2200 stipend check happens before it and refunds are not changed.
+pub trait StorageHook: Send + Sync {
+    /// Returns value pushed to the stack and gas charged for SLOAD of `index` of `address`.
+    fn sload(
+        &self,
+        _storage: &mut HookStorage<'_>,
+        _address: H160,
+        _index: U256,
+        value: U256,
+        cost: u64,
+    ) -> (U256, u64) {
+        (value, cost)
+    }
+
+    /// Returns value that SSTORE writes to `index` of `address`.
+    fn sstore(
+        &self,
+        _storage: &mut HookStorage<'_>,
+        _address: H160,
+        _index: U256,
+        value: U256,
+    ) -> U256 {
+        value
+    }
+
+    /// Returns gas charged for SSTORE to `index` of `address`.
+    fn sstore_cost(&self, _address: H160, _index: U256, cost: u64) -> u64 {
+        cost
+    }
+}
+
+/// Shared [`StorageHook`], equal only to clones of itself.
+#[derive(Clone)]
+pub struct StorageHookRef(pub Arc<dyn StorageHook>);
+
+impl StorageHookRef {
+    pub fn new(hook: impl StorageHook + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+}
+
+impl fmt::Debug for StorageHookRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StorageHookRef")
+    }
+}
+
+impl PartialEq for StorageHookRef {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for StorageHookRef {}
+
+/// Storage of the running transaction given to [`StorageHook`]. Accesses go through the
+/// journal, so they are reverted together with the call, and warm the accounts and slots they
+/// touch without charging gas for it.
+pub struct HookStorage<'a> {
+    pub(crate) journaled_state: &'a mut JournaledState,
+    pub(crate) db: &'a mut dyn Database,
+}
+
+impl<'a> HookStorage<'a> {
+    pub fn sload(&mut self, address: H160, index: U256) -> U256 {
+        self.journaled_state.load_account(address, &mut self.db);
+        self.journaled_state.sload(address, index, &mut self.db).0
+    }
+
+    pub fn sstore(&mut self, address: H160, index: U256, value: U256) {
+        self.journaled_state.load_account(address, &mut self.db);
+        self.journaled_state
+            .sstore(address, index, value, &mut self.db);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::{db::DatabaseRef, gas, Env, TransactTo, KECCAK_EMPTY};
+use primitive_types::U256;
+
+/// Reason transaction can not be included in a block, returned by [`validate_env`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidTransaction {
+    /// Priority fee is greater than max fee (`gas_price`), EIP-1559.
+    PriorityFeeGreaterThanMaxFee,
+    /// Effective gas price is lower than block basefee, EIP-1559.
+    GasPriceLessThanBasefee,
+    /// Gas limit of transaction is more than gas limit of the block.
+    CallerGasLimitMoreThanBlock,
+    /// Init code of create transaction is over [`crate::CfgEnv::max_initcode_size`], EIP-3860.
+    CreateInitcodeSizeLimit,
+    /// Gas limit does not cover the intrinsic gas of the transaction.
+    IntrinsicGasTooLow { intrinsic_gas: u64, gas_limit: u64 },
+    /// Chain id of transaction is different from [`crate::CfgEnv::chain_id`].
+    InvalidChainId,
+    /// Sender has deployed code that is not a delegation designator, EIP-3607 and EIP-7702.
+    RejectCallerWithCode,
+    /// Transaction nonce is lower than nonce of the sender.
+    NonceTooLow { tx: u64, state: u64 },
+    /// Transaction nonce is higher than nonce of the sender.
+    NonceTooHigh { tx: u64, state: u64 },
+    /// `gas_limit * gas_price + value` overflows.
+    OverflowPayment,
+    /// Sender balance does not cover `gas_limit * gas_price + value`.
+    InsufficientFunds { cost: U256, balance: U256 },
+}
+
+/// Check transaction of `env` against `db` the way a node does before including it, without
+/// executing it. Nonce is checked only if set in [`crate::TxEnv::nonce`].
+///
+/// Unlike execution, which charges the effective gas price, the sender needs balance for
+/// `gas_price`, the max fee, so the transaction stays valid if basefee changes.
+pub fn validate_env<DB: DatabaseRef>(env: &Env, db: &DB) -> Result<(), InvalidTransaction> {
+    let tx = &env.tx;
+    let spec_id = env.cfg.spec_id;
+    // Optimism deposits are paid for on L1, fee checks do not apply to them.
+    let is_deposit = tx.is_deposit();
+
+    if spec_id.is_eip_enabled(1559) && !is_deposit {
+        if tx.gas_priority_fee.is_some_and(|fee| fee > tx.gas_price) {
+            return Err(InvalidTransaction::PriorityFeeGreaterThanMaxFee);
+        }
+        if env.effective_gas_price() < env.block.basefee {
+            return Err(InvalidTransaction::GasPriceLessThanBasefee);
+        }
+    }
+
+    if U256::from(tx.gas_limit) > env.block.gas_limit {
+        return Err(InvalidTransaction::CallerGasLimitMoreThanBlock);
+    }
+    let is_create = matches!(tx.transact_to, TransactTo::Create(_));
+    if spec_id.is_eip_enabled(3860) && is_create && tx.data.len() > env.cfg.max_initcode_size() {
+        return Err(InvalidTransaction::CreateInitcodeSizeLimit);
+    }
+    let intrinsic_gas = gas::initial_tx_gas_for(
+        spec_id,
+        &tx.data,
+        is_create,
+        &tx.access_list,
+        tx.authorization_list.len(),
+    );
+    if intrinsic_gas > tx.gas_limit {
+        return Err(InvalidTransaction::IntrinsicGasTooLow {
+            intrinsic_gas,
+            gas_limit: tx.gas_limit,
+        });
+    }
+
+    if tx
+        .chain_id
+        .is_some_and(|chain_id| U256::from(chain_id) != env.cfg.chain_id)
+    {
+        return Err(InvalidTransaction::InvalidChainId);
+    }
+
+    let caller = db.basic(tx.caller);
+    if !is_deposit && caller.code_hash != KECCAK_EMPTY && !caller.code_hash.is_zero() {
+        // EIP-7702: senders that delegate their code are allowed.
+        let code = match &caller.code {
+            Some(code) => code.clone(),
+            None => db.code_by_hash(caller.code_hash),
+        };
+        if code.delegation_address().is_none() {
+            return Err(InvalidTransaction::RejectCallerWithCode);
+        }
+    }
+    if let Some(nonce) = tx.nonce {
+        if nonce < caller.nonce {
+            return Err(InvalidTransaction::NonceTooLow {
+                tx: nonce,
+                state: caller.nonce,
+            });
+        }
+        if nonce > caller.nonce {
+            return Err(InvalidTransaction::NonceTooHigh {
+                tx: nonce,
+                state: caller.nonce,
+            });
+        }
+    }
+
+    let gas_cost = if is_deposit {
+        Some(U256::zero())
+    } else {
+        U256::from(tx.gas_limit).checked_mul(tx.gas_price)
+    };
+    let cost = gas_cost
+        .and_then(|cost| cost.checked_add(tx.value))
+        .ok_or(InvalidTransaction::OverflowPayment)?;
+    if cost > caller.balance {
+        return Err(InvalidTransaction::InsufficientFunds {
+            cost,
+            balance: caller.balance,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccountInfo, Bytecode, InMemoryDB, SpecId};
+    use primitive_types::H160;
+
+    const CALLER: H160 = H160([0x10; 20]);
+
+    fn valid() -> (Env, InMemoryDB) {
+        let mut env = Env::default();
+        env.cfg.spec_id = SpecId::LONDON;
+        env.block.basefee = U256::from(10);
+        env.tx.caller = CALLER;
+        env.tx.transact_to = TransactTo::Call(H160::from_low_u64_be(0x42));
+        env.tx.gas_limit = 21_000;
+        env.tx.gas_price = U256::from(20);
+        env.tx.gas_priority_fee = Some(U256::from(2));
+        env.tx.value = U256::from(100);
+        env.tx.nonce = Some(3);
+
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(
+            CALLER,
+            AccountInfo::new(U256::from(21_000 * 20 + 100), 3, Bytecode::new()),
+        );
+        (env, db)
+    }
+
+    fn validate(change: impl FnOnce(&mut Env, &mut InMemoryDB)) -> Result<(), InvalidTransaction> {
+        let (mut env, mut db) = valid();
+        change(&mut env, &mut db);
+        validate_env(&env, &db)
+    }
+
+    #[test]
+    fn valid_transaction() {
+        assert_eq!(validate(|_, _| ()), Ok(()));
+        // nonce is not checked when not set.
+        assert_eq!(validate(|env, _| env.tx.nonce = None), Ok(()));
+    }
+
+    #[test]
+    fn priority_fee_greater_than_max_fee() {
+        assert_eq!(
+            validate(|env, _| env.tx.gas_priority_fee = Some(U256::from(21))),
+            Err(InvalidTransaction::PriorityFeeGreaterThanMaxFee)
+        );
+    }
+
+    #[test]
+    fn gas_price_less_than_basefee() {
+        assert_eq!(
+            validate(|env, _| env.block.basefee = U256::from(21)),
+            Err(InvalidTransaction::GasPriceLessThanBasefee)
+        );
+        // basefee does not exist before London.
+        assert_eq!(
+            validate(|env, _| {
+                env.cfg.spec_id = SpecId::BERLIN;
+                env.block.basefee = U256::from(21);
+            }),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn gas_limit_more_than_block() {
+        assert_eq!(
+            validate(|env, _| env.block.gas_limit = U256::from(20_999)),
+            Err(InvalidTransaction::CallerGasLimitMoreThanBlock)
+        );
+    }
+
+    #[test]
+    fn intrinsic_gas_too_low() {
+        assert_eq!(
+            validate(|env, _| env.tx.data = vec![1].into()),
+            Err(InvalidTransaction::IntrinsicGasTooLow {
+                intrinsic_gas: 21_016,
+                gas_limit: 21_000
+            })
+        );
+    }
+
+    #[test]
+    fn initcode_size_limit() {
+        let oversized = |env: &mut Env| {
+            env.tx.transact_to = TransactTo::create();
+            env.tx.data = vec![0; env.cfg.max_initcode_size() + 1].into();
+        };
+        assert_eq!(
+            validate(|env, _| {
+                env.cfg.spec_id = SpecId::SHANGHAI;
+                oversized(env);
+            }),
+            Err(InvalidTransaction::CreateInitcodeSizeLimit)
+        );
+        // init code is not limited before Shanghai.
+        assert!(matches!(
+            validate(|env, _| oversized(env)),
+            Err(InvalidTransaction::IntrinsicGasTooLow { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_chain_id() {
+        assert_eq!(validate(|env, _| env.tx.chain_id = Some(1)), Ok(()));
+        assert_eq!(
+            validate(|env, _| env.tx.chain_id = Some(2)),
+            Err(InvalidTransaction::InvalidChainId)
+        );
+    }
+
+    #[test]
+    fn caller_with_code() {
+        assert_eq!(
+            validate(|_, db| {
+                let mut info = AccountInfo::from_code(Bytecode::new_raw(vec![0x00].into()));
+                info.balance = U256::MAX;
+                info.nonce = 3;
+                db.insert_account_info(CALLER, info);
+            }),
+            Err(InvalidTransaction::RejectCallerWithCode)
+        );
+        // EIP-7702 delegated code.
+        assert_eq!(
+            validate(|_, db| {
+                let code = Bytecode::new_delegation(H160::from_low_u64_be(0x42));
+                let mut info = AccountInfo::from_code(code);
+                info.balance = U256::MAX;
+                info.nonce = 3;
+                db.insert_account_info(CALLER, info);
+            }),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn nonce_mismatch() {
+        assert_eq!(
+            validate(|env, _| env.tx.nonce = Some(2)),
+            Err(InvalidTransaction::NonceTooLow { tx: 2, state: 3 })
+        );
+        assert_eq!(
+            validate(|env, _| env.tx.nonce = Some(4)),
+            Err(InvalidTransaction::NonceTooHigh { tx: 4, state: 3 })
+        );
+    }
+
+    #[test]
+    fn insufficient_funds() {
+        assert_eq!(
+            validate(|env, _| env.tx.value = U256::from(101)),
+            Err(InvalidTransaction::InsufficientFunds {
+                cost: U256::from(21_000 * 20 + 101),
+                balance: U256::from(21_000 * 20 + 100),
+            })
+        );
+        assert_eq!(
+            validate(|env, _| env.tx.value = U256::MAX),
+            Err(InvalidTransaction::OverflowPayment)
+        );
+    }
+}
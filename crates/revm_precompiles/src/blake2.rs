@@ -1,6 +1,6 @@
 use crate::{gas_query, Return, StandardPrecompileFn};
 
-use crate::{Precompile, PrecompileOutput, PrecompileResult};
+use crate::{Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult};
 use alloc::borrow::Cow;
 use core::convert::TryInto;
 use primitive_types::H160 as Address;
@@ -13,6 +13,16 @@ pub const FUN: (Address, Precompile) = (
     Precompile::Standard(run as StandardPrecompileFn),
 );
 
+pub const GAS: (Address, PrecompileGasFn) = (super::make_address(0, 9), gas);
+
+/// Cost of the rounds, zero for input of invalid length as it fails without compression.
+fn gas(input: &[u8]) -> u64 {
+    if input.len() != INPUT_LENGTH {
+        return 0;
+    }
+    u32::from_be_bytes(input[..4].try_into().unwrap()) as u64 * F_ROUND
+}
+
 /// reference: https://eips.ethereum.org/EIPS/eip-152
 /// input format:
 /// [4 bytes for rounds][64 bytes for h][128 bytes for m][8 bytes for t_0][8 bytes for t_1][1 byte for f]
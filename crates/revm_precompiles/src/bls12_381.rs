@@ -0,0 +1,972 @@
+//! EIP-2537: Precompile for BLS12-381 curve operations.
+//!
+//! G1/G2 scalar multiplication is served by the MSM precompiles with a single
+//! (point, scalar) pair, as in the final version of the EIP.
+use crate::{gas_query, Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult, Return};
+
+use alloc::{borrow::Cow, vec::Vec};
+use blst::{
+    blst_bendian_from_fp, blst_final_exp, blst_fp, blst_fp12, blst_fp12_is_one, blst_fp12_mul,
+    blst_fp2, blst_fp_from_bendian, blst_map_to_g1, blst_map_to_g2, blst_miller_loop, blst_p1,
+    blst_p1_add_or_double_affine, blst_p1_affine, blst_p1_affine_in_g1, blst_p1_affine_on_curve,
+    blst_p1_from_affine, blst_p1_mult, blst_p1_to_affine, blst_p2, blst_p2_add_or_double_affine,
+    blst_p2_affine, blst_p2_affine_in_g2, blst_p2_affine_on_curve, blst_p2_from_affine,
+    blst_p2_mult, blst_p2_to_affine, blst_scalar, blst_scalar_from_bendian,
+};
+use primitive_types::H160 as Address;
+
+pub mod g1_add {
+    use super::*;
+    const ADDRESS: Address = crate::make_address(0, 0x0b);
+    const BASE_GAS_FEE: u64 = 375;
+
+    pub const PRAGUE: (Address, Precompile) = (
+        ADDRESS,
+        Precompile::Standard(|input: &[u8], target_gas: u64| -> PrecompileResult {
+            super::run_g1_add(input, BASE_GAS_FEE, target_gas)
+        }),
+    );
+    pub const PRAGUE_GAS: (Address, PrecompileGasFn) = (ADDRESS, |_: &[u8]| BASE_GAS_FEE);
+}
+
+pub mod g1_msm {
+    use super::*;
+    const ADDRESS: Address = crate::make_address(0, 0x0c);
+    const BASE_GAS_FEE: u64 = 12_000;
+
+    pub const PRAGUE: (Address, Precompile) = (
+        ADDRESS,
+        Precompile::Standard(|input: &[u8], target_gas: u64| -> PrecompileResult {
+            super::run_g1_msm(input, BASE_GAS_FEE, target_gas)
+        }),
+    );
+    pub const PRAGUE_GAS: (Address, PrecompileGasFn) = (ADDRESS, |input: &[u8]| {
+        super::msm_cost(
+            input.len() / super::G1_MSM_PAIR_LEN,
+            BASE_GAS_FEE,
+            &super::G1_MSM_DISCOUNT_TABLE,
+        )
+    });
+}
+
+pub mod g2_add {
+    use super::*;
+    const ADDRESS: Address = crate::make_address(0, 0x0d);
+    const BASE_GAS_FEE: u64 = 600;
+
+    pub const PRAGUE: (Address, Precompile) = (
+        ADDRESS,
+        Precompile::Standard(|input: &[u8], target_gas: u64| -> PrecompileResult {
+            super::run_g2_add(input, BASE_GAS_FEE, target_gas)
+        }),
+    );
+    pub const PRAGUE_GAS: (Address, PrecompileGasFn) = (ADDRESS, |_: &[u8]| BASE_GAS_FEE);
+}
+
+pub mod g2_msm {
+    use super::*;
+    const ADDRESS: Address = crate::make_address(0, 0x0e);
+    const BASE_GAS_FEE: u64 = 22_500;
+
+    pub const PRAGUE: (Address, Precompile) = (
+        ADDRESS,
+        Precompile::Standard(|input: &[u8], target_gas: u64| -> PrecompileResult {
+            super::run_g2_msm(input, BASE_GAS_FEE, target_gas)
+        }),
+    );
+    pub const PRAGUE_GAS: (Address, PrecompileGasFn) = (ADDRESS, |input: &[u8]| {
+        super::msm_cost(
+            input.len() / super::G2_MSM_PAIR_LEN,
+            BASE_GAS_FEE,
+            &super::G2_MSM_DISCOUNT_TABLE,
+        )
+    });
+}
+
+pub mod pairing {
+    use super::*;
+    const ADDRESS: Address = crate::make_address(0, 0x0f);
+    const PAIRING_PER_PAIR: u64 = 32_600;
+    const PAIRING_BASE: u64 = 37_700;
+
+    pub const PRAGUE: (Address, Precompile) = (
+        ADDRESS,
+        Precompile::Standard(|input: &[u8], target_gas: u64| -> PrecompileResult {
+            super::run_pairing(input, PAIRING_PER_PAIR, PAIRING_BASE, target_gas)
+        }),
+    );
+    pub const PRAGUE_GAS: (Address, PrecompileGasFn) = (ADDRESS, |input: &[u8]| {
+        PAIRING_PER_PAIR * (input.len() / super::PAIRING_PAIR_LEN) as u64 + PAIRING_BASE
+    });
+}
+
+pub mod map_fp_to_g1 {
+    use super::*;
+    const ADDRESS: Address = crate::make_address(0, 0x10);
+    const BASE_GAS_FEE: u64 = 5_500;
+
+    pub const PRAGUE: (Address, Precompile) = (
+        ADDRESS,
+        Precompile::Standard(|input: &[u8], target_gas: u64| -> PrecompileResult {
+            super::run_map_fp_to_g1(input, BASE_GAS_FEE, target_gas)
+        }),
+    );
+    pub const PRAGUE_GAS: (Address, PrecompileGasFn) = (ADDRESS, |_: &[u8]| BASE_GAS_FEE);
+}
+
+pub mod map_fp2_to_g2 {
+    use super::*;
+    const ADDRESS: Address = crate::make_address(0, 0x11);
+    const BASE_GAS_FEE: u64 = 23_800;
+
+    pub const PRAGUE: (Address, Precompile) = (
+        ADDRESS,
+        Precompile::Standard(|input: &[u8], target_gas: u64| -> PrecompileResult {
+            super::run_map_fp2_to_g2(input, BASE_GAS_FEE, target_gas)
+        }),
+    );
+    pub const PRAGUE_GAS: (Address, PrecompileGasFn) = (ADDRESS, |_: &[u8]| BASE_GAS_FEE);
+}
+
+/// Length of a field element as encoded in the ABI (padded to 64 bytes).
+const PADDED_FP_LEN: usize = 64;
+/// Length of a field element in its canonical big-endian form.
+const FP_LEN: usize = 48;
+/// Number of leading zero bytes in an encoded field element.
+const FP_PAD_LEN: usize = PADDED_FP_LEN - FP_LEN;
+/// Length of an encoded Fp2 element.
+const PADDED_FP2_LEN: usize = 2 * PADDED_FP_LEN;
+/// Length of an encoded G1 point.
+const G1_LEN: usize = 2 * PADDED_FP_LEN;
+/// Length of an encoded G2 point.
+const G2_LEN: usize = 2 * PADDED_FP2_LEN;
+/// Length of an encoded scalar.
+const SCALAR_LEN: usize = 32;
+/// Length of a single (point, scalar) pair for the G1 MSM.
+const G1_MSM_PAIR_LEN: usize = G1_LEN + SCALAR_LEN;
+/// Length of a single (point, scalar) pair for the G2 MSM.
+const G2_MSM_PAIR_LEN: usize = G2_LEN + SCALAR_LEN;
+/// Length of a single (G1, G2) pair for the pairing check.
+const PAIRING_PAIR_LEN: usize = G1_LEN + G2_LEN;
+
+/// Multiplier the discount tables are expressed in.
+const MSM_MULTIPLIER: u64 = 1000;
+
+/// Discounts for the G1 MSM, indexed by `k - 1` and capped at the last entry.
+const G1_MSM_DISCOUNT_TABLE: [u16; 128] = [
+    1000, 949, 848, 797, 764, 750, 738, 728, 719, 712, 705, 698, 692, 687, 682, 677, 673, 669, 665,
+    661, 658, 654, 651, 648, 645, 642, 640, 637, 635, 632, 630, 627, 625, 623, 621, 619, 617, 615,
+    613, 611, 609, 608, 606, 604, 603, 601, 599, 598, 596, 595, 593, 592, 591, 589, 588, 586, 585,
+    584, 582, 581, 580, 579, 577, 576, 575, 574, 573, 572, 570, 569, 568, 567, 566, 565, 564, 563,
+    562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 551, 550, 549, 548, 547, 547, 546, 545,
+    544, 543, 542, 541, 540, 540, 539, 538, 537, 536, 536, 535, 534, 533, 532, 532, 531, 530, 529,
+    528, 528, 527, 526, 525, 525, 524, 523, 522, 522, 521, 520, 520, 519,
+];
+
+/// Discounts for the G2 MSM, indexed by `k - 1` and capped at the last entry.
+const G2_MSM_DISCOUNT_TABLE: [u16; 128] = [
+    1000, 1000, 923, 884, 855, 832, 812, 796, 782, 770, 759, 749, 740, 732, 724, 717, 711, 704,
+    699, 693, 688, 683, 679, 674, 670, 666, 663, 659, 655, 652, 649, 646, 643, 640, 637, 634, 632,
+    629, 627, 624, 622, 620, 618, 615, 613, 611, 609, 607, 606, 604, 602, 600, 598, 597, 595, 593,
+    592, 590, 589, 587, 586, 584, 583, 582, 580, 579, 578, 576, 575, 574, 573, 571, 570, 569, 568,
+    567, 566, 565, 563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 552, 551, 550, 549,
+    548, 547, 546, 545, 545, 544, 543, 542, 541, 541, 540, 539, 538, 537, 537, 536, 535, 535, 534,
+    533, 532, 532, 531, 530, 530, 529, 528, 528, 527, 526, 526, 525, 524, 524,
+];
+
+/// Gas cost of an MSM over `k` pairs: `k * base * discount(k) / 1000`.
+fn msm_cost(k: usize, base: u64, discount_table: &[u16; 128]) -> u64 {
+    if k == 0 {
+        return 0;
+    }
+    let discount = discount_table[k.min(discount_table.len()) - 1] as u64;
+    (k as u64).saturating_mul(base).saturating_mul(discount) / MSM_MULTIPLIER
+}
+
+fn err(reason: &'static str) -> Return {
+    Return::Other(Cow::Borrowed(reason))
+}
+
+/// Decodes a 64 byte padded field element. The top 16 bytes must be zero and the
+/// remaining 48 bytes must be a canonical encoding (less than the modulus).
+fn read_fp(input: &[u8]) -> Result<blst_fp, Return> {
+    if input[..FP_PAD_LEN].iter().any(|b| *b != 0) {
+        return Err(err("ERR_BLS12_FP_PADDING"));
+    }
+    let bytes = &input[FP_PAD_LEN..PADDED_FP_LEN];
+    let mut fp = blst_fp::default();
+    let mut canonical = [0u8; FP_LEN];
+    // SAFETY: `bytes` and `canonical` are both exactly 48 bytes long.
+    unsafe {
+        blst_fp_from_bendian(&mut fp, bytes.as_ptr());
+        blst_bendian_from_fp(canonical.as_mut_ptr(), &fp);
+    }
+    if canonical[..] != bytes[..] {
+        return Err(err("ERR_BLS12_FP_NOT_CANONICAL"));
+    }
+    Ok(fp)
+}
+
+fn read_fp2(input: &[u8]) -> Result<blst_fp2, Return> {
+    Ok(blst_fp2 {
+        fp: [
+            read_fp(&input[..PADDED_FP_LEN])?,
+            read_fp(&input[PADDED_FP_LEN..PADDED_FP2_LEN])?,
+        ],
+    })
+}
+
+fn write_fp(out: &mut Vec<u8>, fp: &blst_fp) {
+    let mut buf = [0u8; PADDED_FP_LEN];
+    // SAFETY: the destination has at least 48 bytes after the padding.
+    unsafe { blst_bendian_from_fp(buf[FP_PAD_LEN..].as_mut_ptr(), fp) };
+    out.extend_from_slice(&buf);
+}
+
+/// Decodes a G1 point. The point at infinity is encoded as all zeroes. Subgroup
+/// membership is only checked when `subgroup_check` is set.
+fn read_g1(input: &[u8], subgroup_check: bool) -> Result<blst_p1_affine, Return> {
+    let point = blst_p1_affine {
+        x: read_fp(&input[..PADDED_FP_LEN])?,
+        y: read_fp(&input[PADDED_FP_LEN..G1_LEN])?,
+    };
+    if input[..G1_LEN].iter().all(|b| *b == 0) {
+        return Ok(point);
+    }
+    // SAFETY: `point` is a valid reference.
+    unsafe {
+        if !blst_p1_affine_on_curve(&point) {
+            return Err(err("ERR_BLS12_G1_NOT_ON_CURVE"));
+        }
+        if subgroup_check && !blst_p1_affine_in_g1(&point) {
+            return Err(err("ERR_BLS12_G1_NOT_IN_SUBGROUP"));
+        }
+    }
+    Ok(point)
+}
+
+/// Decodes a G2 point, see [`read_g1`].
+fn read_g2(input: &[u8], subgroup_check: bool) -> Result<blst_p2_affine, Return> {
+    let point = blst_p2_affine {
+        x: read_fp2(&input[..PADDED_FP2_LEN])?,
+        y: read_fp2(&input[PADDED_FP2_LEN..G2_LEN])?,
+    };
+    if input[..G2_LEN].iter().all(|b| *b == 0) {
+        return Ok(point);
+    }
+    // SAFETY: `point` is a valid reference.
+    unsafe {
+        if !blst_p2_affine_on_curve(&point) {
+            return Err(err("ERR_BLS12_G2_NOT_ON_CURVE"));
+        }
+        if subgroup_check && !blst_p2_affine_in_g2(&point) {
+            return Err(err("ERR_BLS12_G2_NOT_IN_SUBGROUP"));
+        }
+    }
+    Ok(point)
+}
+
+fn encode_g1(point: &blst_p1) -> Vec<u8> {
+    let mut affine = blst_p1_affine::default();
+    // SAFETY: both references are valid.
+    unsafe { blst_p1_to_affine(&mut affine, point) };
+    let mut out = Vec::with_capacity(G1_LEN);
+    write_fp(&mut out, &affine.x);
+    write_fp(&mut out, &affine.y);
+    out
+}
+
+fn encode_g2(point: &blst_p2) -> Vec<u8> {
+    let mut affine = blst_p2_affine::default();
+    // SAFETY: both references are valid.
+    unsafe { blst_p2_to_affine(&mut affine, point) };
+    let mut out = Vec::with_capacity(G2_LEN);
+    write_fp(&mut out, &affine.x.fp[0]);
+    write_fp(&mut out, &affine.x.fp[1]);
+    write_fp(&mut out, &affine.y.fp[0]);
+    write_fp(&mut out, &affine.y.fp[1]);
+    out
+}
+
+/// Scalars are not reduced and are interpreted as 256 bit big-endian integers.
+fn read_scalar(input: &[u8]) -> blst_scalar {
+    let mut scalar = blst_scalar::default();
+    // SAFETY: `input` is at least 32 bytes long.
+    unsafe { blst_scalar_from_bendian(&mut scalar, input[..SCALAR_LEN].as_ptr()) };
+    scalar
+}
+
+fn run_g1_add(input: &[u8], cost: u64, target_gas: u64) -> PrecompileResult {
+    let cost = gas_query(cost, target_gas)?;
+    if input.len() != 2 * G1_LEN {
+        return Err(err("ERR_BLS12_G1ADD_INPUT_LEN"));
+    }
+
+    let a = read_g1(&input[..G1_LEN], false)?;
+    let b = read_g1(&input[G1_LEN..], false)?;
+
+    let mut a_jacobian = blst_p1::default();
+    let mut out = blst_p1::default();
+    // SAFETY: all references are valid.
+    unsafe {
+        blst_p1_from_affine(&mut a_jacobian, &a);
+        blst_p1_add_or_double_affine(&mut out, &a_jacobian, &b);
+    }
+    Ok(PrecompileOutput::without_logs(cost, encode_g1(&out)))
+}
+
+fn run_g2_add(input: &[u8], cost: u64, target_gas: u64) -> PrecompileResult {
+    let cost = gas_query(cost, target_gas)?;
+    if input.len() != 2 * G2_LEN {
+        return Err(err("ERR_BLS12_G2ADD_INPUT_LEN"));
+    }
+
+    let a = read_g2(&input[..G2_LEN], false)?;
+    let b = read_g2(&input[G2_LEN..], false)?;
+
+    let mut a_jacobian = blst_p2::default();
+    let mut out = blst_p2::default();
+    // SAFETY: all references are valid.
+    unsafe {
+        blst_p2_from_affine(&mut a_jacobian, &a);
+        blst_p2_add_or_double_affine(&mut out, &a_jacobian, &b);
+    }
+    Ok(PrecompileOutput::without_logs(cost, encode_g2(&out)))
+}
+
+fn run_g1_msm(input: &[u8], base: u64, target_gas: u64) -> PrecompileResult {
+    let k = input.len() / G1_MSM_PAIR_LEN;
+    if k == 0 || input.len() != k * G1_MSM_PAIR_LEN {
+        return Err(err("ERR_BLS12_G1MSM_INPUT_LEN"));
+    }
+    let cost = gas_query(msm_cost(k, base, &G1_MSM_DISCOUNT_TABLE), target_gas)?;
+
+    let mut acc = blst_p1::default();
+    for pair in input.chunks_exact(G1_MSM_PAIR_LEN) {
+        let point = read_g1(&pair[..G1_LEN], true)?;
+        let scalar = read_scalar(&pair[G1_LEN..]);
+
+        let mut jacobian = blst_p1::default();
+        let mut mul = blst_p1::default();
+        let mut mul_affine = blst_p1_affine::default();
+        let mut sum = blst_p1::default();
+        // SAFETY: all references are valid and the scalar holds 256 bits.
+        unsafe {
+            blst_p1_from_affine(&mut jacobian, &point);
+            blst_p1_mult(&mut mul, &jacobian, scalar.b.as_ptr(), 256);
+            blst_p1_to_affine(&mut mul_affine, &mul);
+            blst_p1_add_or_double_affine(&mut sum, &acc, &mul_affine);
+        }
+        acc = sum;
+    }
+    Ok(PrecompileOutput::without_logs(cost, encode_g1(&acc)))
+}
+
+fn run_g2_msm(input: &[u8], base: u64, target_gas: u64) -> PrecompileResult {
+    let k = input.len() / G2_MSM_PAIR_LEN;
+    if k == 0 || input.len() != k * G2_MSM_PAIR_LEN {
+        return Err(err("ERR_BLS12_G2MSM_INPUT_LEN"));
+    }
+    let cost = gas_query(msm_cost(k, base, &G2_MSM_DISCOUNT_TABLE), target_gas)?;
+
+    let mut acc = blst_p2::default();
+    for pair in input.chunks_exact(G2_MSM_PAIR_LEN) {
+        let point = read_g2(&pair[..G2_LEN], true)?;
+        let scalar = read_scalar(&pair[G2_LEN..]);
+
+        let mut jacobian = blst_p2::default();
+        let mut mul = blst_p2::default();
+        let mut mul_affine = blst_p2_affine::default();
+        let mut sum = blst_p2::default();
+        // SAFETY: all references are valid and the scalar holds 256 bits.
+        unsafe {
+            blst_p2_from_affine(&mut jacobian, &point);
+            blst_p2_mult(&mut mul, &jacobian, scalar.b.as_ptr(), 256);
+            blst_p2_to_affine(&mut mul_affine, &mul);
+            blst_p2_add_or_double_affine(&mut sum, &acc, &mul_affine);
+        }
+        acc = sum;
+    }
+    Ok(PrecompileOutput::without_logs(cost, encode_g2(&acc)))
+}
+
+fn run_pairing(
+    input: &[u8],
+    pair_per_point_cost: u64,
+    pair_base_cost: u64,
+    target_gas: u64,
+) -> PrecompileResult {
+    // Unlike alt_bn128, an empty input is invalid here: at least one pair is required.
+    let k = input.len() / PAIRING_PAIR_LEN;
+    if k == 0 || input.len() != k * PAIRING_PAIR_LEN {
+        return Err(err("ERR_BLS12_PAIRING_INPUT_LEN"));
+    }
+    let cost = gas_query(pair_per_point_cost * k as u64 + pair_base_cost, target_gas)?;
+
+    let mut acc: Option<blst_fp12> = None;
+    for pair in input.chunks_exact(PAIRING_PAIR_LEN) {
+        let g1_bytes = &pair[..G1_LEN];
+        let g2_bytes = &pair[G1_LEN..];
+        let g1 = read_g1(g1_bytes, true)?;
+        let g2 = read_g2(g2_bytes, true)?;
+
+        // pairs containing the point at infinity contribute the identity.
+        if g1_bytes.iter().all(|b| *b == 0) || g2_bytes.iter().all(|b| *b == 0) {
+            continue;
+        }
+
+        let mut miller = blst_fp12::default();
+        // SAFETY: all references are valid.
+        unsafe { blst_miller_loop(&mut miller, &g2, &g1) };
+        acc = Some(match acc {
+            Some(acc) => {
+                let mut product = blst_fp12::default();
+                // SAFETY: all references are valid.
+                unsafe { blst_fp12_mul(&mut product, &acc, &miller) };
+                product
+            }
+            None => miller,
+        });
+    }
+
+    let success = match acc {
+        Some(acc) => {
+            let mut result = blst_fp12::default();
+            // SAFETY: all references are valid.
+            unsafe {
+                blst_final_exp(&mut result, &acc);
+                blst_fp12_is_one(&result)
+            }
+        }
+        None => true,
+    };
+
+    let mut out = vec![0u8; 32];
+    out[31] = success as u8;
+    Ok(PrecompileOutput::without_logs(cost, out))
+}
+
+fn run_map_fp_to_g1(input: &[u8], cost: u64, target_gas: u64) -> PrecompileResult {
+    let cost = gas_query(cost, target_gas)?;
+    if input.len() != PADDED_FP_LEN {
+        return Err(err("ERR_BLS12_MAP_FP_TO_G1_INPUT_LEN"));
+    }
+
+    let fp = read_fp(input)?;
+    let mut out = blst_p1::default();
+    // SAFETY: all references are valid, a null `v` selects the single element variant.
+    unsafe { blst_map_to_g1(&mut out, &fp, core::ptr::null()) };
+    Ok(PrecompileOutput::without_logs(cost, encode_g1(&out)))
+}
+
+fn run_map_fp2_to_g2(input: &[u8], cost: u64, target_gas: u64) -> PrecompileResult {
+    let cost = gas_query(cost, target_gas)?;
+    if input.len() != PADDED_FP2_LEN {
+        return Err(err("ERR_BLS12_MAP_FP2_TO_G2_INPUT_LEN"));
+    }
+
+    let fp2 = read_fp2(input)?;
+    let mut out = blst_p2::default();
+    // SAFETY: all references are valid, a null `v` selects the single element variant.
+    unsafe { blst_map_to_g2(&mut out, &fp2, core::ptr::null()) };
+    Ok(PrecompileOutput::without_logs(cost, encode_g2(&out)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const G1_GENERATOR: &str = "\
+        0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb\
+        0000000000000000000000000000000008b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1";
+    const G1_GENERATOR_NEG: &str = "\
+        0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb\
+        00000000000000000000000000000000114d1d6855d545a8aa7d76c8cf2e21f267816aef1db507c96655b9d5caac42364e6f38ba0ecb751bad54dcd6b939c2ca";
+    const G1_GENERATOR_DOUBLE: &str = "\
+        000000000000000000000000000000000572cbea904d67468808c8eb50a9450c9721db309128012543902d0ac358a62ae28f75bb8f1c7c42c39a8c5529bf0f4e\
+        00000000000000000000000000000000166a9d8cabc673a322fda673779d8e3822ba3ecb8670e461f73bb9021d5fd76a4c56d9d4cd16bd1bba86881979749d28";
+    const G2_GENERATOR: &str = "\
+        00000000000000000000000000000000024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8\
+        0000000000000000000000000000000013e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e\
+        000000000000000000000000000000000ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801\
+        000000000000000000000000000000000606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be";
+
+    // Reference vectors for EIP-2537. Expected points are computed with an implementation
+    // independent of blst, hash to curve outputs are taken from RFC 9380.
+    //
+    // points on the curve that are not in the prime order subgroup.
+    const G1_WRONG_ORDER: &str = "\
+        00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000004\
+        000000000000000000000000000000000a989badd40d6212b33cffc3f3763e9bc760f988c9926b26da9dd85e928483446346b8ed00e1de5d5ea93e354abe706c";
+    const G2_WRONG_ORDER: &str = "\
+        00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000002\
+        00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+        00000000000000000000000000000000013a59858b6809fca4d9a3b6539246a70051a3c88899964a42bc9a69cf9acdd9dd387cfa9086b894185b9a46a402be73\
+        0000000000000000000000000000000002d27e0ec3356299a346a09ad7dc4ef68a483c3aed53f9139d2f929a3eecebf72082e5e58c6da24ee32e03040c406d4f";
+    // G + wrong order point, addition does not check subgroup membership.
+    const G1_ADD_WRONG_ORDER: &str = "\
+        0000000000000000000000000000000017bcbbfdd2442c328150f65465bd7b9c4ff36e35261ad3549222e532758a1cf0945ba133ec513517b4ea9de098a037f9\
+        0000000000000000000000000000000006d1d4f6580f49b4e0a98509ffd18f24afcada36fd0d44e9fc9e5f0c19df3ec01474eefc659d57d149b97ca899010a5d";
+    const G2_ADD_WRONG_ORDER: &str = "\
+        000000000000000000000000000000000458a890e90468ba097a1c7cda15cb596e1b876cc1f879f0588a7f08b18064ffe7482e61dcb30899a6799e29941d12d3\
+        00000000000000000000000000000000181d56a9d4d73aad2f8a46208ed0fccd0a4ccbf2cd4ea83d2f64c87111fd0793089000eca5572745c7d45ccea5238cd4\
+        0000000000000000000000000000000002aa216e1fa37b0d1117ceb3281b467f3f74d83199b6dc289f51ba2f974c61fefd2384fa3875888733bc50d2a0c71c22\
+        0000000000000000000000000000000017af003ce4b523d1868afe1441db47193423d5493e941a114e79c504b1c7f778e063fc85fc631db538b3dc5cbbf3e4a3";
+    // multiples of the generators.
+    const G1_FIVE: &str = "\
+        0000000000000000000000000000000010e7791fb972fe014159aa33a98622da3cdc98ff707965e536d8636b5fcc5ac7a91a8c46e59a00dca575af0f18fb13dc\
+        0000000000000000000000000000000016ba437edcc6551e30c10512367494bfb6b01cc6681e8a4c3cd2501832ab5c4abc40b4578b85cbaffbf0bcd70d67c6e2";
+    const G1_THREE: &str = "\
+        0000000000000000000000000000000009ece308f9d1f0131765212deca99697b112d61f9be9a5f1f3780a51335b3ff981747a0b2ca2179b96d2c0c9024e5224\
+        00000000000000000000000000000000032b80d3a6f5b09f8a84623389c5f80ca69a0cddabc3097f9d9c27310fd43be6e745256c634af45ca3473b0590ae30d1";
+    const G2_FIVE: &str = "\
+        000000000000000000000000000000000411a5de6730ffece671a9f21d65028cc0f1102378de124562cb1ff49db6f004fcd14d683024b0548eff3d1468df2688\
+        0000000000000000000000000000000000fb837804dba8213329db46608b6c121d973363c1234a86dd183baff112709cf97096c5e9a1a770ee9d7dc641a894d6\
+        0000000000000000000000000000000019b5e8f5d4a72f2b75811ac084a7f814317360bac52f6aab15eed416b4ef9938e0bdc4865cc2c4d0fd947e7c6925fd14\
+        00000000000000000000000000000000093567b4228be17ee62d11a254edd041ee4b953bffb8b8c7f925bd6662b4298bac2822b446f5b5de3b893e1be5aa4986";
+    const G2_THREE: &str = "\
+        00000000000000000000000000000000122915c824a0857e2ee414a3dccb23ae691ae54329781315a0c75df1c04d6d7a50a030fc866f09d516020ef82324afae\
+        0000000000000000000000000000000009380275bbc8e5dcea7dc4dd7e0550ff2ac480905396eda55062650f8d251c96eb480673937cc6d9d6a44aaa56ca66dc\
+        000000000000000000000000000000000b21da7955969e61010c7a1abc1a6f0136961d1e3b20b1a7326ac738fef5c721479dfd948b52fdf2455e44813ecfd892\
+        0000000000000000000000000000000008f239ba329b3967fe48d718a36cfe5f62a7e42e0bf1c1ed714150a166bfbd6bcf6b3b58b975b9edea56d53f23a0e849";
+    const G2_DOUBLE: &str = "\
+        000000000000000000000000000000001638533957d540a9d2370f17cc7ed5863bc0b995b8825e0ee1ea1e1e4d00dbae81f14b0bf3611b78c952aacab827a053\
+        000000000000000000000000000000000a4edef9c1ed7f729f520e47730a124fd70662a904ba1074728114d1031e1572c6c886f6b57ec72a6178288c47c33577\
+        000000000000000000000000000000000468fb440d82b0630aeb8dca2b5256789a66da69bf91009cbfe6bd221e47aa8ae88dece9764bf3bd999d95d71e4c9899\
+        000000000000000000000000000000000f6d4552fa65dd2638b361543f887136a43253d9c66c411697003f7a13c308f5422e1aa0a59c8967acdefd8b6e36ccf3";
+    // a = sha256("a") and b = sha256("b"), both above the group order.
+    const SCALAR_A: &str = "\
+        ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb";
+    const SCALAR_B: &str = "\
+        3e23e8160039594a33894f6564e1b1348bbd7a0088d42c4acb73eeaed59c009d";
+    const G1_A: &str = "\
+        0000000000000000000000000000000009ed20c683924f4b0cde6f3e0bfbcc060736553648b3654a5411a3a55b22e117292469357a7c99dbfcd2cd954a46d0c8\
+        000000000000000000000000000000000ad120e3b06c4b8c8a07c629f46e0ece829a02db20703fe9bda31bbcf03afbfa8eac4a683e9c54b17a8fab8a6c69ae88";
+    const G2_B: &str = "\
+        0000000000000000000000000000000017177605296011121187d809a08a1d6ee2962129af09e01ed464f8063daf36e3c52eabda0aa5ffb9427e388ac8b3dd27\
+        00000000000000000000000000000000106fe953314962e4e9b587b5efeac2a70ed8ef638941e76e5725a85543e0990361c1cf4935709d55fcee1c8280fe9ca4\
+        000000000000000000000000000000001323a20b93d4cffceca84dd144dd0969049c291573267920bd222571e9a1dbe1337ef9920a93956dfacca106710aab41\
+        00000000000000000000000000000000063819de3fae66f339a338d334a4da99d85a1d9cdc78369e1ae6200b700fe89a5c3e2ccb7dc015df81e7bcce9452627e";
+    // 2 * aG1 + b * 3G1 and 2 * aG2 + b * 3G2.
+    const G1_MSM: &str = "\
+        000000000000000000000000000000000b7e21cb4f42d2fb59ebe491155501eb36bdbc08da44304047f8db554497a219b145281d798c6f526c68392525b41013\
+        00000000000000000000000000000000103bb491fe9b06ad6b1fa2fd834ecb16e7524e1e2d748870169325af60b9aae1a6f003cbbe829b9deb6014dc4d224ddb";
+    const G2_MSM: &str = "\
+        000000000000000000000000000000000d0e0cd7028c0016aab20de4133a39681c560d56c7db694e44d83afed57acbb1ab088f0199b00c5b39f06e6adfc0e7db\
+        00000000000000000000000000000000142be3ce5c6d12e67e5452aa4e6327f87361289a56fe9701c694c7ff0f93a584273fb32478283bb70b2ab3e17dd9d207\
+        000000000000000000000000000000000cfc6996f6f50371bb0037ccc1431d1bf6ade58cc73b3987b9176fa261507a2c88ba344a396bf18dfc37967c94ae9c57\
+        000000000000000000000000000000000c7812e6d331eb04d57fe6f11be56207e811a55030b975bafb7bcce0d66f93d25d89f6347a723cf21e2e8fd233ef29f0";
+    // (2^256 - 1) * G1.
+    const G1_MAX: &str = "\
+        0000000000000000000000000000000016ea601ca88f7d3489479129b258960b4c1df37194d30803627c30c34252679a0ada1a51bc7a4006a4f0564050d31746\
+        00000000000000000000000000000000039e394a6f95c4a2f27bf38f950b2af8d2aa8e0c4a1ffbe9ca518d1bedb573e310fba8f436aec3a3c8f2655fad5e2013";
+    // -(ab mod r) * G1 and -(ab mod r) * G2.
+    const G1_AB_NEG: &str = "\
+        00000000000000000000000000000000101e73bd1e96425dfc38b4c6fdae867d27880652c2ebd12158b3afc08dd62daeef9bd8a3ebc1e0d0a3f14eb86de6aea3\
+        0000000000000000000000000000000001fbeb867e67b8ba649b83863f250dc8f13a4ea513cdaf3b79be338b1cf15eaf584d5765496ab725b29fe4511efb3aaa";
+    const G2_AB_NEG: &str = "\
+        0000000000000000000000000000000015584107325f0ac503660fdcf9045ed82c987cd47e7d975923be4d6c5108bbb00af288ce306eefe577a0c7379d24068c\
+        0000000000000000000000000000000012aa43e89f3230a3d91af73ccb9e171ba011671027ae7c27a2f93fcdf12cbd4b7d40916f71909670f2eea1c8660ff651\
+        00000000000000000000000000000000192034c612cefa3258525d384fbe072701f7b0bca5078a28fa17579d76f2ac5da4ab17f31aa4ba17d3bd9554aa6a5d1e\
+        000000000000000000000000000000001994479c006836892ef7c6bd5964d38288029df4c3a464b4aabf23cdac81c9c5b470a28353a881d4f58475778ca514f9";
+    // field elements (u0, u1) and hash_to_curve outputs for msg "" and "abc" of the
+    // BLS12381G1_XMD:SHA-256_SSWU_RO_ and BLS12381G2_XMD:SHA-256_SSWU_RO_ suites, RFC 9380
+    // appendix J. The precompiles map a single element: hash = map(u0) + map(u1).
+    const G1_U_EMPTY: &str = "\
+        000000000000000000000000000000000ba14bd907ad64a016293ee7c2d276b8eae71f25a4b941eece7b0d89f17f75cb3ae5438a614fb61d6835ad59f29c564f\
+        00000000000000000000000000000000019b9bd7979f12657976de2884c7cce192b82c177c80e0ec604436a7f538d231552f0d96d9f7babe5fa3b19b3ff25ac9";
+    const G2_U_EMPTY: &str = "\
+        0000000000000000000000000000000003dbc2cce174e91ba93cbb08f26b917f98194a2ea08d1cce75b2b9cc9f21689d80bd79b594a613d0a68eb807dfdc1cf8\
+        0000000000000000000000000000000005a2acec64114845711a54199ea339abd125ba38253b70a92c876df10598bd1986b739cad67961eb94f7076511b3b39a\
+        0000000000000000000000000000000002f99798e8a5acdeed60d7e18e9120521ba1f47ec090984662846bc825de191b5b7641148c0dbc237726a334473eee94\
+        00000000000000000000000000000000145a81e418d4010cc027a68f14391b30074e89e60ee7a22f87217b2f6eb0c4b94c9115b436e6fa4607e95a98de30a435";
+    const G1_U_ABC: &str = "\
+        000000000000000000000000000000000d921c33f2bad966478a03ca35d05719bdf92d347557ea166e5bba579eea9b83e9afa5c088573c2281410369fbd32951\
+        00000000000000000000000000000000003574a00b109ada2f26a37a91f9d1e740dffd8d69ec0c35e1e9f4652c7dba61123e9dd2e76c655d956e2b3462611139";
+    const G2_U_ABC: &str = "\
+        0000000000000000000000000000000015f7c0aa8f6b296ab5ff9c2c7581ade64f4ee6f1bf18f55179ff44a2cf355fa53dd2a2158c5ecb17d7c52f63e7195771\
+        0000000000000000000000000000000001c8067bf4c0ba709aa8b9abc3d1cef589a4758e09ef53732d670fd8739a7274e111ba2fcaa71b3d33df2a3a0c8529dd\
+        00000000000000000000000000000000187111d5e088b6b9acfdfad078c4dacf72dcd17ca17c82be35e79f8c372a693f60a033b461d81b025864a0ad051a06e4\
+        0000000000000000000000000000000008b852331c96ed983e497ebc6dee9b75e373d923b729194af8e72a051ea586f3538a6ebb1e80881a082fa2b24df9f566";
+    const G1_HASH_EMPTY: &str = "\
+        00000000000000000000000000000000052926add2207b76ca4fa57a8734416c8dc95e24501772c814278700eed6d1e4e8cf62d9c09db0fac349612b759e79a1\
+        0000000000000000000000000000000008ba738453bfed09cb546dbb0783dbb3a5f1f566ed67bb6be0e8c67e2e81a4cc68ee29813bb7994998f3eae0c9c6a265";
+    const G1_HASH_ABC: &str = "\
+        0000000000000000000000000000000003567bc5ef9c690c2ab2ecdf6a96ef1c139cc0b2f284dca0a9a7943388a49a3aee664ba5379a7655d3c68900be2f6903\
+        000000000000000000000000000000000b9c15f3fe6e5cf4211f346271d7b01c8f3b28be689c8429c85b67af215533311f0b8dfaaa154fa6b88176c229f2885d";
+    const G2_HASH_EMPTY: &str = "\
+        000000000000000000000000000000000141ebfbdca40eb85b87142e130ab689c673cf60f1a3e98d69335266f30d9b8d4ac44c1038e9dcdd5393faf5c41fb78a\
+        0000000000000000000000000000000005cb8437535e20ecffaef7752baddf98034139c38452458baeefab379ba13dff5bf5dd71b72418717047f5b0f37da03d\
+        000000000000000000000000000000000503921d7f6a12805e72940b963c0cf3471c7b2a524950ca195d11062ee75ec076daf2d4bc358c4b190c0c98064fdd92\
+        0000000000000000000000000000000012424ac32561493f3fe3c260708a12b7c620e7be00099a974e259ddc7d1f6395c3c811cdd19f1e8dbf3e9ecfdcbab8d6";
+    const G2_HASH_ABC: &str = "\
+        0000000000000000000000000000000002c2d18e033b960562aae3cab37a27ce00d80ccd5ba4b7fe0e7a210245129dbec7780ccc7954725f4168aff2787776e6\
+        00000000000000000000000000000000139cddbccdc5e91b9623efd38c49f81a6f83f175e80b06fc374de9eb4b41dfe4ca3a230ed250fbe3a2acf73a41177fd8\
+        000000000000000000000000000000001787327b68159716a37440985269cf584bcb1e621d3a7202be6ea05c4cfe244aeb197642555a0645fb87bf7466b2ba48\
+        0000000000000000000000000000000000aa65dae3c8d732d10ecd2c50f8a1baf3001578f71c694e03866e9f3d49ac1e1ce70dd94a733534f106d4cec0eddd16";
+    // field modulus, the first non canonical field element.
+    const MODULUS: &str = "\
+        000000000000000000000000000000001a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab";
+
+    fn scalar(value: u8) -> Vec<u8> {
+        let mut out = vec![0u8; SCALAR_LEN];
+        out[31] = value;
+        out
+    }
+
+    fn run(precompile: (Address, Precompile), input: &[u8], gas: u64) -> PrecompileResult {
+        match precompile.1 {
+            Precompile::Standard(fun) | Precompile::Custom(fun) => fun(input, gas),
+        }
+    }
+
+    #[test]
+    fn g1_add() {
+        let g = hex::decode(G1_GENERATOR).unwrap();
+        let input = [g.clone(), g.clone()].concat();
+        let res = run(g1_add::PRAGUE, &input, 375).unwrap();
+        assert_eq!(res.cost, 375);
+        assert_eq!(res.output, hex::decode(G1_GENERATOR_DOUBLE).unwrap());
+
+        // adding the point at infinity
+        let input = [g.clone(), vec![0u8; G1_LEN]].concat();
+        let res = run(g1_add::PRAGUE, &input, 375).unwrap();
+        assert_eq!(res.output, g);
+
+        // p + (-p) is the point at infinity
+        let input = [g, hex::decode(G1_GENERATOR_NEG).unwrap()].concat();
+        let res = run(g1_add::PRAGUE, &input, 375).unwrap();
+        assert_eq!(res.output, vec![0u8; G1_LEN]);
+
+        assert_eq!(
+            run(g1_add::PRAGUE, &input, 374).unwrap_err(),
+            Return::OutOfGas
+        );
+        assert!(run(g1_add::PRAGUE, &input[1..], 375).is_err());
+    }
+
+    #[test]
+    fn g1_add_rejects_invalid_points() {
+        let mut input = vec![0u8; 2 * G1_LEN];
+        // (0, 1) is not on the curve.
+        input[G1_LEN - 1] = 1;
+        assert!(run(g1_add::PRAGUE, &input, 375).is_err());
+
+        // non zero padding.
+        let mut input = [hex::decode(G1_GENERATOR).unwrap(), vec![0u8; G1_LEN]].concat();
+        input[0] = 1;
+        assert!(run(g1_add::PRAGUE, &input, 375).is_err());
+    }
+
+    #[test]
+    fn g1_msm() {
+        let g = hex::decode(G1_GENERATOR).unwrap();
+
+        let input = [g.clone(), scalar(2)].concat();
+        let res = run(g1_msm::PRAGUE, &input, 12_000).unwrap();
+        assert_eq!(res.cost, 12_000);
+        assert_eq!(res.output, hex::decode(G1_GENERATOR_DOUBLE).unwrap());
+
+        // 1*G + 1*G == 2*G, with the discount applied for k = 2
+        let input = [g.clone(), scalar(1), g.clone(), scalar(1)].concat();
+        let res = run(g1_msm::PRAGUE, &input, u64::MAX).unwrap();
+        assert_eq!(res.cost, 2 * 12_000 * 949 / 1000);
+        assert_eq!(res.output, hex::decode(G1_GENERATOR_DOUBLE).unwrap());
+
+        // multiplication by zero
+        let input = [g, scalar(0)].concat();
+        let res = run(g1_msm::PRAGUE, &input, 12_000).unwrap();
+        assert_eq!(res.output, vec![0u8; G1_LEN]);
+
+        assert!(run(g1_msm::PRAGUE, &[], u64::MAX).is_err());
+    }
+
+    #[test]
+    fn g2_add_and_msm() {
+        let g = hex::decode(G2_GENERATOR).unwrap();
+
+        let input = [g.clone(), vec![0u8; G2_LEN]].concat();
+        let res = run(g2_add::PRAGUE, &input, 600).unwrap();
+        assert_eq!(res.cost, 600);
+        assert_eq!(res.output, g);
+
+        let doubled = run(g2_add::PRAGUE, &[g.clone(), g.clone()].concat(), 600)
+            .unwrap()
+            .output;
+        let res = run(g2_msm::PRAGUE, &[g.clone(), scalar(2)].concat(), 22_500).unwrap();
+        assert_eq!(res.cost, 22_500);
+        assert_eq!(res.output, doubled);
+
+        let input = [g.clone(), scalar(1), g, scalar(1)].concat();
+        let res = run(g2_msm::PRAGUE, &input, u64::MAX).unwrap();
+        assert_eq!(res.cost, 2 * 22_500);
+        assert_eq!(res.output, doubled);
+    }
+
+    #[test]
+    fn pairing() {
+        let g1 = hex::decode(G1_GENERATOR).unwrap();
+        let g1_neg = hex::decode(G1_GENERATOR_NEG).unwrap();
+        let g2 = hex::decode(G2_GENERATOR).unwrap();
+
+        // e(G1, G2) * e(-G1, G2) == 1
+        let input = [g1.clone(), g2.clone(), g1_neg, g2.clone()].concat();
+        let res = run(pairing::PRAGUE, &input, u64::MAX).unwrap();
+        assert_eq!(res.cost, 2 * 32_600 + 37_700);
+        assert_eq!(res.output, scalar(1));
+
+        // e(G1, G2) != 1
+        let input = [g1.clone(), g2.clone()].concat();
+        let res = run(pairing::PRAGUE, &input, u64::MAX).unwrap();
+        assert_eq!(res.output, scalar(0));
+
+        // pair with the point at infinity
+        let input = [vec![0u8; G1_LEN], g2].concat();
+        let res = run(pairing::PRAGUE, &input, u64::MAX).unwrap();
+        assert_eq!(res.output, scalar(1));
+
+        // empty input is invalid
+        assert!(run(pairing::PRAGUE, &[], u64::MAX).is_err());
+        assert_eq!(
+            run(pairing::PRAGUE, &[g1, vec![0u8; G2_LEN]].concat(), 70_299).unwrap_err(),
+            Return::OutOfGas
+        );
+    }
+
+    #[test]
+    fn map_to_curve() {
+        let res = run(map_fp_to_g1::PRAGUE, &[0u8; PADDED_FP_LEN], 5_500).unwrap();
+        assert_eq!(res.cost, 5_500);
+        assert_eq!(res.output.len(), G1_LEN);
+        assert!(read_g1(&res.output, true).is_ok());
+
+        let res = run(map_fp2_to_g2::PRAGUE, &[0u8; PADDED_FP2_LEN], 23_800).unwrap();
+        assert_eq!(res.cost, 23_800);
+        assert_eq!(res.output.len(), G2_LEN);
+        assert!(read_g2(&res.output, true).is_ok());
+
+        // field element equal to the modulus is rejected.
+        let input = hex::decode(
+            "000000000000000000000000000000001a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab",
+        )
+        .unwrap();
+        assert!(run(map_fp_to_g1::PRAGUE, &input, 5_500).is_err());
+    }
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        hex::decode(hex).unwrap()
+    }
+
+    #[test]
+    fn g1_add_vectors() {
+        let input = [bytes(G1_GENERATOR_DOUBLE), bytes(G1_THREE)].concat();
+        let res = run(g1_add::PRAGUE, &input, 375).unwrap();
+        assert_eq!(res.output, bytes(G1_FIVE));
+
+        let input = [bytes(G1_GENERATOR), bytes(G1_WRONG_ORDER)].concat();
+        let res = run(g1_add::PRAGUE, &input, 375).unwrap();
+        assert_eq!(res.output, bytes(G1_ADD_WRONG_ORDER));
+
+        let res = run(g1_add::PRAGUE, &[0u8; 2 * G1_LEN], 375).unwrap();
+        assert_eq!(res.output, vec![0u8; G1_LEN]);
+
+        // invalid encodings.
+        let input = [bytes(G1_GENERATOR), bytes(G1_FIVE)].concat();
+        assert!(run(g1_add::PRAGUE, &[], 375).is_err());
+        assert!(run(g1_add::PRAGUE, &input[..2 * G1_LEN - 1], 375).is_err());
+        assert!(run(g1_add::PRAGUE, &[&input[..], &[0]].concat(), 375).is_err());
+        let mut invalid = input.clone();
+        invalid[..PADDED_FP_LEN].copy_from_slice(&bytes(MODULUS));
+        assert!(run(g1_add::PRAGUE, &invalid, 375).is_err());
+        let mut invalid = input;
+        invalid[G1_LEN - 1] ^= 1;
+        assert!(run(g1_add::PRAGUE, &invalid, 375).is_err());
+    }
+
+    #[test]
+    fn g2_add_vectors() {
+        let input = [bytes(G2_DOUBLE), bytes(G2_THREE)].concat();
+        let res = run(g2_add::PRAGUE, &input, 600).unwrap();
+        assert_eq!(res.output, bytes(G2_FIVE));
+
+        let input = [bytes(G2_GENERATOR), bytes(G2_WRONG_ORDER)].concat();
+        let res = run(g2_add::PRAGUE, &input, 600).unwrap();
+        assert_eq!(res.output, bytes(G2_ADD_WRONG_ORDER));
+
+        let res = run(g2_add::PRAGUE, &[0u8; 2 * G2_LEN], 600).unwrap();
+        assert_eq!(res.output, vec![0u8; G2_LEN]);
+
+        // invalid encodings.
+        let input = [bytes(G2_GENERATOR), bytes(G2_FIVE)].concat();
+        assert!(run(g2_add::PRAGUE, &[], 600).is_err());
+        assert!(run(g2_add::PRAGUE, &input[..2 * G2_LEN - 1], 600).is_err());
+        assert!(run(g2_add::PRAGUE, &[&input[..], &[0]].concat(), 600).is_err());
+        let mut invalid = input.clone();
+        invalid[PADDED_FP_LEN..PADDED_FP2_LEN].copy_from_slice(&bytes(MODULUS));
+        assert!(run(g2_add::PRAGUE, &invalid, 600).is_err());
+        let mut invalid = input.clone();
+        invalid[0] = 1;
+        assert!(run(g2_add::PRAGUE, &invalid, 600).is_err());
+        let mut invalid = input;
+        invalid[G2_LEN - 1] ^= 1;
+        assert!(run(g2_add::PRAGUE, &invalid, 600).is_err());
+    }
+
+    #[test]
+    fn g1_msm_vectors() {
+        let input = [bytes(G1_GENERATOR), bytes(SCALAR_A)].concat();
+        let res = run(g1_msm::PRAGUE, &input, 12_000).unwrap();
+        assert_eq!(res.output, bytes(G1_A));
+
+        let input = [bytes(G1_GENERATOR), vec![0xff; SCALAR_LEN]].concat();
+        let res = run(g1_msm::PRAGUE, &input, 12_000).unwrap();
+        assert_eq!(res.output, bytes(G1_MAX));
+
+        // scalars are not reduced, the group order maps to the point at infinity.
+        let order = "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+        let input = [bytes(G1_GENERATOR), bytes(order)].concat();
+        let res = run(g1_msm::PRAGUE, &input, 12_000).unwrap();
+        assert_eq!(res.output, vec![0u8; G1_LEN]);
+
+        let input = [bytes(G1_A), scalar(2), bytes(G1_THREE), bytes(SCALAR_B)].concat();
+        let res = run(g1_msm::PRAGUE, &input, u64::MAX).unwrap();
+        assert_eq!(res.output, bytes(G1_MSM));
+
+        // invalid inputs.
+        let input = [bytes(G1_WRONG_ORDER), scalar(1)].concat();
+        assert!(run(g1_msm::PRAGUE, &input, 12_000).is_err());
+        let input = [bytes(G1_GENERATOR), scalar(1)].concat();
+        assert!(run(g1_msm::PRAGUE, &input[..G1_MSM_PAIR_LEN - 1], 12_000).is_err());
+        assert!(run(g1_msm::PRAGUE, &[&input[..], &[0]].concat(), u64::MAX).is_err());
+    }
+
+    #[test]
+    fn g2_msm_vectors() {
+        let input = [bytes(G2_GENERATOR), bytes(SCALAR_B)].concat();
+        let res = run(g2_msm::PRAGUE, &input, 22_500).unwrap();
+        assert_eq!(res.output, bytes(G2_B));
+
+        let input = [
+            bytes(G2_DOUBLE),
+            bytes(SCALAR_A),
+            bytes(G2_THREE),
+            bytes(SCALAR_B),
+        ]
+        .concat();
+        let res = run(g2_msm::PRAGUE, &input, u64::MAX).unwrap();
+        assert_eq!(res.output, bytes(G2_MSM));
+
+        // invalid inputs.
+        let input = [bytes(G2_WRONG_ORDER), scalar(1)].concat();
+        assert!(run(g2_msm::PRAGUE, &input, 22_500).is_err());
+        let input = [bytes(G2_GENERATOR), scalar(1)].concat();
+        assert!(run(g2_msm::PRAGUE, &input[..G2_MSM_PAIR_LEN - 1], 22_500).is_err());
+        assert!(run(g2_msm::PRAGUE, &[&input[..], &[0]].concat(), u64::MAX).is_err());
+    }
+
+    #[test]
+    fn pairing_vectors() {
+        let one = scalar(1);
+        // bilinearity: e(aG1, bG2) * e(-abG1, G2) == 1 and e(aG1, bG2) * e(G1, -abG2) == 1
+        let input = [
+            bytes(G1_A),
+            bytes(G2_B),
+            bytes(G1_AB_NEG),
+            bytes(G2_GENERATOR),
+        ]
+        .concat();
+        assert_eq!(run(pairing::PRAGUE, &input, u64::MAX).unwrap().output, one);
+        let input = [
+            bytes(G1_A),
+            bytes(G2_B),
+            bytes(G1_GENERATOR),
+            bytes(G2_AB_NEG),
+        ]
+        .concat();
+        assert_eq!(run(pairing::PRAGUE, &input, u64::MAX).unwrap().output, one);
+
+        // e(aG1, bG2) * e(G1, G2) != 1
+        let input = [
+            bytes(G1_A),
+            bytes(G2_B),
+            bytes(G1_GENERATOR),
+            bytes(G2_GENERATOR),
+        ]
+        .concat();
+        assert_eq!(
+            run(pairing::PRAGUE, &input, u64::MAX).unwrap().output,
+            scalar(0)
+        );
+
+        // points outside the subgroup are rejected.
+        let input = [bytes(G1_WRONG_ORDER), bytes(G2_GENERATOR)].concat();
+        assert!(run(pairing::PRAGUE, &input, u64::MAX).is_err());
+        let input = [bytes(G1_GENERATOR), bytes(G2_WRONG_ORDER)].concat();
+        assert!(run(pairing::PRAGUE, &input, u64::MAX).is_err());
+        let input = [bytes(G1_GENERATOR), bytes(G2_GENERATOR)].concat();
+        assert!(run(pairing::PRAGUE, &input[1..], u64::MAX).is_err());
+    }
+
+    #[test]
+    fn map_vectors() {
+        let hash_g1 = |u: &str| {
+            let u = bytes(u);
+            let input = [
+                run(map_fp_to_g1::PRAGUE, &u[..PADDED_FP_LEN], 5_500)
+                    .unwrap()
+                    .output,
+                run(map_fp_to_g1::PRAGUE, &u[PADDED_FP_LEN..], 5_500)
+                    .unwrap()
+                    .output,
+            ]
+            .concat();
+            run(g1_add::PRAGUE, &input, 375).unwrap().output
+        };
+        assert_eq!(hash_g1(G1_U_EMPTY), bytes(G1_HASH_EMPTY));
+        assert_eq!(hash_g1(G1_U_ABC), bytes(G1_HASH_ABC));
+
+        let hash_g2 = |u: &str| {
+            let u = bytes(u);
+            let input = [
+                run(map_fp2_to_g2::PRAGUE, &u[..PADDED_FP2_LEN], 23_800)
+                    .unwrap()
+                    .output,
+                run(map_fp2_to_g2::PRAGUE, &u[PADDED_FP2_LEN..], 23_800)
+                    .unwrap()
+                    .output,
+            ]
+            .concat();
+            run(g2_add::PRAGUE, &input, 600).unwrap().output
+        };
+        assert_eq!(hash_g2(G2_U_EMPTY), bytes(G2_HASH_EMPTY));
+        assert_eq!(hash_g2(G2_U_ABC), bytes(G2_HASH_ABC));
+
+        // invalid encodings.
+        let u = bytes(G1_U_EMPTY);
+        let mut invalid = u[..PADDED_FP_LEN].to_vec();
+        invalid[FP_PAD_LEN - 1] = 1;
+        assert!(run(map_fp_to_g1::PRAGUE, &invalid, 5_500).is_err());
+        assert!(run(map_fp_to_g1::PRAGUE, &[], 5_500).is_err());
+        assert!(run(map_fp_to_g1::PRAGUE, &u[..PADDED_FP_LEN - 1], 5_500).is_err());
+        assert!(run(map_fp_to_g1::PRAGUE, &u[..PADDED_FP_LEN + 1], 5_500).is_err());
+
+        let u = bytes(G2_U_EMPTY);
+        let invalid = [&u[..PADDED_FP_LEN], &bytes(MODULUS)[..]].concat();
+        assert!(run(map_fp2_to_g2::PRAGUE, &invalid, 23_800).is_err());
+        assert!(run(map_fp2_to_g2::PRAGUE, &u[..PADDED_FP2_LEN - 1], 23_800).is_err());
+        assert!(run(map_fp2_to_g2::PRAGUE, &u[..PADDED_FP2_LEN + 1], 23_800).is_err());
+    }
+
+    #[test]
+    fn msm_discount() {
+        assert_eq!(msm_cost(1, 12_000, &G1_MSM_DISCOUNT_TABLE), 12_000);
+        assert_eq!(
+            msm_cost(200, 12_000, &G1_MSM_DISCOUNT_TABLE),
+            200 * 12_000 * 519 / 1000
+        );
+        assert_eq!(
+            msm_cost(128, 22_500, &G2_MSM_DISCOUNT_TABLE),
+            128 * 22_500 * 524 / 1000
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{gas_query, Precompile, PrecompileOutput, PrecompileResult, Return};
+use crate::{gas_query, Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult, Return};
 
 use alloc::{borrow::Cow, vec::Vec};
 use primitive_types::{H160 as Address, U256};
@@ -20,6 +20,9 @@ pub mod add {
             super::run_add(input, 500, target_gas)
         }),
     );
+
+    pub const ISTANBUL_GAS: (Address, PrecompileGasFn) = (ADDRESS, |_: &[u8]| 150);
+    pub const BYZANTIUM_GAS: (Address, PrecompileGasFn) = (ADDRESS, |_: &[u8]| 500);
 }
 
 pub mod mul {
@@ -38,6 +41,9 @@ pub mod mul {
             super::run_mul(input, 40_000, target_gas)
         }),
     );
+
+    pub const ISTANBUL_GAS: (Address, PrecompileGasFn) = (ADDRESS, |_: &[u8]| 6_000);
+    pub const BYZANTIUM_GAS: (Address, PrecompileGasFn) = (ADDRESS, |_: &[u8]| 40_000);
 }
 
 pub mod pair {
@@ -71,6 +77,13 @@ pub mod pair {
             )
         }),
     );
+
+    pub const ISTANBUL_GAS: (Address, PrecompileGasFn) = (ADDRESS, |input: &[u8]| {
+        super::pair_gas(input, ISTANBUL_PAIR_PER_POINT, ISTANBUL_PAIR_BASE)
+    });
+    pub const BYZANTIUM_GAS: (Address, PrecompileGasFn) = (ADDRESS, |input: &[u8]| {
+        super::pair_gas(input, BYZANTIUM_PAIR_PER_POINT, BYZANTIUM_PAIR_BASE)
+    });
 }
 
 /// Input length for the add operation.
@@ -154,14 +167,20 @@ fn run_mul(input: &[u8], cost: u64, target_gas: u64) -> PrecompileResult {
     Ok(PrecompileOutput::without_logs(cost, out.to_vec()))
 }
 
+fn pair_gas(input: &[u8], pair_per_point_cost: u64, pair_base_cost: u64) -> u64 {
+    pair_per_point_cost * input.len() as u64 / PAIR_ELEMENT_LEN as u64 + pair_base_cost
+}
+
 fn run_pair(
     input: &[u8],
     pair_per_point_cost: u64,
     pair_base_cost: u64,
     target_gas: u64,
 ) -> PrecompileResult {
-    let cost = pair_per_point_cost * input.len() as u64 / PAIR_ELEMENT_LEN as u64 + pair_base_cost;
-    let cost = gas_query(cost, target_gas)?;
+    let cost = gas_query(
+        pair_gas(input, pair_per_point_cost, pair_base_cost),
+        target_gas,
+    )?;
 
     use bn::{AffineG1, AffineG2, Fq, Fq2, Group, Gt, G1, G2};
 
@@ -250,272 +269,150 @@ fn run_pair(
     Ok(PrecompileOutput::without_logs(cost, buf.to_vec()))
 }
 
-/*
 #[cfg(test)]
 mod tests {
-    use crate::test_utils::new_context;
-
     use super::*;
+    use crate::Precompile;
+
+    fn run(precompile: (Address, Precompile), input: &str, gas_limit: u64) -> PrecompileResult {
+        let input = hex::decode(input).unwrap();
+        match precompile.1 {
+            Precompile::Standard(fun) => fun(&input, gas_limit),
+            Precompile::Custom(_) => unreachable!(),
+        }
+    }
+
+    const PAIR_INPUT: &str = "\
+        1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59\
+        3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41\
+        209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7\
+        04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678\
+        2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d\
+        120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550\
+        111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c\
+        2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411\
+        198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+        1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+        090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+        12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+
+    const ONE: &str = "0000000000000000000000000000000000000000000000000000000000000001";
 
     #[test]
     fn test_alt_bn128_add() {
-        let input = hex::decode(
-            "\
-             18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
-             063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
-             07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
-             06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7",
-        )
-        .unwrap();
-        let expected = hex::decode(
-            "\
+        let input = "\
+            18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+            063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
+            07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
+            06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7";
+        let expected = "\
             2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703\
-            301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915",
-        )
-        .unwrap();
-
-        let res = Bn128Add::<Byzantium>::run(&input, 500, &new_context(), false)
-            .unwrap()
-            .output;
-        assert_eq!(res, expected);
-
-        // zero sum test
-        let input = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-        let expected = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = Bn128Add::<Byzantium>::run(&input, 500, &new_context(), false)
-            .unwrap()
-            .output;
-        assert_eq!(res, expected);
-
-        // out of gas test
-        let input = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-        let res = Bn128Add::<Byzantium>::run(&input, 499, &new_context(), false);
-        assert!(matches!(res, Err(Return::OutOfGas)));
-
-        // no input test
-        let input = [0u8; 0];
-        let expected = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = Bn128Add::<Byzantium>::run(&input, 500, &new_context(), false)
-            .unwrap()
-            .output;
-        assert_eq!(res, expected);
-
-        // point not on curve fail
-        let input = hex::decode(
-            "\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111",
-        )
-        .unwrap();
+            301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915";
+
+        let res = run(add::BYZANTIUM, input, 500).unwrap();
+        assert_eq!(res.cost, 500);
+        assert_eq!(hex::encode(res.output), expected);
+        let res = run(add::ISTANBUL, input, 150).unwrap();
+        assert_eq!(res.cost, 150);
+        assert_eq!(hex::encode(res.output), expected);
+        assert!(matches!(
+            run(add::ISTANBUL, input, 149),
+            Err(Return::OutOfGas)
+        ));
 
-        let res = Bn128Add::<Byzantium>::run(&input, 500, &new_context(), false);
+        // empty input is padded to two points at infinity
+        let res = run(add::ISTANBUL, "", 150).unwrap();
+        assert_eq!(res.output, [0u8; 64]);
+
+        // point not on curve
+        let input = "\
+            1111111111111111111111111111111111111111111111111111111111111111\
+            1111111111111111111111111111111111111111111111111111111111111111";
         assert!(matches!(
-            res,
+            run(add::ISTANBUL, input, 150),
             Err(Return::Other(Cow::Borrowed("ERR_BN128_INVALID_POINT")))
         ));
     }
 
     #[test]
     fn test_alt_bn128_mul() {
-        let input = hex::decode(
-            "\
+        let input = "\
             2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7\
             21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204\
-            00000000000000000000000000000000000000000000000011138ce750fa15c2",
-        )
-        .unwrap();
-        let expected = hex::decode(
-            "\
+            00000000000000000000000000000000000000000000000011138ce750fa15c2";
+        let expected = "\
             070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c\
-            031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc",
-        )
-        .unwrap();
-
-        let res = Bn128Mul::<Byzantium>::run(&input, 40_000, &new_context(), false)
-            .unwrap()
-            .output;
-        assert_eq!(res, expected);
-
-        // out of gas test
-        let input = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0200000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-        let res = Bn128Mul::<Byzantium>::run(&input, 39_999, &new_context(), false);
-        assert!(matches!(res, Err(Return::OutOfGas)));
-
-        // zero multiplication test
-        let input = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0200000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-        let expected = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = Bn128Mul::<Byzantium>::run(&input, 40_000, &new_context(), false)
-            .unwrap()
-            .output;
-        assert_eq!(res, expected);
-
-        // no input test
-        let input = [0u8; 0];
-        let expected = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = Bn128Mul::<Byzantium>::run(&input, 40_000, &new_context(), false)
-            .unwrap()
-            .output;
-        assert_eq!(res, expected);
-
-        // point not on curve fail
-        let input = hex::decode(
-            "\
+            031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc";
+
+        let res = run(mul::BYZANTIUM, input, 40_000).unwrap();
+        assert_eq!(res.cost, 40_000);
+        assert_eq!(hex::encode(res.output), expected);
+        let res = run(mul::ISTANBUL, input, 6_000).unwrap();
+        assert_eq!(res.cost, 6_000);
+        assert_eq!(hex::encode(res.output), expected);
+        assert!(matches!(
+            run(mul::ISTANBUL, input, 5_999),
+            Err(Return::OutOfGas)
+        ));
+
+        // missing scalar is padded with zeroes
+        let res = run(mul::ISTANBUL, &input[..128], 6_000).unwrap();
+        assert_eq!(res.output, [0u8; 64]);
+
+        // point not on curve
+        let input = "\
             1111111111111111111111111111111111111111111111111111111111111111\
             1111111111111111111111111111111111111111111111111111111111111111\
-            0f00000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = Bn128Mul::<Byzantium>::run(&input, 40_000, &new_context(), false);
+            0f00000000000000000000000000000000000000000000000000000000000000";
         assert!(matches!(
-            res,
+            run(mul::ISTANBUL, input, 6_000),
             Err(Return::Other(Cow::Borrowed("ERR_BN128_INVALID_POINT")))
         ));
     }
 
     #[test]
     fn test_alt_bn128_pair() {
-        let input = hex::decode(
-            "\
-            1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59\
-            3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41\
-            209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7\
-            04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678\
-            2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d\
-            120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550\
-            111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c\
-            2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411\
-            198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
-            1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
-            090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
-            12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
-        )
-        .unwrap();
-        let expected =
-            hex::decode("0000000000000000000000000000000000000000000000000000000000000001")
-                .unwrap();
-
-        let res = Bn128Pair::<Byzantium>::run(&input, 260_000, &new_context(), false)
-            .unwrap()
-            .output;
-        assert_eq!(res, expected);
-
-        // out of gas test
-        let input = hex::decode(
-            "\
-            1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59\
-            3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41\
-            209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7\
-            04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678\
-            2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d\
-            120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550\
-            111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c\
-            2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411\
-            198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
-            1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
-            090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
-            12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
-        )
-        .unwrap();
-        let res = Bn128Pair::<Byzantium>::run(&input, 259_999, &new_context(), false);
-        assert!(matches!(res, Err(Return::OutOfGas)));
-
-        // no input test
-        let input = [0u8; 0];
-        let expected =
-            hex::decode("0000000000000000000000000000000000000000000000000000000000000001")
-                .unwrap();
-
-        let res = Bn128Pair::<Byzantium>::run(&input, 260_000, &new_context(), false)
-            .unwrap()
-            .output;
-        assert_eq!(res, expected);
-
-        // point not on curve fail
-        let input = hex::decode(
-            "\
+        let res = run(pair::BYZANTIUM, PAIR_INPUT, 260_000).unwrap();
+        assert_eq!(res.cost, 260_000);
+        assert_eq!(hex::encode(res.output), ONE);
+        let res = run(pair::ISTANBUL, PAIR_INPUT, 113_000).unwrap();
+        assert_eq!(res.cost, 113_000);
+        assert_eq!(hex::encode(res.output), ONE);
+        assert!(matches!(
+            run(pair::BYZANTIUM, PAIR_INPUT, 259_999),
+            Err(Return::OutOfGas)
+        ));
+        assert!(matches!(
+            run(pair::ISTANBUL, PAIR_INPUT, 112_999),
+            Err(Return::OutOfGas)
+        ));
+
+        // no input
+        let res = run(pair::ISTANBUL, "", 45_000).unwrap();
+        assert_eq!(res.cost, 45_000);
+        assert_eq!(hex::encode(res.output), ONE);
+
+        // point not on curve
+        let input = "\
             1111111111111111111111111111111111111111111111111111111111111111\
             1111111111111111111111111111111111111111111111111111111111111111\
             1111111111111111111111111111111111111111111111111111111111111111\
             1111111111111111111111111111111111111111111111111111111111111111\
             1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111",
-        )
-        .unwrap();
-
-        let res = Bn128Pair::<Byzantium>::run(&input, 260_000, &new_context(), false);
+            1111111111111111111111111111111111111111111111111111111111111111";
         assert!(matches!(
-            res,
+            run(pair::ISTANBUL, input, 79_000),
             Err(Return::Other(Cow::Borrowed("ERR_BN128_INVALID_A")))
         ));
 
         // invalid input length
-        let input = hex::decode(
-            "\
+        let input = "\
             1111111111111111111111111111111111111111111111111111111111111111\
             1111111111111111111111111111111111111111111111111111111111111111\
-            111111111111111111111111111111\
-        ",
-        )
-        .unwrap();
-
-        let res = Bn128Pair::<Byzantium>::run(&input, 260_000, &new_context(), false);
+            111111111111111111111111111111";
         assert!(matches!(
-            res,
-            Err(Return::Other(Cow::Borrowed("ERR_BN128_INVALID_LEN",)))
+            run(pair::ISTANBUL, input, 79_000),
+            Err(Return::Other(Cow::Borrowed("ERR_BN128_INVALID_LEN")))
         ));
     }
 }
-*/
@@ -1,6 +1,8 @@
 use super::{calc_linear_cost_u32, gas_query};
 
-use crate::{Precompile, PrecompileOutput, PrecompileResult, StandardPrecompileFn};
+use crate::{
+    Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult, StandardPrecompileFn,
+};
 use primitive_types::H160 as Address;
 use sha2::*;
 
@@ -13,11 +15,22 @@ pub const RIPEMD160: (Address, Precompile) = (
     Precompile::Standard(ripemd160_run as StandardPrecompileFn),
 );
 
+pub const SHA256_GAS: (Address, PrecompileGasFn) = (super::make_address(0, 2), sha256_gas);
+pub const RIPEMD160_GAS: (Address, PrecompileGasFn) = (super::make_address(0, 3), ripemd160_gas);
+
+fn sha256_gas(input: &[u8]) -> u64 {
+    calc_linear_cost_u32(input.len(), 60, 12)
+}
+
+fn ripemd160_gas(input: &[u8]) -> u64 {
+    calc_linear_cost_u32(input.len(), 600, 120)
+}
+
 /// See: https://ethereum.github.io/yellowpaper/paper.pdf
 /// See: https://docs.soliditylang.org/en/develop/units-and-global-variables.html#mathematical-and-cryptographic-functions
 /// See: https://etherscan.io/address/0000000000000000000000000000000000000002
 fn sha256_run(input: &[u8], gas_limit: u64) -> PrecompileResult {
-    let cost = gas_query(calc_linear_cost_u32(input.len(), 60, 12), gas_limit)?;
+    let cost = gas_query(sha256_gas(input), gas_limit)?;
     let output = sha2::Sha256::digest(input).to_vec();
     Ok(PrecompileOutput::without_logs(cost, output))
 }
@@ -26,8 +39,60 @@ fn sha256_run(input: &[u8], gas_limit: u64) -> PrecompileResult {
 /// See: https://docs.soliditylang.org/en/develop/units-and-global-variables.html#mathematical-and-cryptographic-functions
 /// See: https://etherscan.io/address/0000000000000000000000000000000000000003
 fn ripemd160_run(input: &[u8], gas_limit: u64) -> PrecompileResult {
-    let gas_used = gas_query(calc_linear_cost_u32(input.len(), 600, 120), gas_limit)?;
+    let gas_used = gas_query(ripemd160_gas(input), gas_limit)?;
     let mut ret = [0u8; 32];
     ret[12..32].copy_from_slice(&ripemd::Ripemd160::digest(input));
     Ok(PrecompileOutput::without_logs(gas_used, ret.to_vec()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Return;
+    use alloc::string::String;
+
+    fn run(f: fn(&[u8], u64) -> PrecompileResult, input: &[u8]) -> (u64, String) {
+        let out = f(input, u64::MAX).unwrap();
+        (out.cost, hex::encode(out.output))
+    }
+
+    #[test]
+    fn sha256_vectors() {
+        assert_eq!(
+            run(sha256_run, b""),
+            (
+                60,
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".into()
+            )
+        );
+        assert_eq!(
+            run(sha256_run, b"abc"),
+            (
+                72,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into()
+            )
+        );
+        assert_eq!(run(sha256_run, &[0; 33]).0, 84);
+        assert_eq!(sha256_run(b"abc", 71).unwrap_err(), Return::OutOfGas);
+    }
+
+    #[test]
+    fn ripemd160_vectors() {
+        assert_eq!(
+            run(ripemd160_run, b""),
+            (
+                600,
+                "0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31".into()
+            )
+        );
+        assert_eq!(
+            run(ripemd160_run, b"abc"),
+            (
+                720,
+                "0000000000000000000000008eb208f7e05d987a9b044a8e98c6b087f15a0bfc".into()
+            )
+        );
+        assert_eq!(run(ripemd160_run, &[0; 33]).0, 840);
+        assert_eq!(ripemd160_run(b"abc", 719).unwrap_err(), Return::OutOfGas);
+    }
+}
@@ -1,4 +1,6 @@
-use crate::{Precompile, PrecompileOutput, PrecompileResult, StandardPrecompileFn};
+use crate::{
+    Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult, StandardPrecompileFn,
+};
 use primitive_types::H160 as Address;
 
 use super::{calc_linear_cost_u32, gas_query};
@@ -8,6 +10,8 @@ pub const FUN: (Address, Precompile) = (
     Precompile::Standard(identity_run as StandardPrecompileFn),
 );
 
+pub const GAS: (Address, PrecompileGasFn) = (super::make_address(0, 4), identity_gas);
+
 /// The base cost of the operation.
 const IDENTITY_BASE: u64 = 15;
 /// The cost per word.
@@ -18,9 +22,28 @@ const IDENTITY_PER_WORD: u64 = 3;
 /// See: https://ethereum.github.io/yellowpaper/paper.pdf
 /// See: https://etherscan.io/address/0000000000000000000000000000000000000004
 fn identity_run(input: &[u8], gas_limit: u64) -> PrecompileResult {
-    let gas_used = gas_query(
-        calc_linear_cost_u32(input.len(), IDENTITY_BASE, IDENTITY_PER_WORD),
-        gas_limit,
-    )?;
+    let gas_used = gas_query(identity_gas(input), gas_limit)?;
     Ok(PrecompileOutput::without_logs(gas_used, input.to_vec()))
 }
+
+fn identity_gas(input: &[u8]) -> u64 {
+    calc_linear_cost_u32(input.len(), IDENTITY_BASE, IDENTITY_PER_WORD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Return;
+    use alloc::{vec, vec::Vec};
+
+    #[test]
+    fn identity_vectors() {
+        let out = identity_run(&[], 15).unwrap();
+        assert_eq!((out.cost, out.output), (15, vec![]));
+
+        let input: Vec<u8> = (0..33).collect();
+        let out = identity_run(&input, 21).unwrap();
+        assert_eq!((out.cost, out.output), (21, input.clone()));
+        assert_eq!(identity_run(&input, 20).unwrap_err(), Return::OutOfGas);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{
+    gas_query, Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult, Return,
+    StandardPrecompileFn,
+};
+
+use alloc::{borrow::Cow, boxed::Box, vec::Vec};
+use c_kzg::{Bytes32, Bytes48, KzgProof, KzgSettings, BYTES_PER_G1_POINT, BYTES_PER_G2_POINT};
+use once_cell::race::OnceBox;
+use primitive_types::H160 as Address;
+use sha2::{Digest, Sha256};
+
+pub const POINT_EVALUATION: (Address, Precompile) = (
+    super::make_address(0, 0x0a),
+    Precompile::Standard(run as StandardPrecompileFn),
+);
+
+/// Fixed cost of the point evaluation precompile.
+pub const GAS_COST: u64 = 50_000;
+
+pub const POINT_EVALUATION_GAS: (Address, PrecompileGasFn) =
+    (super::make_address(0, 0x0a), |_: &[u8]| GAS_COST);
+
+/// Version byte of a versioned hash derived from a KZG commitment.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Input is `versioned_hash | z | y | commitment | proof`.
+const INPUT_LEN: usize = 192;
+
+/// `U256(FIELD_ELEMENTS_PER_BLOB).to_be_bytes() ++ BLS_MODULUS.to_bytes32()`
+pub const RETURN_VALUE: &[u8; 64] = &[
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00,
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// Mainnet trusted setup from the KZG ceremony, in the format used by c-kzg.
+const TRUSTED_SETUP: &str = include_str!("trusted_setup.txt");
+
+/// Returns the mainnet KZG settings, loading them on first use.
+pub fn kzg_settings() -> &'static KzgSettings {
+    static SETTINGS: OnceBox<KzgSettings> = OnceBox::new();
+    SETTINGS.get_or_init(|| {
+        let (g1, g2) = parse_trusted_setup(TRUSTED_SETUP).expect("valid trusted setup");
+        Box::new(KzgSettings::load_trusted_setup(&g1, &g2).expect("valid trusted setup"))
+    })
+}
+
+/// EIP-4844: Shard Blob Transactions, point evaluation precompile.
+///
+/// Verifies that `p(z) = y` for the polynomial committed to by `commitment`, and that
+/// `versioned_hash` is derived from `commitment`.
+///
+/// See: https://eips.ethereum.org/EIPS/eip-4844#point-evaluation-precompile
+fn run(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    let cost = gas_query(GAS_COST, gas_limit)?;
+
+    if input.len() != INPUT_LEN {
+        return Err(Return::Other(Cow::Borrowed("ERR_KZG_INVALID_INPUT_LEN")));
+    }
+
+    let versioned_hash = &input[..32];
+    let commitment = &input[96..144];
+    if kzg_to_versioned_hash(commitment)[..] != versioned_hash[..] {
+        return Err(Return::Other(Cow::Borrowed("ERR_KZG_MISMATCHED_VERSION")));
+    }
+
+    let z = as_bytes32(&input[32..64]);
+    let y = as_bytes32(&input[64..96]);
+    let commitment = as_bytes48(commitment);
+    let proof = as_bytes48(&input[144..192]);
+    match KzgProof::verify_kzg_proof(&commitment, &z, &y, &proof, kzg_settings()) {
+        Ok(true) => Ok(PrecompileOutput::without_logs(cost, RETURN_VALUE.to_vec())),
+        _ => Err(Return::Other(Cow::Borrowed("ERR_KZG_VERIFICATION_FAILED"))),
+    }
+}
+
+/// `VERSIONED_HASH_VERSION_KZG ++ sha256(commitment)[1..]`
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> [u8; 32] {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+fn as_bytes32(bytes: &[u8]) -> Bytes32 {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(bytes);
+    out.into()
+}
+
+fn as_bytes48(bytes: &[u8]) -> Bytes48 {
+    let mut out = [0u8; 48];
+    out.copy_from_slice(bytes);
+    out.into()
+}
+
+/// Points of the trusted setup, G1 then G2.
+type TrustedSetup = (Vec<[u8; BYTES_PER_G1_POINT]>, Vec<[u8; BYTES_PER_G2_POINT]>);
+
+/// Parses the trusted setup text format: number of G1 points, number of G2 points,
+/// followed by one hex encoded point per line. Malformed counts, missing points and lines
+/// that are not exactly one hex encoded point are rejected.
+fn parse_trusted_setup(setup: &str) -> Result<TrustedSetup, &'static str> {
+    let mut lines = setup.lines().map(str::trim).filter(|line| !line.is_empty());
+    let mut count = || -> Result<usize, &'static str> {
+        lines
+            .next()
+            .and_then(|n| n.parse().ok())
+            .ok_or("invalid number of points")
+    };
+    let num_g1 = count()?;
+    let num_g2 = count()?;
+
+    let g1 = lines
+        .by_ref()
+        .take(num_g1)
+        .map(decode_hex)
+        .collect::<Result<Vec<_>, _>>()?;
+    let g2 = lines
+        .take(num_g2)
+        .map(decode_hex)
+        .collect::<Result<Vec<_>, _>>()?;
+    if g1.len() != num_g1 || g2.len() != num_g2 {
+        return Err("missing points");
+    }
+    Ok((g1, g2))
+}
+
+fn decode_hex<const N: usize>(line: &str) -> Result<[u8; N], &'static str> {
+    let nibble = |c: u8| match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err("invalid hex character"),
+    };
+    if line.len() != 2 * N {
+        return Err("invalid point length");
+    }
+    let mut out = [0u8; N];
+    for (byte, pair) in out.iter_mut().zip(line.as_bytes().chunks_exact(2)) {
+        *byte = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_test() {
+        // Test data from: https://github.com/ethereum/c-kzg-4844/blob/main/tests/verify_kzg_proof/kzg-mainnet/verify_kzg_proof_case_correct_proof_4_4/data.yaml
+        let commitment = hex::decode("8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7").unwrap();
+        let versioned_hash = kzg_to_versioned_hash(&commitment).to_vec();
+        let z = hex::decode("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000")
+            .unwrap();
+        let y = hex::decode("1522a4a7f34e1ea350ae07c29c96c7e79655aa926122e95fe69fcbd932ca49e9")
+            .unwrap();
+        let proof = hex::decode("a62ad71d14c5719385c0686f1871430475bf3a00f0aa3f7b8dd99a9abc2160744faf0070725e00b60ad9a026a15b1a8c").unwrap();
+
+        let input = [versioned_hash, z, y, commitment, proof].concat();
+
+        let expected_output = hex::decode("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001").unwrap();
+        let res = run(&input, u64::MAX).unwrap();
+        assert_eq!(res.cost, GAS_COST);
+        assert_eq!(res.output, expected_output);
+
+        assert_eq!(run(&input, GAS_COST - 1).unwrap_err(), Return::OutOfGas);
+
+        // wrong length
+        assert!(run(&input[1..], u64::MAX).is_err());
+        assert!(run(&[input.clone(), vec![0]].concat(), u64::MAX).is_err());
+
+        // mismatched versioned hash
+        let mut bad_hash = input.clone();
+        bad_hash[0] = 0x02;
+        assert!(run(&bad_hash, u64::MAX).is_err());
+
+        // wrong evaluation
+        let mut bad_y = input;
+        bad_y[95] ^= 1;
+        assert!(run(&bad_y, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn malformed_trusted_setup() {
+        let (g1, g2) = parse_trusted_setup(TRUSTED_SETUP).unwrap();
+        assert_eq!((g1.len(), g2.len()), (4096, 65));
+
+        let g1 = "aa".repeat(BYTES_PER_G1_POINT);
+        let g2 = "bb".repeat(BYTES_PER_G2_POINT);
+        let setup = |g1: &str| format!("1\n1\n{}\n{}\n", g1, g2);
+        let (parsed, _) = parse_trusted_setup(&setup(&g1)).unwrap();
+        assert_eq!(parsed, vec![[0xaa; BYTES_PER_G1_POINT]]);
+
+        // non hex character.
+        let invalid = format!("{}g{}", &g1[..10], &g1[11..]);
+        assert!(parse_trusted_setup(&setup(&invalid)).is_err());
+        // odd and short length.
+        assert!(parse_trusted_setup(&setup(&g1[1..])).is_err());
+        assert!(parse_trusted_setup(&setup(&g1[2..])).is_err());
+        // missing point and invalid count.
+        assert!(parse_trusted_setup(&format!("1\n2\n{}\n{}\n", g1, g2)).is_err());
+        assert!(parse_trusted_setup(&format!("x\n1\n{}\n{}\n", g1, g2)).is_err());
+    }
+}
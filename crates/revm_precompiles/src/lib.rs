@@ -4,14 +4,24 @@ use bytes::Bytes;
 use primitive_types::{H160 as Address, H256, U256};
 
 mod blake2;
+#[cfg(feature = "bls12_381")]
+mod bls12_381;
+#[cfg(feature = "bn128")]
 mod bn128;
 mod error;
 mod hash;
 mod identity;
+#[cfg(feature = "c-kzg")]
+mod kzg_point_evaluation;
 mod modexp;
 mod secp256k1;
+#[cfg(feature = "secp256r1")]
+mod secp256r1;
 
 pub use error::Return;
+pub use secp256k1::recover_address;
+#[cfg(feature = "secp256r1")]
+pub use secp256r1::{P256VERIFY, P256VERIFY_GAS};
 
 /// libraries for no_std flag
 #[macro_use]
@@ -59,9 +69,13 @@ pub type PrecompileResult = Result<PrecompileOutput, Return>;
 
 pub type StandardPrecompileFn = fn(&[u8], u64) -> PrecompileResult;
 pub type CustomPrecompileFn = fn(&[u8], u64) -> PrecompileResult;
+/// Gas cost of a precompile computed from its input alone. It is checked against the gas
+/// limit before the precompile runs, so calls that can't pay fail without doing the work.
+pub type PrecompileGasFn = fn(&[u8]) -> u64;
 
 pub struct Precompiles {
     fun: BTreeMap<Address, Precompile>,
+    gas: BTreeMap<Address, PrecompileGasFn>,
 }
 
 impl Default for Precompiles {
@@ -81,6 +95,8 @@ pub enum SpecId {
     BYZANTIUM = 1,
     ISTANBUL = 2,
     BERLIN = 3,
+    CANCUN = 4,
+    PRAGUE = 5,
 }
 
 impl SpecId {
@@ -92,42 +108,84 @@ impl SpecId {
 impl Precompiles {
     pub fn new<const SPEC_ID: u8>() -> Self {
         let mut fun: BTreeMap<Address, Precompile> = BTreeMap::new();
+        let mut gas: BTreeMap<Address, PrecompileGasFn> = BTreeMap::new();
         let mut insert_fun =
             |precompile: (Address, Precompile)| fun.insert(precompile.0, precompile.1);
+        let mut insert_gas = |gas_fn: (Address, PrecompileGasFn)| gas.insert(gas_fn.0, gas_fn.1);
 
         if SpecId::HOMESTEAD.enabled(SPEC_ID) {
             insert_fun(hash::SHA256);
             insert_fun(hash::RIPEMD160);
             insert_fun(secp256k1::ECRECOVER);
             insert_fun(identity::FUN);
+            insert_gas(hash::SHA256_GAS);
+            insert_gas(hash::RIPEMD160_GAS);
+            insert_gas(secp256k1::ECRECOVER_GAS);
+            insert_gas(identity::GAS);
         }
 
         if SpecId::ISTANBUL.enabled(SPEC_ID) {
             // EIP-152: Add BLAKE2 compression function `F` precompile
             insert_fun(blake2::FUN);
+            insert_gas(blake2::GAS);
         }
 
+        #[cfg(feature = "bn128")]
         if SpecId::ISTANBUL.enabled(SPEC_ID) {
             // EIP-1108: Reduce alt_bn128 precompile gas costs
             insert_fun(bn128::add::ISTANBUL);
             insert_fun(bn128::mul::ISTANBUL);
             insert_fun(bn128::pair::ISTANBUL);
+            insert_gas(bn128::add::ISTANBUL_GAS);
+            insert_gas(bn128::mul::ISTANBUL_GAS);
+            insert_gas(bn128::pair::ISTANBUL_GAS);
         } else if SpecId::BYZANTIUM.enabled(SPEC_ID) {
             // EIP-196: Precompiled contracts for addition and scalar multiplication on the elliptic curve alt_bn128
             // EIP-197: Precompiled contracts for optimal ate pairing check on the elliptic curve alt_bn128
             insert_fun(bn128::add::BYZANTIUM);
             insert_fun(bn128::mul::BYZANTIUM);
             insert_fun(bn128::pair::BYZANTIUM);
+            insert_gas(bn128::add::BYZANTIUM_GAS);
+            insert_gas(bn128::mul::BYZANTIUM_GAS);
+            insert_gas(bn128::pair::BYZANTIUM_GAS);
         }
 
         if SpecId::BERLIN.enabled(SPEC_ID) {
             insert_fun(modexp::BERLIN);
+            insert_gas(modexp::BERLIN_GAS);
         } else if SpecId::BYZANTIUM.enabled(SPEC_ID) {
             //EIP-198: Big integer modular exponentiation
             insert_fun(modexp::BYZANTIUM);
+            insert_gas(modexp::BYZANTIUM_GAS);
         }
 
-        Self { fun }
+        #[cfg(feature = "c-kzg")]
+        if SpecId::CANCUN.enabled(SPEC_ID) {
+            // EIP-4844: Shard Blob Transactions
+            insert_fun(kzg_point_evaluation::POINT_EVALUATION);
+            insert_gas(kzg_point_evaluation::POINT_EVALUATION_GAS);
+        }
+
+        #[cfg(feature = "bls12_381")]
+        if SpecId::PRAGUE.enabled(SPEC_ID) {
+            // EIP-2537: Precompile for BLS12-381 curve operations
+            insert_fun(bls12_381::g1_add::PRAGUE);
+            insert_fun(bls12_381::g1_msm::PRAGUE);
+            insert_fun(bls12_381::g2_add::PRAGUE);
+            insert_fun(bls12_381::g2_msm::PRAGUE);
+            insert_fun(bls12_381::pairing::PRAGUE);
+            insert_fun(bls12_381::map_fp_to_g1::PRAGUE);
+            insert_fun(bls12_381::map_fp2_to_g2::PRAGUE);
+            insert_gas(bls12_381::g1_add::PRAGUE_GAS);
+            insert_gas(bls12_381::g1_msm::PRAGUE_GAS);
+            insert_gas(bls12_381::g2_add::PRAGUE_GAS);
+            insert_gas(bls12_381::g2_msm::PRAGUE_GAS);
+            insert_gas(bls12_381::pairing::PRAGUE_GAS);
+            insert_gas(bls12_381::map_fp_to_g1::PRAGUE_GAS);
+            insert_gas(bls12_381::map_fp2_to_g2::PRAGUE_GAS);
+        }
+
+        Self { fun, gas }
     }
 
     pub fn addresses(&self) -> impl IntoIterator<Item = &Address> {
@@ -142,6 +200,43 @@ impl Precompiles {
         //return None;
         self.fun.get(address).cloned()
     }
+
+    /// Add or replace precompile at `address`. If `gas` is set, it is checked before the
+    /// precompile runs.
+    pub fn insert(
+        &mut self,
+        address: Address,
+        precompile: Precompile,
+        gas: Option<PrecompileGasFn>,
+    ) {
+        self.fun.insert(address, precompile);
+        match gas {
+            Some(gas) => self.gas.insert(address, gas),
+            None => self.gas.remove(&address),
+        };
+    }
+
+    /// Gas that precompile at `address` charges for `input`, if it is known up front.
+    pub fn gas_cost(&self, address: &Address, input: &[u8]) -> Option<u64> {
+        self.gas.get(address).map(|gas| gas(input))
+    }
+
+    /// Run precompile at `address`, `None` if there is no precompile there. Fails with
+    /// [`Return::OutOfGas`] without running the precompile if its up front cost is over
+    /// `gas_limit`.
+    pub fn run(&self, address: &Address, input: &[u8], gas_limit: u64) -> Option<PrecompileResult> {
+        let precompile = self.fun.get(address)?;
+        if self
+            .gas_cost(address, input)
+            .is_some_and(|cost| cost > gas_limit)
+        {
+            return Some(Err(Return::OutOfGas));
+        }
+        Some(match precompile {
+            Precompile::Standard(fun) => fun(input, gas_limit),
+            Precompile::Custom(fun) => fun(input, gas_limit),
+        })
+    }
 }
 
 /// Matches the address given to Homestead precompiles.
@@ -215,6 +310,83 @@ pub fn u256_to_arr(value: &U256) -> [u8; 32] {
     result
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "bn128")]
+    fn underfunded_precompile_is_not_run() {
+        let precompiles = Precompiles::new::<{ SpecId::BERLIN as u8 }>();
+        let pair = make_address(0, 8);
+        // two pairs of points that are not on the curve, pairing would fail on them.
+        let input = [0x11u8; 2 * 192];
+        assert_eq!(precompiles.gas_cost(&pair, &input), Some(113_000));
+        assert_eq!(
+            precompiles
+                .run(&pair, &input, 112_999)
+                .unwrap()
+                .unwrap_err(),
+            Return::OutOfGas
+        );
+        assert!(matches!(
+            precompiles.run(&pair, &input, 113_000).unwrap(),
+            Err(Return::Other(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "bls12_381")]
+    fn bls12_381_gas_is_checked_up_front() {
+        let precompiles = Precompiles::new::<{ SpecId::PRAGUE as u8 }>();
+        for address in 0x0b..=0x11 {
+            assert!(precompiles
+                .gas_cost(&make_address(0, address), &[])
+                .is_some());
+        }
+        // two G1 MSM pairs of points at infinity.
+        let msm = make_address(0, 0x0c);
+        let input = [0u8; 2 * 160];
+        assert_eq!(precompiles.gas_cost(&msm, &input), Some(22_776));
+        assert_eq!(
+            precompiles.run(&msm, &input, 22_775).unwrap().unwrap_err(),
+            Return::OutOfGas
+        );
+        assert!(precompiles.run(&msm, &input, 22_776).unwrap().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "c-kzg")]
+    fn point_evaluation_gas_is_checked_up_front() {
+        let precompiles = Precompiles::new::<{ SpecId::CANCUN as u8 }>();
+        let address = make_address(0, 0x0a);
+        assert_eq!(precompiles.gas_cost(&address, &[]), Some(50_000));
+        assert_eq!(
+            precompiles.run(&address, &[], 49_999).unwrap().unwrap_err(),
+            Return::OutOfGas
+        );
+    }
+
+    #[test]
+    fn underfunded_custom_precompile_is_not_run() {
+        let mut precompiles = Precompiles::new::<{ SpecId::BERLIN as u8 }>();
+        let address = make_address(0, 0x100);
+        precompiles.insert(
+            address,
+            Precompile::Custom(|_, _| panic!("precompile should not run")),
+            Some(|input| input.len() as u64 * 1_000),
+        );
+        assert_eq!(
+            precompiles
+                .run(&address, &[0; 10], 9_999)
+                .unwrap()
+                .unwrap_err(),
+            Return::OutOfGas
+        );
+        assert!(precompiles.run(&make_address(0, 0x101), &[], 0).is_none());
+    }
+}
+
 /*
 #[cfg(test)]
 mod tests {
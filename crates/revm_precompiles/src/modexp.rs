@@ -1,5 +1,7 @@
 use super::gas_query;
-use crate::{Precompile, PrecompileOutput, PrecompileResult, StandardPrecompileFn};
+use crate::{
+    Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult, StandardPrecompileFn,
+};
 use alloc::vec::Vec;
 use core::{
     cmp::{max, min, Ordering},
@@ -18,6 +20,15 @@ pub const BERLIN: (Address, Precompile) = (
     Precompile::Standard(berlin_run as StandardPrecompileFn),
 );
 
+pub const BYZANTIUM_GAS: (Address, PrecompileGasFn) =
+    (super::make_address(0, 5), |input: &[u8]| {
+        gas_cost(input, 0, byzantium_gas_calc)
+    });
+
+pub const BERLIN_GAS: (Address, PrecompileGasFn) = (super::make_address(0, 5), |input: &[u8]| {
+    gas_cost(input, 200, berlin_gas_calc)
+});
+
 /// See: https://eips.ethereum.org/EIPS/eip-198
 /// See: https://etherscan.io/address/0000000000000000000000000000000000000005
 fn byzantium_run(input: &[u8], gas_limit: u64) -> PrecompileResult {
@@ -63,6 +74,42 @@ macro_rules! read_u64_with_overflow {
     }};
 }
 
+/// Gas of the call, computed from the length header and the first 32 bytes of exponent.
+fn gas_cost<F>(input: &[u8], min_gas: u64, calc_gas: F) -> u64
+where
+    F: FnOnce(u64, u64, u64, &BigUint) -> u64,
+{
+    let (base_len, base_overflow) = read_u64_with_overflow!(input, 0, 32, u32::MAX as usize);
+    let (exp_len, exp_overflow) = read_u64_with_overflow!(input, 32, 64, u32::MAX as usize);
+    let (mod_len, mod_overflow) = read_u64_with_overflow!(input, 64, 96, u32::MAX as usize);
+
+    if base_overflow || mod_overflow {
+        return u64::MAX;
+    }
+    if base_len == 0 && mod_len == 0 {
+        return min_gas;
+    }
+    if exp_overflow {
+        return u64::MAX;
+    }
+    let exp_highp = read_exp_highp(input, base_len, exp_len);
+    calc_gas(base_len as u64, exp_len as u64, mod_len as u64, &exp_highp)
+}
+
+/// First 32 bytes of exponent, right padded with zeros if input is short.
+fn read_exp_highp(input: &[u8], base_len: usize, exp_len: usize) -> BigUint {
+    let len = input.len();
+    let base_end = 96 + base_len;
+    let exp_highp_end = base_end + min(32, exp_len);
+    let mut out = vec![0; 32];
+    let from = min(base_end, len);
+    let to = min(exp_highp_end, len);
+    let target_from = 32 - (exp_highp_end - base_end); // 32 - exp length
+    let target_to = target_from + (to - from); // beginning + size to copy
+    out[target_from..target_to].copy_from_slice(&input[from..to]);
+    BigUint::from_bytes_be(&out)
+}
+
 fn run_inner<F>(input: &[u8], gas_limit: u64, min_gas: u64, calc_gas: F) -> PrecompileResult
 where
     F: FnOnce(u64, u64, u64, &BigUint) -> u64,
@@ -86,18 +133,9 @@ where
         let base_start = 96;
         let base_end = base_start + base_len;
         let exp_end = base_end + exp_len;
-        let exp_highp_end = base_end + min(32, exp_len);
         let mod_end = exp_end + mod_len;
 
-        let exp_highp = {
-            let mut out = vec![0; 32];
-            let from = min(base_end, len);
-            let to = min(exp_highp_end, len);
-            let target_from = 32 - (exp_highp_end - base_end); // 32 - exp length
-            let target_to = target_from + (to - from); // beginning + size to copy
-            out[target_from..target_to].copy_from_slice(&input[from..to]);
-            BigUint::from_bytes_be(&out)
-        };
+        let exp_highp = read_exp_highp(input, base_len, exp_len);
 
         let gas_cost = gas_query(
             calc_gas(base_len as u64, exp_len as u64, mod_len as u64, &exp_highp),
@@ -400,6 +438,93 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_modexp_gas_up_front() {
+        for test in TESTS.iter() {
+            let input = hex::decode(test.input).unwrap();
+            assert_eq!(
+                gas_cost(&input, 0, byzantium_gas_calc),
+                byzantium_run(&input, 100_000_000).unwrap().cost,
+                "test:{}",
+                test.name
+            );
+            assert_eq!(
+                gas_cost(&input, 200, berlin_gas_calc),
+                berlin_run(&input, 100_000_000).unwrap().cost,
+                "test:{}",
+                test.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_modexp_edge_cases() {
+        // zero length modulus returns nothing.
+        let input = hex::decode(
+            "\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000000\
+            0305",
+        )
+        .unwrap();
+        let res = berlin_run(&input, 100_000).unwrap();
+        assert_eq!(res.cost, 200);
+        assert!(res.output.is_empty());
+
+        // modulus of zero returns zeros of modulus length.
+        let input = hex::decode(
+            "\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            03050000",
+        )
+        .unwrap();
+        assert_eq!(berlin_run(&input, 100_000).unwrap().output, vec![0, 0]);
+
+        // leading zeros of exponent count in its bit length, 2^3 % 5 with 64 byte exponent
+        // and 32 byte modulus: 4 words squared times 8 * (64 - 32) iterations, over 3.
+        let mut input = hex::decode(
+            "\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000040\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            02",
+        )
+        .unwrap();
+        input.extend_from_slice(&[0; 63]);
+        input.push(3);
+        input.extend_from_slice(&[0; 31]);
+        input.push(5);
+        let res = berlin_run(&input, 100_000).unwrap();
+        assert_eq!(res.cost, 16 * 256 / 3);
+        let mut expected = vec![0; 31];
+        expected.push(3);
+        assert_eq!(res.output, expected);
+
+        // exponent length over u32 costs all gas, unless base and modulus are empty.
+        let input = hex::decode(
+            "\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000100000000\
+            0000000000000000000000000000000000000000000000000000000000000001",
+        )
+        .unwrap();
+        assert_eq!(gas_cost(&input, 200, berlin_gas_calc), u64::MAX);
+        assert_eq!(berlin_run(&input, 100_000).unwrap().cost, u64::MAX);
+        let input = hex::decode(
+            "\
+            0000000000000000000000000000000000000000000000000000000000000000\
+            0000000000000000000000000000000000000000000000000000000100000000\
+            0000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        let res = berlin_run(&input, 100_000).unwrap();
+        assert_eq!(res.cost, 200);
+        assert!(res.output.is_empty());
+    }
+
     #[test]
     fn test_berlin_modexp_empty_input() {
         let res = berlin_run(&[], 100_000).unwrap();
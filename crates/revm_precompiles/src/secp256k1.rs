@@ -1,4 +1,7 @@
-use crate::{gas_query, Precompile, PrecompileOutput, PrecompileResult, StandardPrecompileFn};
+use crate::{
+    gas_query, Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult,
+    StandardPrecompileFn,
+};
 use alloc::vec::Vec;
 use core::cmp::min;
 
@@ -11,6 +14,9 @@ pub const ECRECOVER: (Address, Precompile) = (
     Precompile::Standard(ec_recover_run as StandardPrecompileFn),
 );
 
+pub const ECRECOVER_GAS: (Address, PrecompileGasFn) =
+    (super::make_address(0, 1), |_: &[u8]| ECRECOVER_BASE);
+
 #[cfg(feature = "k256_ecrecover")]
 #[allow(clippy::module_inception)]
 mod secp256k1 {
@@ -59,6 +65,16 @@ mod secp256k1 {
     }
 }
 
+/// Recover signer of `msg` from 65 byte signature `r || s || recovery_id`.
+pub fn recover_address(sig: &[u8; 65], msg: &[u8; 32]) -> Option<Address> {
+    secp256k1::ecrecover(sig, msg).ok()
+}
+
+/// Input is `hash || v || r || s`, right padded with zeros to 128 bytes. `v` has to be 27 or 28,
+/// high `s` values are accepted. Invalid signatures return empty output, not the zero address.
+///
+/// See: https://ethereum.github.io/yellowpaper/paper.pdf
+/// See: https://etherscan.io/address/0000000000000000000000000000000000000001
 fn ec_recover_run(i: &[u8], target_gas: u64) -> PrecompileResult {
     let cost = gas_query(ECRECOVER_BASE, target_gas)?;
     let mut input = [0u8; 128];
@@ -84,3 +100,79 @@ fn ec_recover_run(i: &[u8], target_gas: u64) -> PrecompileResult {
 
     Ok(PrecompileOutput::without_logs(cost, out))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Return;
+    use primitive_types::U256;
+
+    /// Signature of the well known test account `0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b`.
+    const VALID: &str = "\
+        18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c\
+        000000000000000000000000000000000000000000000000000000000000001c\
+        73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f\
+        eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549";
+    const SIGNER: &str = "000000000000000000000000a94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+
+    /// Order of the secp256k1 curve.
+    const N: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+    fn run(input: &[u8]) -> Vec<u8> {
+        ec_recover_run(input, ECRECOVER_BASE).unwrap().output
+    }
+
+    #[test]
+    fn recovers_signer() {
+        let input = hex::decode(VALID).unwrap();
+        assert_eq!(run(&input), hex::decode(SIGNER).unwrap());
+
+        // bytes after 128 are ignored.
+        let mut long = input.clone();
+        long.extend_from_slice(&[0xff; 32]);
+        assert_eq!(run(&long), hex::decode(SIGNER).unwrap());
+
+        assert_eq!(
+            ec_recover_run(&input, ECRECOVER_BASE - 1).unwrap_err(),
+            Return::OutOfGas
+        );
+    }
+
+    #[test]
+    fn high_s_is_accepted() {
+        let mut input = hex::decode(VALID).unwrap();
+        let n = U256::from_big_endian(&hex::decode(N).unwrap());
+        let s = U256::from_big_endian(&input[96..128]);
+        (n - s).to_big_endian(&mut input[96..128]);
+        // negated `s` recovers the same key with the other parity.
+        input[63] = 27;
+        assert_eq!(run(&input), hex::decode(SIGNER).unwrap());
+    }
+
+    #[test]
+    fn invalid_input_returns_empty() {
+        let valid = hex::decode(VALID).unwrap();
+
+        // v out of range or with non zero high bytes.
+        for v in [0u8, 1, 26, 29] {
+            let mut input = valid.clone();
+            input[63] = v;
+            assert!(run(&input).is_empty(), "v: {}", v);
+        }
+        let mut input = valid.clone();
+        input[32] = 1;
+        assert!(run(&input).is_empty());
+
+        // r of zero, s of curve order.
+        let mut input = valid.clone();
+        input[64..96].copy_from_slice(&[0; 32]);
+        assert!(run(&input).is_empty());
+        let mut input = valid.clone();
+        input[96..128].copy_from_slice(&hex::decode(N).unwrap());
+        assert!(run(&input).is_empty());
+
+        // short input is padded with zeros, zero `s` is invalid.
+        assert!(run(&valid[..96]).is_empty());
+        assert!(run(&[]).is_empty());
+    }
+}
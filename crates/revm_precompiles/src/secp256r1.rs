@@ -0,0 +1,133 @@
+use crate::{
+    gas_query, Precompile, PrecompileGasFn, PrecompileOutput, PrecompileResult,
+    StandardPrecompileFn,
+};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use p256::{
+    ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey},
+    EncodedPoint,
+};
+use primitive_types::H160 as Address;
+
+/// RIP-7212: Precompile for secp256r1 curve support. Not part of any Ethereum hardfork,
+/// insert it with [`crate::Precompiles::insert`] on chains that adopted it.
+pub const P256VERIFY: (Address, Precompile) = (
+    super::make_address(0, 0x100),
+    Precompile::Standard(p256_verify_run as StandardPrecompileFn),
+);
+
+pub const P256VERIFY_GAS: (Address, PrecompileGasFn) =
+    (super::make_address(0, 0x100), |_| P256VERIFY_BASE);
+
+/// Fixed cost of signature verification.
+pub const P256VERIFY_BASE: u64 = 3_450;
+
+/// Input is `hash | r | s | qx | qy`.
+const INPUT_LEN: usize = 160;
+
+/// Returns 32 byte word `1` if signature is valid, empty output otherwise. Invalid input,
+/// `r` or `s` out of range and public keys that are not on the curve all fail verification
+/// and are charged in full.
+fn p256_verify_run(input: &[u8], gas_limit: u64) -> PrecompileResult {
+    let gas_used = gas_query(P256VERIFY_BASE, gas_limit)?;
+    let output = if verify(input) {
+        let mut word = vec![0u8; 32];
+        word[31] = 1;
+        word
+    } else {
+        Vec::new()
+    };
+    Ok(PrecompileOutput::without_logs(gas_used, output))
+}
+
+fn verify(input: &[u8]) -> bool {
+    if input.len() != INPUT_LEN {
+        return false;
+    }
+    let hash = &input[..32];
+    // zero `r` or `s` and values not below the curve order are rejected.
+    let signature = match Signature::try_from(&input[32..96]) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    let point =
+        EncodedPoint::from_affine_coordinates(input[96..128].into(), input[128..160].into(), false);
+    // the point at infinity and points not on the curve are rejected.
+    let key = match VerifyingKey::from_encoded_point(&point) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    key.verify_prehash(hash, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Return;
+
+    const VALID: &str = "4cee90eb86eaa050036147a12d49004b6b9c72bd725d39d4785011fe190f0b4da73bd4903f0ce3b639bbbf6e8e80d16931ff4bcf5993d58468e8fb19086e8cac36dbcd03009df8c59286b162af3bd7fcc0450c9aa81be5d10d312af6c66b1d604aebd3099c618202fcfe16ae7770b0c49ab5eadf74b754204a3bb6060e44eff37618b065f9832de4ca6ca971a7a1adc826d0f7c00181a5fb2ddf79ae00b4e10e";
+
+    fn run(input: &[u8]) -> Vec<u8> {
+        let output = p256_verify_run(input, 3_450).unwrap();
+        assert_eq!(output.cost, 3_450);
+        output.output
+    }
+
+    fn success() -> Vec<u8> {
+        let mut word = vec![0u8; 32];
+        word[31] = 1;
+        word
+    }
+
+    #[test]
+    fn test_vectors() {
+        let input = hex::decode(VALID).unwrap();
+        assert_eq!(run(&input), success());
+
+        // changed hash
+        let mut changed = input.clone();
+        changed[0] ^= 1;
+        assert!(run(&changed).is_empty());
+        // wrong length
+        assert!(run(&input[..159]).is_empty());
+        assert!(run(&[input.as_slice(), &[0]].concat()).is_empty());
+        assert!(run(&[]).is_empty());
+    }
+
+    #[test]
+    fn out_of_range_signature() {
+        let input = hex::decode(VALID).unwrap();
+        // curve order n
+        let order = hex::decode("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551")
+            .unwrap();
+        for range in [32..64, 64..96] {
+            let mut zero = input.clone();
+            zero[range.clone()].fill(0);
+            assert!(run(&zero).is_empty());
+
+            let mut at_order = input.clone();
+            at_order[range].copy_from_slice(&order);
+            assert!(run(&at_order).is_empty());
+        }
+    }
+
+    #[test]
+    fn point_not_on_curve() {
+        let mut input = hex::decode(VALID).unwrap();
+        input[159] ^= 1;
+        assert!(run(&input).is_empty());
+        // point at infinity
+        input[96..].fill(0);
+        assert!(run(&input).is_empty());
+    }
+
+    #[test]
+    fn out_of_gas() {
+        let input = hex::decode(VALID).unwrap();
+        assert_eq!(
+            p256_verify_run(&input, 3_449).unwrap_err(),
+            Return::OutOfGas
+        );
+    }
+}